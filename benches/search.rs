@@ -0,0 +1,213 @@
+//! Search benchmarks.
+//!
+//! Run with `cargo bench`. Each benchmark prints
+//! the average time per search for each configuration.
+
+use petgraph::graph::{NodeIndex, UnGraph};
+use std::cell::Cell;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use vf2::{CsrGraph, Direction, Graph};
+
+fn main() {
+    dense_adjacency();
+    csr_graph();
+    label_index();
+    precompute_compat();
+    #[cfg(feature = "approx")]
+    feasibility_sets();
+    match_many();
+}
+
+/// Compares checking edges in the data graph with and without
+/// a dense adjacency matrix, across data graph sizes and degrees.
+///
+/// Counting every isomorphism amortizes building the matrix,
+/// while finding the first one may not.
+fn dense_adjacency() {
+    let query = cycle(4);
+    for len in [30, 100, 300, 1000] {
+        for degree in [4, 8, 16] {
+            let data = random(len, degree);
+            report(&format!("dense_adjacency/count/{len}x{degree}/off"), || {
+                vf2::subgraph_isomorphisms(&query, &data).count()
+            });
+            report(&format!("dense_adjacency/count/{len}x{degree}/on"), || {
+                vf2::subgraph_isomorphisms(&query, &data)
+                    .dense_adjacency()
+                    .count()
+            });
+        }
+    }
+    for len in [100, 300, 1000, 3000, 10000] {
+        let data = random(len, 4);
+        report(&format!("dense_adjacency/first/{len}x4/off"), || {
+            vf2::subgraph_isomorphisms(&query, &data).first().is_some() as usize
+        });
+        report(&format!("dense_adjacency/first/{len}x4/on"), || {
+            vf2::subgraph_isomorphisms(&query, &data)
+                .dense_adjacency()
+                .first()
+                .is_some() as usize
+        });
+    }
+}
+
+/// Compares a petgraph data graph with the same graph in CSR format.
+fn csr_graph() {
+    let query = cycle(4);
+    let data = random(300, 8);
+    let csr_query = CsrGraph::from(&query);
+    let csr_data = CsrGraph::from(&data);
+    report("csr_graph/neighbors/petgraph", || {
+        (0..data.node_count())
+            .map(|node| Graph::neighbors(&data, node, Direction::Outgoing).sum::<usize>())
+            .sum()
+    });
+    report("csr_graph/neighbors/csr", || {
+        (0..csr_data.node_count())
+            .map(|node| csr_data.neighbors(node, Direction::Outgoing).sum::<usize>())
+            .sum()
+    });
+    report("csr_graph/search/petgraph", || {
+        vf2::subgraph_isomorphisms(&query, &data).count()
+    });
+    report("csr_graph/search/csr", || {
+        vf2::subgraph_isomorphisms(&csr_query, &csr_data).count()
+    });
+}
+
+/// Compares searching labeled graphs with and without a label index,
+/// and prints the number of pairs the node equality function is called on.
+fn label_index() {
+    let query = labeled(&cycle(4), 30);
+    let data = labeled(&random(300, 4), 30);
+    for (name, indexed) in [("off", false), ("on", true)] {
+        let candidates = Cell::new(0);
+        let mut builder = vf2::subgraph_isomorphisms(&query, &data).node_eq(|left, right| {
+            candidates.set(candidates.get() + 1);
+            left == right
+        });
+        if indexed {
+            builder = builder.label_index();
+        }
+        builder.clone().count();
+        println!("label_index/{name}: {} candidates", candidates.get());
+        report(&format!("label_index/{name}"), || builder.clone().count());
+    }
+}
+
+/// Compares searching labeled graphs with and without
+/// a precomputed node compatibility matrix, and prints
+/// the number of times the node equality function is called.
+fn precompute_compat() {
+    let query = labeled(&cycle(5), 2);
+    let data = labeled(&random(60, 8), 2);
+    for (name, precomputed) in [("off", false), ("on", true)] {
+        let calls = Cell::new(0);
+        let mut builder = vf2::subgraph_isomorphisms(&query, &data).node_eq(|left, right| {
+            calls.set(calls.get() + 1);
+            left == right
+        });
+        if precomputed {
+            builder = builder.precompute_node_compat();
+        }
+        builder.clone().count();
+        println!("precompute_compat/{name}: {} calls", calls.get());
+        report(&format!("precompute_compat/{name}"), || {
+            builder.clone().count()
+        });
+    }
+}
+
+/// Compares searching labeled graphs with a label index and with
+/// feasibility sets, and prints the number of pairs tried.
+///
+/// Run with `cargo bench --features approx`.
+#[cfg(feature = "approx")]
+fn feasibility_sets() {
+    use std::cell::Cell;
+    let query = labeled(&cycle(4), 30);
+    let data = labeled(&random(300, 4), 30);
+    for (name, feasibility_sets) in [("label_index", false), ("feasibility_sets", true)] {
+        let tried = Cell::new(0);
+        let builder = vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .node_eq_abort(|_, _| {
+                tried.set(tried.get() + 1);
+                vf2::NodeMatch::Match
+            });
+        let builder = if feasibility_sets {
+            builder.feasibility_sets()
+        } else {
+            builder.label_index()
+        };
+        builder.clone().count();
+        println!("feasibility_sets/{name}: {} pairs tried", tried.get());
+        report(&format!("feasibility_sets/{name}"), || {
+            builder.clone().count()
+        });
+    }
+}
+
+/// Compares matching many small queries against one data graph
+/// in a loop with matching them in one batch.
+fn match_many() {
+    let queries: Vec<_> = (0..50).map(|_| cycle(3)).collect();
+    let queries: Vec<_> = queries.iter().collect();
+    let data = random(1000, 3);
+    report("match_many/loop", || {
+        queries
+            .iter()
+            .map(|query| vf2::subgraph_isomorphisms(*query, &data).vec().len())
+            .sum()
+    });
+    report("match_many/batch", || {
+        vf2::match_many(&queries, &data).iter().map(Vec::len).sum()
+    });
+}
+
+/// Runs `search` repeatedly and prints the average time per run.
+fn report(name: &str, mut search: impl FnMut() -> usize) {
+    let mut runs = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) || runs < 3 {
+        black_box(search());
+        runs += 1;
+    }
+    println!("{name}: {:?} per run", start.elapsed() / runs);
+}
+
+/// Returns an undirected cycle with `len` nodes.
+fn cycle(len: usize) -> UnGraph<(), ()> {
+    UnGraph::from_edges((0..len).map(|node| (node as u32, ((node + 1) % len) as u32)))
+}
+
+/// Returns a copy of `graph` where node `i` is labeled `i % classes`.
+fn labeled(graph: &UnGraph<(), ()>, classes: usize) -> UnGraph<usize, ()> {
+    graph.map(|node, _| node.index() % classes, |_, _| ())
+}
+
+/// Returns an undirected graph with `len` nodes where each node is
+/// connected to about `degree` others chosen by a fixed pseudorandom sequence.
+fn random(len: usize, degree: usize) -> UnGraph<(), ()> {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let mut graph = UnGraph::with_capacity(len, len * degree / 2);
+    for _ in 0..len {
+        graph.add_node(());
+    }
+    for _ in 0..len * degree / 2 {
+        let source = next() as usize % len;
+        let target = next() as usize % len;
+        if source != target {
+            graph.update_edge(NodeIndex::new(source), NodeIndex::new(target), ());
+        }
+    }
+    graph
+}
@@ -0,0 +1,90 @@
+//! Matches against a data graph whose labels live in a key-value store.
+//!
+//! The store keeps labels in its own format, so it returns them owned
+//! through [`LazyGraph`] instead of by reference through [`vf2::Graph`].
+//! Run with `cargo run --example lazy_store`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use vf2::{CsrGraph, Direction, LazyGraph, LazyLabels, NodeIndex};
+
+/// A stand-in for a database of nodes, edges, and labels.
+///
+/// Node labels are stored as strings and parsed when fetched.
+/// Edge labels are stored as they are, so they are borrowed.
+struct Store {
+    node_labels: HashMap<NodeIndex, String>,
+    edges: HashMap<NodeIndex, Vec<(NodeIndex, char)>>,
+    node_count: usize,
+}
+
+impl Store {
+    /// Returns the outgoing edges of `node` and their labels.
+    fn edges(&self, node: NodeIndex) -> &[(NodeIndex, char)] {
+        self.edges.get(&node).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl LazyGraph for Store {
+    type NodeLabel = u32;
+    type EdgeLabel = char;
+
+    fn is_directed(&self) -> bool {
+        true
+    }
+
+    fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    fn node_label(&self, node: NodeIndex) -> Option<Cow<'_, u32>> {
+        let label = self.node_labels.get(&node)?.parse().ok()?;
+        Some(Cow::Owned(label))
+    }
+
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        let neighbors: Vec<NodeIndex> = match direction {
+            Direction::Outgoing => self.edges(node).iter().map(|&(target, _)| target).collect(),
+            Direction::Incoming => (0..self.node_count)
+                .filter(|&source| self.contains_edge(source, node))
+                .collect(),
+        };
+        neighbors.into_iter()
+    }
+
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.edges(source).iter().any(|&(node, _)| node == target)
+    }
+
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<Cow<'_, char>> {
+        self.edges(source)
+            .iter()
+            .find(|&&(node, _)| node == target)
+            .map(|(_, label)| Cow::Borrowed(label))
+    }
+}
+
+fn main() {
+    let store = Store {
+        node_labels: HashMap::from([
+            (0, "1".to_string()),
+            (1, "2".to_string()),
+            (2, "1".to_string()),
+            (3, "2".to_string()),
+        ]),
+        edges: HashMap::from([
+            (0, vec![(1, 'a')]),
+            (2, vec![(3, 'b')]),
+            (3, vec![(0, 'a')]),
+        ]),
+        node_count: 4,
+    };
+    let data = LazyLabels::new(store);
+
+    // Find edges labeled 'a' from a node labeled 1 to a node labeled 2.
+    let query = CsrGraph::new(true, vec![1, 2], [(0, 1, 'a')]);
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+
+    assert_eq!(isomorphisms, vec![vec![0, 1]]);
+    println!("{isomorphisms:?}");
+}
@@ -0,0 +1,20 @@
+//! Matches graphs from a `no_std` crate.
+//!
+//! This only needs to compile. Check that the crate works without `std` with
+//! `cargo build --example no_std --no-default-features`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use vf2::CsrGraph;
+
+/// Returns the subgraph isomorphisms from
+/// a directed path with two edges to a directed triangle.
+pub fn triangle_paths() -> Vec<vf2::Isomorphism> {
+    let query = CsrGraph::new(true, vec![(); 3], [(0, 1, ()), (1, 2, ())]);
+    let data = CsrGraph::new(true, vec![(); 3], [(0, 1, ()), (1, 2, ()), (2, 0, ())]);
+    vf2::subgraph_isomorphisms(&query, &data).vec()
+}
@@ -1,9 +1,34 @@
-use crate::{Graph, Isomorphism, IsomorphismIter};
-use std::fmt::Debug;
+use crate::config::{Callbacks, Config, Predicate, Root};
+use crate::graph_ref::GraphRef;
+use crate::label_index::LabelIndex;
+use crate::state::{fits_reserved_values, GraphCache};
+use crate::union_find::UnionFind;
+use crate::{
+    Direction, EdgeCtx, EdgeDensityIter, EdgeIsomorphismIter, Graph, IntervalLabel, Isomorphism,
+    IsomorphismIter, NodeFiltered, NodeIndex, RestrictedQuery, SearchIncomplete, StateSnapshot,
+    Vf2Error,
+};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeSet, BinaryHeap};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::mpsc::Sender;
 
 /// Creates a new [`Vf2Builder`] to find
 /// isomorphisms from `query` to `data`.
 ///
+/// No isomorphisms are found if the graphs have
+/// different numbers of nodes.
+///
 /// Node and edge equality are not checked by default.
 /// Use [`node_eq`], [`edge_eq`], and [`default_eq`]
 /// on the builder to set equality functions.
@@ -65,31 +90,95 @@ where
 }
 
 /// A VF2 builder used to configure the algorithm.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct Vf2Builder<'a, Query, Data, NodeEq, EdgeEq> {
+///
+/// The builder is [`Clone`] if its equality functions and callbacks are.
+/// It is not [`Copy`], [`PartialEq`], or [`Eq`], since its options
+/// own allocations and user functions.
+#[derive(Clone, Debug)]
+pub struct Vf2Builder<
+    'a,
+    Query,
+    Data,
+    NodeEq,
+    EdgeEq,
+    OnPartial = DefaultOnPartial,
+    OnProgress = DefaultOnProgress,
+> where
+    Query: Graph,
+    Data: Graph,
+{
     /// Problem type.
     problem: Problem,
     /// Query graph.
-    query: &'a Query,
+    query: GraphRef<'a, Query>,
     /// Data graph.
-    data: &'a Data,
+    data: GraphRef<'a, Data>,
     /// Node equality function.
     node_eq: Option<NodeEq>,
     /// Edge equality function.
     edge_eq: Option<EdgeEq>,
+    /// User callbacks.
+    callbacks: Callbacks<OnPartial, OnProgress>,
+    /// Search options.
+    config: Config<'a, Query, Data>,
 }
 
 /// Default VF2 builder type.
 ///
 /// This is [`Vf2Builder`] with function pointers as
 /// the node and edge equality function types.
-pub type DefaultVf2Builder<'a, Query, Data> = Vf2Builder<
-    'a,
-    Query,
-    Data,
-    fn(&<Query as Graph>::NodeLabel, &<Data as Graph>::NodeLabel) -> bool,
-    fn(&<Query as Graph>::EdgeLabel, &<Data as Graph>::EdgeLabel) -> bool,
->;
+pub type DefaultVf2Builder<'a, Query, Data> =
+    Vf2Builder<'a, Query, Data, DefaultNodeEq<Query, Data>, DefaultEdgeEq<Query, Data>>;
+
+/// Boxed node equality function type.
+///
+/// Use this with [`Vf2Builder::node_eq_dyn`] to choose
+/// the node equality function at runtime.
+pub type DynNodeEq<'a, Query, Data> =
+    Box<dyn Fn(&<Query as Graph>::NodeLabel, &<Data as Graph>::NodeLabel) -> bool + 'a>;
+
+/// Boxed edge equality function type.
+///
+/// Use this with [`Vf2Builder::edge_eq_dyn`] to choose
+/// the edge equality function at runtime.
+pub type DynEdgeEq<'a, Query, Data> =
+    Box<dyn Fn(&<Query as Graph>::EdgeLabel, &<Data as Graph>::EdgeLabel) -> bool + 'a>;
+
+/// Default node equality function type.
+///
+/// This is the node equality function type set by
+/// [`Vf2Builder::default_eq`] and [`Vf2Builder::nodes_only_eq`].
+pub type DefaultNodeEq<Query, Data> =
+    fn(&<Query as Graph>::NodeLabel, &<Data as Graph>::NodeLabel) -> bool;
+
+/// Default edge equality function type.
+///
+/// This is the edge equality function type set by
+/// [`Vf2Builder::default_eq`] and [`Vf2Builder::edges_only_eq`].
+pub type DefaultEdgeEq<Query, Data> =
+    fn(&<Query as Graph>::EdgeLabel, &<Data as Graph>::EdgeLabel) -> bool;
+
+/// Default partial map callback type.
+///
+/// This is the callback type of a builder without
+/// [`Vf2Builder::on_partial`], which is never called.
+pub type DefaultOnPartial = fn(&[NodeIndex], usize);
+
+/// Default progress callback type.
+///
+/// This is the callback type of a builder without
+/// [`Vf2Builder::on_progress`], which is never called.
+pub type DefaultOnProgress = fn(f64);
+
+/// Builder type returned by [`Vf2Builder::restrict_query`].
+type RestrictedVf2Builder<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> =
+    Vf2Builder<'a, RestrictedQuery<'a, Query>, Data, NodeEq, EdgeEq, OnPartial, OnProgress>;
+
+/// Interval containment function type.
+///
+/// This is the node equality function type set by
+/// [`Vf2Builder::node_eq_contains`].
+pub type IntervalEq<T> = fn(&IntervalLabel<T>, &IntervalLabel<T>) -> bool;
 
 impl<'a, Query, Data> DefaultVf2Builder<'a, Query, Data>
 where
@@ -99,26 +188,57 @@ where
     /// Creates a new [`Vf2Builder`] that does not check
     /// node and edge equality.
     fn new(problem: Problem, query: &'a Query, data: &'a Data) -> Self {
+        Self::with_graphs(problem, GraphRef::Borrowed(query), GraphRef::Borrowed(data))
+    }
+
+    /// Creates a new [`Vf2Builder`] that shares ownership of
+    /// the query and data graphs and does not check node and edge equality.
+    ///
+    /// Unlike [`isomorphisms`] and the other constructors, this does not
+    /// borrow the graphs, so the builder and its iterator can outlive
+    /// the scope that created the graphs and can be `'static`.
+    pub fn owned(problem: Problem, query: Arc<Query>, data: Arc<Data>) -> Self {
+        Self::with_graphs(problem, GraphRef::Shared(query), GraphRef::Shared(data))
+    }
+
+    /// Creates a new [`Vf2Builder`] with the given graphs
+    /// that does not check node and edge equality.
+    fn with_graphs(problem: Problem, query: GraphRef<'a, Query>, data: GraphRef<'a, Data>) -> Self {
         Self {
             problem,
             query,
             data,
             node_eq: None,
             edge_eq: None,
+            callbacks: Callbacks::default(),
+            config: Config::default(),
         }
     }
 }
 
-impl<'a, Query, Data, NodeEq, EdgeEq> Vf2Builder<'a, Query, Data, NodeEq, EdgeEq>
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+    Vf2Builder<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
 where
     Query: Graph,
     Data: Graph,
     NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
     EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
 {
     /// Configures VF2 to use the [`PartialEq`] implementations
     /// for node and edge equalities.
-    pub fn default_eq(self) -> DefaultVf2Builder<'a, Query, Data>
+    pub fn default_eq(
+        self,
+    ) -> Vf2Builder<
+        'a,
+        Query,
+        Data,
+        DefaultNodeEq<Query, Data>,
+        DefaultEdgeEq<Query, Data>,
+        OnPartial,
+        OnProgress,
+    >
     where
         Query::NodeLabel: PartialEq<Data::NodeLabel>,
         Query::EdgeLabel: PartialEq<Data::EdgeLabel>,
@@ -129,6 +249,52 @@ where
             data: self.data,
             node_eq: Some(<Query::NodeLabel as PartialEq<Data::NodeLabel>>::eq),
             edge_eq: Some(<Query::EdgeLabel as PartialEq<Data::EdgeLabel>>::eq),
+            callbacks: self.callbacks,
+            config: self.config,
+        }
+    }
+
+    /// Configures VF2 to use the [`PartialEq`] implementation
+    /// for node equality and not to compare edge labels.
+    ///
+    /// Unlike [`default_eq`](Self::default_eq), this only compares
+    /// node labels. Removes any edge equality function set before.
+    pub fn nodes_only_eq(
+        self,
+    ) -> Vf2Builder<'a, Query, Data, DefaultNodeEq<Query, Data>, EdgeEq, OnPartial, OnProgress>
+    where
+        Query::NodeLabel: PartialEq<Data::NodeLabel>,
+    {
+        Vf2Builder {
+            problem: self.problem,
+            query: self.query,
+            data: self.data,
+            node_eq: Some(<Query::NodeLabel as PartialEq<Data::NodeLabel>>::eq),
+            edge_eq: None,
+            callbacks: self.callbacks,
+            config: self.config,
+        }
+    }
+
+    /// Configures VF2 to use the [`PartialEq`] implementation
+    /// for edge equality and not to compare node labels.
+    ///
+    /// Unlike [`default_eq`](Self::default_eq), this only compares
+    /// edge labels. Removes any node equality function set before.
+    pub fn edges_only_eq(
+        self,
+    ) -> Vf2Builder<'a, Query, Data, NodeEq, DefaultEdgeEq<Query, Data>, OnPartial, OnProgress>
+    where
+        Query::EdgeLabel: PartialEq<Data::EdgeLabel>,
+    {
+        Vf2Builder {
+            problem: self.problem,
+            query: self.query,
+            data: self.data,
+            node_eq: None,
+            edge_eq: Some(<Query::EdgeLabel as PartialEq<Data::EdgeLabel>>::eq),
+            callbacks: self.callbacks,
+            config: self.config,
         }
     }
 
@@ -136,7 +302,7 @@ where
     pub fn node_eq<NewNodeEq>(
         self,
         node_eq: NewNodeEq,
-    ) -> Vf2Builder<'a, Query, Data, NewNodeEq, EdgeEq>
+    ) -> Vf2Builder<'a, Query, Data, NewNodeEq, EdgeEq, OnPartial, OnProgress>
     where
         NewNodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
     {
@@ -146,6 +312,8 @@ where
             data: self.data,
             node_eq: Some(node_eq),
             edge_eq: self.edge_eq,
+            callbacks: self.callbacks,
+            config: self.config,
         }
     }
 
@@ -153,7 +321,7 @@ where
     pub fn edge_eq<NewEdgeEq>(
         self,
         edge_eq: NewEdgeEq,
-    ) -> Vf2Builder<'a, Query, Data, NodeEq, NewEdgeEq>
+    ) -> Vf2Builder<'a, Query, Data, NodeEq, NewEdgeEq, OnPartial, OnProgress>
     where
         NewEdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
     {
@@ -163,7 +331,806 @@ where
             data: self.data,
             node_eq: self.node_eq,
             edge_eq: Some(edge_eq),
+            callbacks: self.callbacks,
+            config: self.config,
+        }
+    }
+
+    /// Configures VF2 to match a query node to a data node
+    /// if the query interval contains the data interval.
+    ///
+    /// This sets the node equality function to [`IntervalLabel::contains`].
+    pub fn node_eq_contains<T>(
+        self,
+    ) -> Vf2Builder<'a, Query, Data, IntervalEq<T>, EdgeEq, OnPartial, OnProgress>
+    where
+        Query: Graph<NodeLabel = IntervalLabel<T>>,
+        Data: Graph<NodeLabel = IntervalLabel<T>>,
+        T: PartialOrd,
+    {
+        self.node_eq(IntervalLabel::contains)
+    }
+
+    /// Configures VF2 to use the boxed `node_eq` as the node equality function.
+    ///
+    /// Unlike [`node_eq`](Self::node_eq), the builder type does not depend
+    /// on the function type, so the function can be chosen at runtime,
+    /// such as from a configuration file.
+    pub fn node_eq_dyn(
+        self,
+        node_eq: DynNodeEq<'a, Query, Data>,
+    ) -> Vf2Builder<'a, Query, Data, DynNodeEq<'a, Query, Data>, EdgeEq, OnPartial, OnProgress>
+    {
+        self.node_eq(node_eq)
+    }
+
+    /// Configures VF2 to use the boxed `edge_eq` as the edge equality function.
+    ///
+    /// Unlike [`edge_eq`](Self::edge_eq), the builder type does not depend
+    /// on the function type, so the function can be chosen at runtime,
+    /// such as from a configuration file.
+    pub fn edge_eq_dyn(
+        self,
+        edge_eq: DynEdgeEq<'a, Query, Data>,
+    ) -> Vf2Builder<'a, Query, Data, NodeEq, DynEdgeEq<'a, Query, Data>, OnPartial, OnProgress>
+    {
+        self.edge_eq(edge_eq)
+    }
+
+    /// Configures VF2 to use `node_eq_abort` as a node equality function
+    /// that can also abort the search.
+    ///
+    /// Return [`NodeMatch::Abort`] when a pair shows the whole search
+    /// is hopeless. The search then ends with the isomorphisms found so far.
+    /// This is checked before, and in addition to,
+    /// the function set with [`node_eq`](Self::node_eq).
+    pub fn node_eq_abort<F>(mut self, node_eq_abort: F) -> Self
+    where
+        F: Fn(&Query::NodeLabel, &Data::NodeLabel) -> NodeMatch + 'a,
+    {
+        self.config.node_match = Some(Predicate::new(Arc::new(node_eq_abort)));
+        self
+    }
+
+    /// Configures VF2 to let `query_nodes` match any data node
+    /// regardless of label.
+    ///
+    /// The node equality functions set with [`node_eq`](Self::node_eq)
+    /// and [`node_eq_abort`](Self::node_eq_abort) are not called
+    /// for these query nodes, and [`label_index`](Self::label_index)
+    /// does not restrict their candidates. Edges are still checked.
+    /// Replaces any previous wildcard query nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`]
+    /// if a query node is not in the query graph.
+    pub fn wildcard_query_nodes(mut self, query_nodes: &[NodeIndex]) -> Result<Self, Vf2Error> {
+        let mut wildcards = vec![false; self.query.node_count()];
+        for &query_node in query_nodes {
+            self.check_query_node(query_node)?;
+            wildcards[query_node] = true;
+        }
+        self.config.wildcards = wildcards;
+        Ok(self)
+    }
+
+    /// Configures VF2 to let `query_nodes` map to data nodes
+    /// that other query nodes already map to.
+    ///
+    /// This is a homomorphism mode for quotient-style matching:
+    /// the map need not be injective on these query nodes,
+    /// but every query edge must still map to a data edge.
+    /// Two adjacent query nodes merged into one data node
+    /// therefore need a data self-loop.
+    /// Replaces any previous mergeable query nodes.
+    ///
+    /// Has no effect when finding graph isomorphisms
+    /// or induced subgraph isomorphisms, which are injective.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`]
+    /// if a query node is not in the query graph.
+    pub fn allow_merge(mut self, query_nodes: &[NodeIndex]) -> Result<Self, Vf2Error> {
+        let mut mergeable = vec![false; self.query.node_count()];
+        for &query_node in query_nodes {
+            self.check_query_node(query_node)?;
+            mergeable[query_node] = true;
+        }
+        self.config.mergeable = mergeable;
+        Ok(self)
+    }
+
+    /// Configures VF2 to let `edges` of the query graph
+    /// be absent from the data graph.
+    ///
+    /// An optional query edge is not required to map to a data edge,
+    /// but if the data edge exists, their labels are still compared.
+    /// This lets one query match several structural variants.
+    /// Each edge is a `(source, target)` pair, and either orientation
+    /// names the same edge if the graphs are undirected.
+    /// Replaces any previous optional query edges.
+    ///
+    /// The degree and edge count checks are skipped, since a match
+    /// may cover fewer data edges than there are query edges.
+    /// [`iter_with_edges`](Self::iter_with_edges) still pairs each
+    /// optional query edge with a data edge, which may be absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`]
+    /// if a query node is not in the query graph.
+    pub fn optional_query_edges(
+        mut self,
+        edges: &[(NodeIndex, NodeIndex)],
+    ) -> Result<Self, Vf2Error> {
+        let mut optional_edges = BTreeSet::new();
+        for &(source, target) in edges {
+            self.check_query_node(source)?;
+            self.check_query_node(target)?;
+            optional_edges.insert(if self.query.is_directed() {
+                (source, target)
+            } else {
+                (source.min(target), source.max(target))
+            });
+        }
+        self.config.optional_edges = optional_edges;
+        Ok(self)
+    }
+
+    /// Configures VF2 to read both graphs as undirected,
+    /// matching edges in either orientation.
+    ///
+    /// A query edge then maps to a data edge between the same nodes
+    /// in either direction, which is useful to match the structure
+    /// of a directed graph, or to match an undirected query
+    /// against a directed data graph and vice versa.
+    /// Edges between two nodes in both directions, and parallel edges,
+    /// count as one edge, so edge multiplicities are not compared.
+    /// An edge label is read from the edge in the direction
+    /// it is looked up in if it exists, or else the other direction.
+    /// The edge count and degree checks done before the search are skipped.
+    pub fn ignore_direction(mut self) -> Self {
+        self.config.ignore_direction = true;
+        self
+    }
+
+    /// Configures VF2 to only map `query_node` to one of `data_nodes`.
+    ///
+    /// Only these data nodes are considered as candidates for `query_node`,
+    /// together with the candidates from the terminal sets.
+    /// This is much faster than rejecting nodes in [`node_eq`](Self::node_eq)
+    /// when the sets are small, for example when they come from an external index.
+    /// Replaces any previous candidates for `query_node`
+    /// and takes precedence over [`label_index`](Self::label_index) for it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`] or [`Vf2Error::DataNodeOutOfRange`]
+    /// if a node is not in its graph.
+    pub fn candidates(
+        mut self,
+        query_node: NodeIndex,
+        mut data_nodes: Vec<NodeIndex>,
+    ) -> Result<Self, Vf2Error> {
+        self.check_query_node(query_node)?;
+        for &data_node in &data_nodes {
+            self.check_data_node(data_node)?;
+        }
+        data_nodes.sort_unstable();
+        data_nodes.dedup();
+        if self.config.candidates.is_empty() {
+            self.config.candidates = vec![None; self.query.node_count()];
+        }
+        self.config.candidates[query_node] = Some(data_nodes);
+        Ok(self)
+    }
+
+    /// Configures VF2 to only match the subgraph of the query graph
+    /// induced by `query_nodes`.
+    ///
+    /// Only these query nodes and the edges among them are matched,
+    /// which saves building a separate query graph for each sub-pattern.
+    /// Query node `i` of the restricted query is `query_nodes[i]`,
+    /// so isomorphisms are indexed by position in `query_nodes`.
+    /// Map them back with [`RestrictedQuery::original_index`].
+    ///
+    /// Options that take query nodes refer to the restricted query
+    /// once this is called. Those set before are kept for the chosen nodes
+    /// and dropped for the others. Identical components are no longer
+    /// deduplicated, so call [`dedup_components`](Self::dedup_components)
+    /// after this.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`] if a query node
+    /// is not in the query graph or the [`anchor`](Self::anchor)
+    /// query node is not chosen, or [`Vf2Error::DuplicateQueryNode`]
+    /// if a query node is chosen more than once.
+    pub fn restrict_query(
+        self,
+        query_nodes: &[NodeIndex],
+    ) -> Result<
+        RestrictedVf2Builder<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+        Vf2Error,
+    > {
+        let query = RestrictedQuery::new(self.query, query_nodes)?;
+        if let Some(Root::Pair(query_node, _)) = self.config.root {
+            if query.restricted_index(query_node).is_none() {
+                return Err(Vf2Error::QueryNodeOutOfRange(query_node));
+            }
+        }
+        let config = self.config.restrict_query(
+            query_nodes,
+            |node| query.restricted_index(node),
+            query.is_directed(),
+        );
+        Ok(Vf2Builder {
+            problem: self.problem,
+            query: GraphRef::Shared(Arc::new(query)),
+            data: self.data,
+            node_eq: self.node_eq,
+            edge_eq: self.edge_eq,
+            callbacks: self.callbacks,
+            config,
+        })
+    }
+
+    /// Configures VF2 to find each isomorphism only once up to
+    /// permutations of identical connected components of the query graph.
+    ///
+    /// If the query graph has `k` connected components that are isomorphic,
+    /// labels included, every isomorphism is otherwise found `k!` times,
+    /// once for each way of assigning the components to their images.
+    /// This only keeps the assignment that maps a chosen node of each
+    /// component to increasing data nodes, in the order of
+    /// the lowest node index of each component.
+    ///
+    /// Components are compared with the [`PartialEq`] implementations
+    /// of the query labels, so the equality functions must not tell
+    /// equal labels apart. Options that treat the nodes of identical components
+    /// differently, such as [`candidates`](Self::candidates),
+    /// [`wildcard_query_nodes`](Self::wildcard_query_nodes),
+    /// [`allow_merge`](Self::allow_merge),
+    /// [`optional_query_edges`](Self::optional_query_edges),
+    /// and [`anchor`](Self::anchor), may then drop isomorphisms.
+    pub fn dedup_components(mut self) -> Self
+    where
+        Query::NodeLabel: PartialEq,
+        Query::EdgeLabel: PartialEq,
+    {
+        self.config.component_order = component_order(&*self.query);
+        self
+    }
+
+    /// Configures VF2 to match each connected component
+    /// of a disconnected query graph on its own.
+    ///
+    /// The search covers the component of the first query node as usual.
+    /// Each other component is searched on its own the first time
+    /// it is reached, and its matches are kept. The search then takes
+    /// the product of these matches, pushing one match of each remaining
+    /// component at a time and skipping those that conflict with the
+    /// partial map. So a later component is searched once, instead of
+    /// once for each match of the components before it.
+    ///
+    /// The isomorphisms are the same as without decomposition,
+    /// though they may be found in a different order.
+    /// Partial maps passed to [`on_partial`](Self::on_partial) are taken
+    /// at the leaves of the combined search, and the kept matches use
+    /// memory in proportion to their number. The steps of the searches
+    /// of single components do not count toward
+    /// [`node_budget`](Self::node_budget).
+    /// The query graph is not decomposed with a
+    /// [`query_order`](Self::query_order) or when partial maps are collected
+    /// with [`min_matched_nodes`](Self::min_matched_nodes).
+    pub fn decompose_components(mut self) -> Self {
+        self.config.decompose_components = true;
+        self
+    }
+
+    /// Configures VF2 to only find isomorphisms that map the query nodes
+    /// of each `(earlier, later)` pair in `pairs` to increasing data nodes.
+    ///
+    /// This keeps the images in a partial order on the query nodes,
+    /// such as the order of a schedule, or breaks symmetries
+    /// of the query graph that [`dedup_components`](Self::dedup_components)
+    /// does not. Each pair is checked as soon as both nodes are mapped.
+    /// Replaces any previous constraints.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`]
+    /// if a query node is not in the query graph.
+    pub fn index_order_constraints(
+        mut self,
+        pairs: &[(NodeIndex, NodeIndex)],
+    ) -> Result<Self, Vf2Error> {
+        for &(earlier, later) in pairs {
+            self.check_query_node(earlier)?;
+            self.check_query_node(later)?;
         }
+        self.config.index_order = pairs.to_vec();
+        Ok(self)
+    }
+
+    /// Configures VF2 to use `edge_eq_symmetric` as the edge equality function
+    /// on undirected graphs, trying both orientations of each query edge.
+    ///
+    /// An undirected edge has no orientation, but its label may.
+    /// The function is called with `reversed` set to `false`,
+    /// and then to `true` if that does not match.
+    /// When `reversed` is `true`, it should compare the query label
+    /// as if read from the other end of the edge.
+    /// An edge matches if either call returns `true`.
+    ///
+    /// This replaces the function set with [`edge_eq`](Self::edge_eq)
+    /// on undirected graphs, and has no effect on directed graphs.
+    pub fn edge_eq_symmetric<F>(mut self, edge_eq_symmetric: F) -> Self
+    where
+        F: Fn(&Query::EdgeLabel, &Data::EdgeLabel, bool) -> bool + 'a,
+    {
+        self.config.edge_eq_symmetric = Some(Predicate::new(Arc::new(edge_eq_symmetric)));
+        self
+    }
+
+    /// Configures VF2 to also compare edges with `edge_eq_ctx`,
+    /// which sees the labels of the endpoints as well as the edge label.
+    ///
+    /// Use this when edge equivalence depends on the nodes an edge connects.
+    /// Each query edge is passed with the data edge it maps to,
+    /// read so the source of the query edge maps to the source
+    /// of the data edge. On undirected graphs, either end may be the source.
+    /// This is checked in addition to [`edge_eq`](Self::edge_eq)
+    /// and [`edge_eq_symmetric`](Self::edge_eq_symmetric).
+    pub fn edge_eq_ctx<F>(mut self, edge_eq_ctx: F) -> Self
+    where
+        F: Fn(EdgeCtx<'_, Query>, EdgeCtx<'_, Data>) -> bool + 'a,
+    {
+        self.config.edge_eq_ctx = Some(Predicate::new(Arc::new(edge_eq_ctx)));
+        self
+    }
+
+    /// Configures VF2 to treat data edges for which `transparent_edge`
+    /// returns `true` as absent when checking that a subgraph is induced.
+    ///
+    /// This only relaxes the induced check, which rejects data edges
+    /// between covered nodes that have no counterpart in the query graph.
+    /// A query edge mapped to a transparent data edge is still matched,
+    /// and the edge equality function is still called on its labels.
+    /// When finding graph isomorphisms, the data graph may then have
+    /// extra transparent edges.
+    /// Has no effect when finding subgraph isomorphisms,
+    /// which are not induced.
+    pub fn transparent_edge<F>(mut self, transparent_edge: F) -> Self
+    where
+        F: Fn(&Data::EdgeLabel) -> bool + 'a,
+    {
+        self.config.transparent_edge = Some(Predicate::new(Arc::new(transparent_edge)));
+        self
+    }
+
+    /// Configures VF2 to allow up to `slack` extra data edges
+    /// when checking that a subgraph is induced.
+    ///
+    /// An induced subgraph normally has no data edges between covered nodes
+    /// without a counterpart in the query graph. This allows up to `slack`
+    /// such edges in each isomorphism, which is useful for noisy data graphs.
+    /// Transparent edges set with [`transparent_edge`](Self::transparent_edge)
+    /// are not counted.
+    /// When finding graph isomorphisms, the data graph may then have
+    /// up to `slack` more edges than the query graph.
+    /// Has no effect when finding subgraph isomorphisms,
+    /// which are not induced.
+    pub fn induced_edge_slack(mut self, slack: usize) -> Self {
+        self.config.induced_edge_slack = slack;
+        self
+    }
+
+    /// Configures VF2 to allow up to `k` query nodes in each isomorphism
+    /// whose labels do not match their data nodes.
+    ///
+    /// A pair rejected by the node equality function set with
+    /// [`node_eq`](Self::node_eq) is then still tried while the partial map
+    /// has fewer than `k` such pairs, which is useful for noisy labels.
+    /// Edges are still checked, and so is the function set with
+    /// [`node_eq_abort`](Self::node_eq_abort).
+    /// The [`label_index`](Self::label_index) is not used while
+    /// mismatches are allowed, since it rules out nodes by label,
+    /// but [`atom_classes`](Self::atom_classes) are still enforced.
+    pub fn node_mismatch_budget(mut self, k: usize) -> Self {
+        self.config.node_mismatch_budget = k;
+        self
+    }
+
+    /// Configures VF2 to only find isomorphisms
+    /// that map `query_node` to `data_node`.
+    ///
+    /// The search starts from this pair, so only the part of
+    /// the data graph around `data_node` is explored.
+    /// Replaces any previous [`anchor`](Self::anchor)
+    /// or [`must_include_data_node`](Self::must_include_data_node).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`] or [`Vf2Error::DataNodeOutOfRange`]
+    /// if a node is not in its graph.
+    pub fn anchor(mut self, query_node: NodeIndex, data_node: NodeIndex) -> Result<Self, Vf2Error> {
+        self.check_query_node(query_node)?;
+        self.check_data_node(data_node)?;
+        self.config.root = Some(Root::Pair(query_node, data_node));
+        Ok(self)
+    }
+
+    /// Configures VF2 to only find isomorphisms
+    /// that map some query node to `data_node`.
+    ///
+    /// This is useful after adding `data_node` to the data graph
+    /// to find only the new isomorphisms.
+    /// The search tries each query node anchored to `data_node` in turn,
+    /// so only the part of the data graph around `data_node` is explored.
+    /// Replaces any previous [`anchor`](Self::anchor)
+    /// or [`must_include_data_node`](Self::must_include_data_node).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::DataNodeOutOfRange`]
+    /// if `data_node` is not in the data graph.
+    pub fn must_include_data_node(mut self, data_node: NodeIndex) -> Result<Self, Vf2Error> {
+        self.check_data_node(data_node)?;
+        self.config.root = Some(Root::DataNode(data_node));
+        Ok(self)
+    }
+
+    /// Configures VF2 to only find isomorphisms
+    /// that map no query node to any of `data_nodes`.
+    ///
+    /// This is the opposite of [`anchor`](Self::anchor): the data nodes
+    /// are treated as already taken, such as by matches of other patterns,
+    /// and are never added to the partial map.
+    /// Replaces any previous occupied data nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::DataNodeOutOfRange`]
+    /// if a data node is not in the data graph.
+    pub fn occupied_data_nodes(mut self, data_nodes: &[NodeIndex]) -> Result<Self, Vf2Error> {
+        let mut occupied = vec![false; self.data.node_count()];
+        for &data_node in data_nodes {
+            self.check_data_node(data_node)?;
+            occupied[data_node] = true;
+        }
+        self.config.occupied = if data_nodes.is_empty() {
+            Vec::new()
+        } else {
+            occupied
+        };
+        Ok(self)
+    }
+
+    /// Configures VF2 to add query nodes to the partial map in `order`.
+    ///
+    /// By default, the next query node is the lowest-index node
+    /// in the first non-empty terminal set. An order that puts
+    /// selective query nodes first, such as those with rare labels,
+    /// can prune the search earlier.
+    /// The order only affects performance and the order isomorphisms
+    /// are found in, not which isomorphisms are found.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::InvalidQueryOrder`] if `order`
+    /// is not a permutation of the query nodes.
+    pub fn query_order(mut self, order: Vec<NodeIndex>) -> Result<Self, Vf2Error> {
+        let mut seen = vec![false; self.query.node_count()];
+        if order.len() != seen.len() {
+            return Err(Vf2Error::InvalidQueryOrder);
+        }
+        for &query_node in &order {
+            match seen.get_mut(query_node) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(Vf2Error::InvalidQueryOrder),
+            }
+        }
+        self.config.query_order = order;
+        Ok(self)
+    }
+
+    /// Returns the order the search adds query nodes to the partial map.
+    ///
+    /// This is the order set with [`query_order`](Self::query_order), if any.
+    /// Otherwise, it is the default order, which only depends on the query:
+    /// the next query node is the lowest-index node in the first non-empty
+    /// terminal set, or the lowest-index uncovered node if all are empty.
+    /// The order starts at the query node set with
+    /// [`anchor`](Self::anchor), or else at node 0.
+    /// With [`must_include_data_node`](Self::must_include_data_node),
+    /// each branch starts at a different query node,
+    /// and this returns the order of the branch starting at node 0.
+    ///
+    /// The search has no degree-based ordering like that of VF2++,
+    /// so the order does not start at a node of highest degree
+    /// and does not sort nodes by degree. Pass such an order, or a modified
+    /// one, to [`query_order`](Self::query_order) to override it.
+    pub fn computed_order(&self) -> Vec<NodeIndex> {
+        if !self.config.query_order.is_empty() {
+            return self.config.query_order.clone();
+        }
+        let first = match self.config.root {
+            Some(Root::Pair(query_node, _)) => query_node,
+            Some(Root::DataNode(_)) | None => 0,
+        };
+        terminal_set_order(
+            &*self.query,
+            first,
+            self.config.terminal_preference,
+            self.config.ignore_direction,
+        )
+    }
+
+    /// Configures VF2 to try candidate data nodes adjacent to
+    /// the most covered data nodes first.
+    ///
+    /// Isomorphisms covering more data edges are then found earlier,
+    /// which changes the order isomorphisms are found in,
+    /// but not which isomorphisms are found.
+    /// Ordering the candidates costs extra time at each step.
+    pub fn prefer_dense(mut self) -> Self {
+        self.config.prefer_dense = true;
+        self
+    }
+
+    /// Configures VF2 to precompute a dense adjacency matrix
+    /// of the data graph to check whether edges exist.
+    ///
+    /// Checking an edge in the matrix takes constant time,
+    /// where [`Graph::contains_edge`] may scan the neighbors of a node.
+    /// The matrix uses one bit per node pair, so it is only built
+    /// for data graphs with at most 10,000 nodes, which take about 12 MiB.
+    ///
+    /// Building the matrix pays off when the search is long.
+    /// In `benches/search.rs`, counting every 4-cycle in random data graphs
+    /// with 30 to 1,000 nodes and average degree 4 to 16 ran 10% to 30% faster
+    /// with the matrix, gaining more at higher degrees.
+    /// Finding the first 4-cycle in data graphs of average degree 4
+    /// broke even up to 300 nodes and ran 20% to 60% slower
+    /// from 1,000 nodes, since building the matrix took longer than the search.
+    /// So leave this off for short searches in large sparse graphs.
+    pub fn dense_adjacency(mut self) -> Self {
+        self.config.dense_adjacency = true;
+        self
+    }
+
+    /// Configures VF2 to call the node equality function
+    /// on every pair of query and data nodes once when the search starts,
+    /// and look up the results instead of calling it during the search.
+    ///
+    /// The search tries the same pair in many branches, so this trades
+    /// memory for fewer calls when the function is expensive
+    /// or the search revisits pairs often. It costs one call per pair
+    /// up front, so it does not pay off for short searches.
+    /// The matrix uses one bit per pair, so it is only built for
+    /// at most 134,217,728 pairs, which take 16 MiB.
+    /// Wildcard query nodes and [`node_eq_abort`](Self::node_eq_abort)
+    /// are still checked as usual.
+    pub fn precompute_node_compat(mut self) -> Self {
+        self.config.precompute_node_compat = true;
+        self
+    }
+
+    /// Configures VF2 to compare every pair of query and data edges once
+    /// when the search starts, and look up the results instead of calling
+    /// the edge equality functions during the search.
+    ///
+    /// Like [`precompute_node_compat`](Self::precompute_node_compat),
+    /// this trades memory for fewer calls, with the same size limit.
+    /// Undirected edges are compared in both orientations,
+    /// so each counts twice toward the limit.
+    pub fn precompute_edge_compat(mut self) -> Self {
+        self.config.precompute_edge_compat = true;
+        self
+    }
+
+    /// Configures VF2 to group data nodes by label before the search,
+    /// and only try data nodes with the same label as the query node.
+    ///
+    /// Without this, every query node is tested against every
+    /// candidate data node, even if labels rarely match.
+    /// Use this with [`default_eq`](Self::default_eq),
+    /// or a node equality function that only matches equal labels,
+    /// since pairs with different labels are never tried.
+    ///
+    /// The search is skipped when some label is on fewer data nodes
+    /// than query nodes, since no isomorphism can exist.
+    #[cfg(feature = "std")]
+    pub fn label_index(mut self) -> Self
+    where
+        Query: Graph<NodeLabel = Data::NodeLabel>,
+        Data::NodeLabel: Eq + Hash,
+    {
+        self.config.label_index = Some(Arc::new(LabelIndex::new(&*self.query, &*self.data)));
+        self
+    }
+
+    /// Configures VF2 to only map each query node
+    /// to a data node of the same class.
+    ///
+    /// The value at index `i` of `query_classes` is the class of query node `i`,
+    /// and likewise for `data_classes` and the data nodes. This is a fast
+    /// pre-filter for labels that fall into equivalence classes, such as
+    /// atom types in molecular graphs: data nodes are grouped by class
+    /// before the search, so pairs in different classes are never tried,
+    /// and the node equality function is only called within a class.
+    /// The search is skipped when some class has fewer data nodes
+    /// than query nodes, since no isomorphism can exist.
+    ///
+    /// Unlike the [`label_index`](Self::label_index), classes are a hard
+    /// constraint: they are intersected with any [`candidates`](Self::candidates)
+    /// and still apply to wildcard query nodes and with a
+    /// [`node_mismatch_budget`](Self::node_mismatch_budget).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::NodeClassesMismatch`] if there is not
+    /// one class per node of each graph.
+    pub fn atom_classes(
+        mut self,
+        query_classes: Vec<usize>,
+        data_classes: Vec<usize>,
+    ) -> Result<Self, Vf2Error> {
+        if query_classes.len() != self.query.node_count()
+            || data_classes.len() != self.data.node_count()
+        {
+            return Err(Vf2Error::NodeClassesMismatch);
+        }
+        let node_classes = LabelIndex::from_classes(&query_classes, &data_classes);
+        self.config.node_classes = Some(Arc::new(node_classes));
+        Ok(self)
+    }
+
+    /// Configures VF2 to compute a feasibility set for each query node
+    /// before the search, and only try data nodes in it.
+    ///
+    /// This is the pattern node class preprocessing from VF3.
+    /// The feasibility set of a query node holds the data nodes
+    /// with the same label and a compatible degree,
+    /// so pairs that [`label_index`](Self::label_index) would try
+    /// and the degree check would reject are never tried.
+    /// Like the label index, this requires `NodeLabel: Eq + Hash`,
+    /// and should be used with [`default_eq`](Self::default_eq)
+    /// or a node equality function that only matches equal labels.
+    ///
+    /// The sets are intersected with any [`candidates`](Self::candidates).
+    /// Degrees are not compared when query nodes may be merged
+    /// with [`allow_merge`](Self::allow_merge).
+    #[cfg(feature = "approx")]
+    pub fn feasibility_sets(mut self) -> Self
+    where
+        Query: Graph<NodeLabel = Data::NodeLabel>,
+        Data::NodeLabel: Eq + Hash,
+    {
+        self = self.label_index();
+        self.config.feasibility_sets = true;
+        self
+    }
+
+    /// Configures VF2 to read the query graph neighbors from `cache`
+    /// instead of the query graph, so searches can share them.
+    pub(crate) fn query_cache(mut self, cache: Arc<GraphCache>) -> Self {
+        self.config.query_cache = Some(cache);
+        self
+    }
+
+    /// Configures VF2 to read the data graph neighbors from `cache`
+    /// instead of the data graph, so searches can share them.
+    pub(crate) fn data_cache(mut self, cache: Arc<GraphCache>) -> Self {
+        self.config.data_cache = Some(cache);
+        self
+    }
+
+    /// Configures VF2 to call `on_partial` whenever the search
+    /// backtracks from a leaf of the SSR tree, that is, a partial map
+    /// that could not be extended.
+    ///
+    /// The callback receives the partial map and the depth reached.
+    /// The value at index `i` is the data node that query node `i` maps to,
+    /// or [`NodeIndex::MAX`] if query node `i` is not covered.
+    /// Complete isomorphisms are not passed to the callback.
+    /// Pairs pruned before the search descends, such as a data node
+    /// with fewer neighbors than its query node, are never reported.
+    ///
+    /// This is useful to see how close a query came to matching.
+    pub fn on_partial<NewOnPartial>(
+        self,
+        on_partial: NewOnPartial,
+    ) -> Vf2Builder<'a, Query, Data, NodeEq, EdgeEq, NewOnPartial, OnProgress>
+    where
+        NewOnPartial: FnMut(&[NodeIndex], usize),
+    {
+        Vf2Builder {
+            problem: self.problem,
+            query: self.query,
+            data: self.data,
+            node_eq: self.node_eq,
+            edge_eq: self.edge_eq,
+            callbacks: Callbacks {
+                on_partial: Some(on_partial),
+                on_progress: self.callbacks.on_progress,
+            },
+            config: self.config,
+        }
+    }
+
+    /// Configures VF2 to call `on_progress` with a rough estimate
+    /// of how much of the search is complete, from `0.0` to `1.0`.
+    ///
+    /// The estimate is the fraction of candidate data nodes for
+    /// the first query node whose branches have been fully explored.
+    /// It is reported whenever a branch is finished, so it increases
+    /// in coarse and uneven steps, and reaches `1.0` when the search ends.
+    /// Branches can differ greatly in size, so this is only suited
+    /// for progress bars, not time estimates.
+    pub fn on_progress<NewOnProgress>(
+        self,
+        on_progress: NewOnProgress,
+    ) -> Vf2Builder<'a, Query, Data, NodeEq, EdgeEq, OnPartial, NewOnProgress>
+    where
+        NewOnProgress: FnMut(f64),
+    {
+        Vf2Builder {
+            problem: self.problem,
+            query: self.query,
+            data: self.data,
+            node_eq: self.node_eq,
+            edge_eq: self.edge_eq,
+            callbacks: Callbacks {
+                on_partial: self.callbacks.on_partial,
+                on_progress: Some(on_progress),
+            },
+            config: self.config,
+        }
+    }
+
+    /// Configures VF2 to give up after `steps` search steps.
+    ///
+    /// Each step tries one candidate pair or backtracks once,
+    /// so the budget bounds the work done regardless of machine speed,
+    /// and the same search always stops at the same point.
+    /// When the budget runs out, the iterator yields no further isomorphisms
+    /// and [`IsomorphismIter::budget_exhausted`] returns `true`.
+    /// This is useful for reproducible partial searches, such as in tests.
+    pub fn node_budget(mut self, steps: usize) -> Self {
+        self.config.node_budget = Some(steps);
+        self
+    }
+
+    /// Configures which terminal set the search takes
+    /// the next query node from when both are non-empty.
+    ///
+    /// By default, the search prefers successors of covered query nodes,
+    /// [`SourcePreference::Outgoing`]. Preferring predecessors can
+    /// prune much earlier on directed graphs whose edges mostly point
+    /// toward the distinctive nodes, such as trees stored leaf to root.
+    /// This only changes the order in which nodes are added and
+    /// isomorphisms are found, not which isomorphisms are found,
+    /// so it is purely a performance knob. It has no effect
+    /// on undirected graphs or with [`query_order`](Self::query_order).
+    pub fn terminal_preference(mut self, preference: SourcePreference) -> Self {
+        self.config.terminal_preference = preference;
+        self
+    }
+
+    /// Configures what the search does when the query graph has no nodes.
+    ///
+    /// By default, the search panics, [`EmptyBehavior::Panic`].
+    /// Pipelines that generate queries may instead treat an empty query
+    /// as having no isomorphisms, or as having the empty map as its only
+    /// isomorphism, which is the trivial one when finding graph isomorphisms
+    /// or subgraph isomorphisms.
+    pub fn empty_query_behavior(mut self, behavior: EmptyBehavior) -> Self {
+        self.config.empty_query = behavior;
+        self
     }
 
     /// Returns the first isomorphism
@@ -172,29 +1139,1011 @@ where
         self.iter().into_next()
     }
 
+    /// Returns the first isomorphism
+    /// from the query graph to the data graph,
+    /// telling apart a search that found none from one cut short.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SearchIncomplete`] if no isomorphism was found before
+    /// the [`node_budget`](Self::node_budget) ran out or
+    /// [`node_eq_abort`](Self::node_eq_abort) aborted the search.
+    /// Returns `Ok(None)` only if the search was complete.
+    pub fn try_first(self) -> Result<Option<Isomorphism>, SearchIncomplete> {
+        let mut iter = self.iter();
+        if let Some(isomorphism) = iter.next_ref() {
+            Ok(Some(isomorphism.clone()))
+        } else if iter.budget_exhausted() || iter.aborted() {
+            Err(SearchIncomplete)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns `true` if there is an isomorphism
+    /// from the query graph to the data graph.
+    ///
+    /// Unlike [`first`](Self::first), this does not allocate the isomorphism.
+    pub fn exists(self) -> bool {
+        self.iter().next_ref().is_some()
+    }
+
+    /// Returns a vector of at most `n` isomorphisms
+    /// from the query graph to the data graph.
+    ///
+    /// The search stops as soon as `n` isomorphisms are found.
+    pub fn first_n(self, n: usize) -> Vec<Isomorphism> {
+        self.iter().take(n).collect()
+    }
+
     /// Returns a vector of isomorphisms
     /// from the query graph to the data graph.
     pub fn vec(self) -> Vec<Isomorphism> {
         self.iter().collect()
     }
 
+    /// Replaces the contents of `buf` with the isomorphisms
+    /// from the query graph to the data graph.
+    ///
+    /// This gives the same isomorphisms as [`vec`](Self::vec),
+    /// but reuses the allocations of `buf` and of the isomorphisms
+    /// already in it, so repeated searches into the same buffer
+    /// only allocate when they find more isomorphisms than before.
+    pub fn collect_into(self, buf: &mut Vec<Isomorphism>) {
+        let mut iter = self.iter();
+        let mut len = 0;
+        while let Some(isomorphism) = iter.next_ref() {
+            match buf.get_mut(len) {
+                Some(reused) => {
+                    reused.clear();
+                    reused.extend_from_slice(isomorphism);
+                }
+                None => buf.push(isomorphism.clone()),
+            }
+            len += 1;
+        }
+        buf.truncate(len);
+    }
+
+    /// Returns a vector of isomorphisms
+    /// from the query graph to the data graph,
+    /// each as a map from query nodes to data nodes.
+    ///
+    /// See [`IsomorphismIter::next_map`] for the extra cost.
+    #[cfg(feature = "std")]
+    pub fn vec_maps(self) -> Vec<HashMap<NodeIndex, NodeIndex>> {
+        let mut iter = self.iter();
+        let mut maps = Vec::new();
+        while let Some(map) = iter.next_map() {
+            maps.push(map);
+        }
+        maps
+    }
+
+    /// Returns a vector of isomorphisms
+    /// from the query graph to the data graph,
+    /// sorted lexicographically.
+    ///
+    /// Unlike [`vec`](Self::vec), the order does not depend on the order
+    /// nodes are visited in the search, so it is reproducible.
+    /// All isomorphisms are held in memory to be sorted.
+    pub fn sorted(self) -> Vec<Isomorphism> {
+        let mut isomorphisms = self.vec();
+        isomorphisms.sort_unstable();
+        isomorphisms
+    }
+
+    /// Returns the `k` isomorphisms with the highest `score`,
+    /// with their scores, from highest to lowest.
+    ///
+    /// Scores are compared with [`f64::total_cmp`], and equal scores
+    /// rank in the order the isomorphisms are found.
+    /// Only the best `k` isomorphisms so far are held in a heap,
+    /// so memory stays proportional to `k` however many are found.
+    /// Every isomorphism is still scored, so the search runs to the end.
+    pub fn top_k_by<F>(self, k: usize, mut score: F) -> Vec<(f64, Isomorphism)>
+    where
+        F: FnMut(&Isomorphism) -> f64,
+    {
+        if k == 0 {
+            return Vec::new();
+        }
+        // A min-heap, so the worst of the best is on top.
+        let mut best = BinaryHeap::new();
+        let mut iter = self.iter();
+        let mut found = 0;
+        while let Some(isomorphism) = iter.next_ref() {
+            let score = score(isomorphism);
+            let better = best.len() < k
+                || best.peek().is_some_and(|Reverse(worst): &Reverse<Scored>| {
+                    score.total_cmp(&worst.score).is_gt()
+                });
+            if better {
+                if best.len() == k {
+                    best.pop();
+                }
+                best.push(Reverse(Scored {
+                    score,
+                    found,
+                    isomorphism: isomorphism.clone(),
+                }));
+            }
+            found += 1;
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| (scored.score, scored.isomorphism))
+            .collect()
+    }
+
+    /// Returns the isomorphisms from the query graph to the data graph
+    /// concatenated into a single vector, and the stride.
+    ///
+    /// The stride is the number of query nodes.
+    /// Isomorphism `i` is at `i * stride..(i + 1) * stride`.
+    /// This avoids allocating a vector per isomorphism.
+    pub fn vec_flat(self) -> (Vec<NodeIndex>, usize) {
+        let stride = self.query.node_count();
+        let mut iter = self.iter();
+        let mut flat = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            flat.extend_from_slice(isomorphism);
+        }
+        (flat, stride)
+    }
+
+    /// Returns the number of isomorphisms
+    /// from the query graph to the data graph.
+    ///
+    /// Unlike [`vec`](Self::vec), this does not store the isomorphisms.
+    pub fn count(self) -> usize {
+        let mut iter = self.iter();
+        let mut count = 0;
+        while iter.next_ref().is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the number of isomorphisms
+    /// from the query graph to the data graph,
+    /// grouped by the key that `key` computes for each isomorphism.
+    ///
+    /// Only the counts are stored, so memory use
+    /// grows with the number of distinct keys.
+    #[cfg(feature = "std")]
+    pub fn count_by<K, F>(self, mut key: F) -> HashMap<K, usize>
+    where
+        K: Eq + Hash,
+        F: FnMut(&Isomorphism) -> K,
+    {
+        let mut iter = self.iter();
+        let mut counts = HashMap::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            *counts.entry(key(isomorphism)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the number of isomorphisms
+    /// from the query graph to the data graph,
+    /// grouped by the data node that `query_node` maps to.
+    ///
+    /// Data nodes that `query_node` never maps to are omitted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`]
+    /// if `query_node` is not in the query graph.
+    #[cfg(feature = "std")]
+    pub fn count_by_query_node(
+        self,
+        query_node: NodeIndex,
+    ) -> Result<HashMap<NodeIndex, usize>, Vf2Error> {
+        self.check_query_node(query_node)?;
+        Ok(self.count_by(|isomorphism| isomorphism[query_node]))
+    }
+
+    /// Sends the isomorphisms from the query graph to the data graph
+    /// into `sender` as they are found, and returns the number sent.
+    ///
+    /// This runs the search on the calling thread, so a consumer
+    /// on another thread can receive isomorphisms while the search runs.
+    /// The search stops early if the receiver is dropped.
+    #[cfg(feature = "std")]
+    pub fn send_to(self, sender: Sender<Isomorphism>) -> usize {
+        let mut iter = self.iter();
+        let mut sent = 0;
+        while let Some(isomorphism) = iter.next_ref() {
+            if sender.send(isomorphism.clone()).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+        sent
+    }
+
+    /// Folds the isomorphisms from the query graph to the data graph
+    /// into an accumulator, stopping early if `f` returns [`ControlFlow::Break`].
+    ///
+    /// Starting from `init`, `f` is called with the accumulator and each
+    /// isomorphism in turn, and returns the next accumulator.
+    /// Returns the accumulator from the last call,
+    /// or `init` if there are no isomorphisms.
+    ///
+    /// The isomorphism is borrowed from the search state and only valid
+    /// during the call, so clone it if it must be kept.
+    /// Unlike folding over [`iter`](Self::iter), this does not allocate.
+    pub fn try_fold<B, F>(self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &Isomorphism) -> ControlFlow<B, B>,
+    {
+        let mut iter = self.iter();
+        let mut accumulator = init;
+        while let Some(isomorphism) = iter.next_ref() {
+            match f(accumulator, isomorphism) {
+                ControlFlow::Continue(next) => accumulator = next,
+                ControlFlow::Break(last) => return last,
+            }
+        }
+        accumulator
+    }
+
+    /// Writes the isomorphisms from the query graph to the data graph
+    /// to `writer` as JSON lines, and returns the number written.
+    ///
+    /// Each isomorphism is written as a JSON array on its own line,
+    /// as it is found, so the isomorphisms are never held in memory.
+    /// Writes are small, so `writer` should be buffered.
+    #[cfg(feature = "json")]
+    pub fn write_jsonl<W>(self, mut writer: W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        let mut iter = self.iter();
+        let mut count = 0;
+        while let Some(isomorphism) = iter.next_ref() {
+            writer.write_all(b"[")?;
+            for (index, node) in isomorphism.iter().enumerate() {
+                if index > 0 {
+                    writer.write_all(b",")?;
+                }
+                write!(writer, "{node}")?;
+            }
+            writer.write_all(b"]\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Returns the subgraph of the data graph induced by
+    /// each isomorphism from the query graph to the data graph,
+    /// as a [`petgraph::Graph`] with cloned labels.
+    ///
+    /// Node `i` of each subgraph is the data node query node `i` maps to.
+    /// Every data edge between these nodes is copied,
+    /// even when finding subgraph isomorphisms that do not use it.
+    /// The edge type `Ty` must match the directedness of the data graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data graph is directed and `Ty` is undirected, or vice versa.
+    #[cfg(feature = "petgraph")]
+    pub fn extract_subgraphs<Ty>(self) -> Vec<petgraph::Graph<Data::NodeLabel, Data::EdgeLabel, Ty>>
+    where
+        Data::NodeLabel: Clone,
+        Data::EdgeLabel: Clone,
+        Ty: petgraph::EdgeType,
+    {
+        let data = self.data.clone();
+        let mut positions = vec![None; data.node_count()];
+        let mut iter = self.iter();
+        let mut subgraphs = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            subgraphs.push(crate::petgraph::induced_subgraph(
+                &*data,
+                isomorphism,
+                &mut positions,
+            ));
+        }
+        subgraphs
+    }
+
+    /// Returns the isomorphisms from the query graph to the data graph,
+    /// grouped into clusters of isomorphisms that overlap.
+    ///
+    /// Two isomorphisms are in the same cluster if they share a data node,
+    /// directly or through other isomorphisms in the cluster.
+    /// Each cluster covers a distinct region of the data graph.
+    /// Clusters are in the order their first isomorphisms are found.
+    pub fn match_clusters(self) -> Vec<Vec<Isomorphism>> {
+        let data_len = self.data.node_count();
+        let mut sets = UnionFind::new(data_len);
+        let isomorphisms = self.vec();
+        for isomorphism in &isomorphisms {
+            for window in isomorphism.windows(2) {
+                sets.union(window[0], window[1]);
+            }
+        }
+        // The value at index `i` is the cluster of root data node `i`, if any.
+        let mut cluster_indices = vec![None; data_len];
+        let mut clusters: Vec<Vec<Isomorphism>> = Vec::new();
+        for isomorphism in isomorphisms {
+            let Some(&first) = isomorphism.first() else {
+                // The empty map of an empty query overlaps no other.
+                clusters.push(vec![isomorphism]);
+                continue;
+            };
+            let root = sets.find(first);
+            let index = *cluster_indices[root].get_or_insert_with(|| {
+                clusters.push(Vec::new());
+                clusters.len() - 1
+            });
+            clusters[index].push(isomorphism);
+        }
+        clusters
+    }
+
+    /// Returns isomorphisms from the query graph to the data graph
+    /// whose sets of data nodes are pairwise disjoint, chosen greedily.
+    ///
+    /// Each isomorphism found forbids its data nodes
+    /// for the rest of the search, and the search continues
+    /// until no isomorphism avoids every forbidden data node.
+    /// The result is maximal, since no other isomorphism is disjoint
+    /// from all of them, but it need not be the largest possible.
+    /// This is useful for tiling or packing copies of the query.
+    pub fn disjoint_matches(self) -> Vec<Isomorphism> {
+        let mut iter = self.iter();
+        let mut matches = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            let isomorphism = isomorphism.clone();
+            iter.forbid_data_nodes(&isomorphism);
+            matches.push(isomorphism);
+        }
+        matches
+    }
+
+    /// Returns the first isomorphism from the query graph to the data graph
+    /// found for each data node that query node 0 maps to.
+    ///
+    /// Each isomorphism found forbids mapping query node 0
+    /// to the same data node for the rest of the search,
+    /// so the branches that would only find more isomorphisms
+    /// from that root are skipped. This gives one representative
+    /// per starting location in the data graph.
+    pub fn one_per_root(self) -> Vec<Isomorphism> {
+        let mut iter = self.iter();
+        let mut matches = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            let isomorphism = isomorphism.clone();
+            if let Some(&root) = isomorphism.first() {
+                iter.forbid_root(root);
+            }
+            matches.push(isomorphism);
+        }
+        matches
+    }
+
+    /// Returns isomorphisms from the query graph to the data graph
+    /// whose sets of data edges are pairwise disjoint, chosen greedily.
+    ///
+    /// This is like [`disjoint_matches`](Self::disjoint_matches),
+    /// but each isomorphism found forbids the data edges its query edges
+    /// map to, rather than its data nodes, so matches may share nodes.
+    /// If the query graph has no edges, every isomorphism is returned.
+    pub fn edge_disjoint_matches(self) -> Vec<Isomorphism> {
+        let query = self.query.clone();
+        let mut iter = self.iter();
+        let mut matches = Vec::new();
+        let mut data_edges = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            let isomorphism = isomorphism.clone();
+            data_edges.clear();
+            for source in query.node_indices() {
+                for target in query.neighbors(source, Direction::Outgoing) {
+                    data_edges.push((isomorphism[source], isomorphism[target]));
+                }
+            }
+            iter.forbid_data_edges(&data_edges);
+            matches.push(isomorphism);
+        }
+        matches
+    }
+
+    /// Returns the distinct sets of data nodes covered by isomorphisms
+    /// from the query graph to the data graph.
+    ///
+    /// Isomorphisms that only differ in which query node maps to
+    /// which data node are collapsed into one set.
+    /// Each set is sorted, and sets are in the order they are first found.
+    /// Only the distinct sets are stored, not every isomorphism.
+    pub fn distinct_node_sets(self) -> Vec<Vec<NodeIndex>> {
+        let mut iter = self.iter();
+        let mut seen = BTreeSet::new();
+        let mut sets = Vec::new();
+        while let Some(isomorphism) = iter.next_ref() {
+            let set: BTreeSet<NodeIndex> = isomorphism.iter().copied().collect();
+            if !seen.contains(&set) {
+                sets.push(set.iter().copied().collect());
+                seen.insert(set);
+            }
+        }
+        sets
+    }
+
+    /// Returns the data nodes that no isomorphism
+    /// from the query graph to the data graph maps to, in index order.
+    ///
+    /// These are the parts of the data graph the query never touches.
+    /// This enumerates every isomorphism, so it takes as long as
+    /// [`count`](Self::count).
+    pub fn unmatched_data_nodes(self) -> Vec<NodeIndex> {
+        let mut matched = vec![false; self.data.node_count()];
+        let mut iter = self.iter();
+        while let Some(isomorphism) = iter.next_ref() {
+            for &data_node in isomorphism {
+                matched[data_node] = true;
+            }
+        }
+        (0..matched.len())
+            .filter(|&data_node| !matched[data_node])
+            .collect()
+    }
+
     /// Returns an iterator of isomorphisms
     /// from the query graph to the data graph.
-    pub fn iter(self) -> IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq> {
-        if self.problem == Problem::Isomorphism {
-            assert_eq!(
-                self.query.node_count(),
-                self.data.node_count(),
-                "graphs must be the same size"
-            );
+    ///
+    /// # Panics
+    ///
+    /// Panics if a graph is too large to search
+    /// or the graphs disagree on direction.
+    /// See [`try_iter`](Self::try_iter).
+    pub fn iter(self) -> IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> {
+        self.try_iter().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Returns an iterator of isomorphisms
+    /// from the query graph to the data graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::GraphTooLarge`] if a graph has
+    /// [`NodeIndex::MAX`] or more nodes, since the search
+    /// reserves that value to mark uncovered nodes.
+    ///
+    /// Returns [`Vf2Error::DirectionMismatch`] if one graph is directed
+    /// and the other is not, unless the search
+    /// [ignores direction](Self::ignore_direction).
+    pub fn try_iter(
+        self,
+    ) -> Result<IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>, Vf2Error>
+    {
+        self.check_graphs()?;
+        // Merged query nodes share data nodes and edges,
+        // so the counts only rule out injective maps.
+        // Optional query edges need not be covered, and edges read as
+        // undirected may collapse, so the edge counts and degrees are not checked.
+        let compatible = self.merges()
+            || (self.node_counts_compatible()
+                && self.labels_compatible()
+                && self.classes_compatible()
+                && (!self.config.optional_edges.is_empty()
+                    || self.config.ignore_direction
+                    || (self.edge_counts_compatible()
+                        && self.degrees_compatible(Direction::Outgoing)
+                        && (!self.query.is_directed()
+                            || self.degrees_compatible(Direction::Incoming)))));
+        let mut iter = self.search();
+        if !compatible {
+            iter.finish();
         }
+        Ok(iter)
+    }
+
+    /// Returns an iterator that continues the search
+    /// checkpointed in `snapshot`.
+    ///
+    /// The builder must have the same graphs, problem type,
+    /// equality functions, and options as the checkpointed search.
+    /// See [`IsomorphismIter::checkpoint`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::SnapshotMismatch`]
+    /// if the snapshot does not fit the graph sizes,
+    /// [`Vf2Error::GraphTooLarge`] if a graph is too large to search,
+    /// or [`Vf2Error::DirectionMismatch`] if the graphs disagree on direction.
+    pub fn resume(
+        self,
+        snapshot: StateSnapshot,
+    ) -> Result<IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>, Vf2Error>
+    {
+        self.check_graphs()?;
+        let mut iter = self.search();
+        iter.restore(snapshot)?;
+        Ok(iter)
+    }
+
+    /// Returns an iterator of isomorphisms
+    /// from the query graph to the data graph,
+    /// together with the data edge each query edge maps to.
+    pub fn iter_with_edges(
+        self,
+    ) -> EdgeIsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> {
+        let query = self.query.clone();
+        EdgeIsomorphismIter::new(&query, self.iter())
+    }
+
+    /// Returns an iterator of isomorphisms
+    /// from the query graph to the data graph,
+    /// together with the number of data edges among the data nodes
+    /// each isomorphism maps to.
+    ///
+    /// This counts every data edge between these nodes, even when finding
+    /// subgraph isomorphisms that do not use it, so it can rank matches
+    /// by how many extra data edges they cover. Self-loops count,
+    /// and each undirected edge counts once.
+    pub fn iter_with_edge_density(
+        self,
+    ) -> EdgeDensityIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> {
+        let data = self.data.clone();
+        EdgeDensityIter::new(data, self.iter())
+    }
+
+    /// Returns the deepest partial isomorphism the search reaches
+    /// and the number of query nodes it covers.
+    ///
+    /// The value at index `i` is the data node that query node `i` maps to,
+    /// or [`NodeIndex::MAX`] if query node `i` is not covered.
+    /// If an isomorphism exists, the first one found is returned.
+    /// Returns [`None`] if no query node can be covered.
+    ///
+    /// This is a cheap heuristic for when no isomorphism exists.
+    /// It is not a maximum common subgraph,
+    /// since the search order limits which partial maps are reached.
+    /// Pairs pruned before the search descends, such as a data node
+    /// with fewer neighbors than its query node, are never covered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a graph is too large to search
+    /// or the graphs disagree on direction.
+    pub fn best_partial(self) -> Option<(Isomorphism, usize)> {
+        self.check_graphs()
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.search().best_partial()
+    }
+
+    /// Returns the partial isomorphisms that cover at least
+    /// `min_nodes` query nodes and that the search cannot extend,
+    /// together with the isomorphisms if `min_nodes` allows them.
+    ///
+    /// The value at index `i` is the data node that query node `i` maps to,
+    /// or [`NodeIndex::MAX`] if query node `i` is not covered.
+    /// Query nodes are covered in the search order,
+    /// so a partial map cannot be extended when the next query node
+    /// has no feasible data node. Maps that skip that query node
+    /// and cover later ones are not tried.
+    /// For induced subgraph isomorphisms, each partial map
+    /// is an induced embedding of the covered query nodes.
+    ///
+    /// This explores the whole SSR tree without the checks that skip
+    /// the search or prune partial maps when no isomorphism can exist,
+    /// so feasibility sets are not used either. The number of partial maps
+    /// can grow exponentially with the query size.
+    /// Keep `min_nodes` close to the query size to limit the output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a graph is too large to search
+    /// or the graphs disagree on direction.
+    pub fn min_matched_nodes(mut self, min_nodes: usize) -> Vec<Isomorphism> {
+        self.check_graphs()
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.config.feasibility_sets = false;
+        self.search().maximal_partials(min_nodes.max(1))
+    }
+
+    /// Returns an iterator over the search without
+    /// checking whether the search can be skipped.
+    fn search(mut self) -> IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> {
+        // Graphs of different sizes have no isomorphisms.
+        let mismatched = self.problem == Problem::Isomorphism
+            && self.query.node_count() != self.data.node_count();
         let induced = match self.problem {
             Problem::Isomorphism => true,
             Problem::SubgraphIsomorphism => false,
             Problem::InducedSubgraphIsomorphism => true,
         };
-        IsomorphismIter::new(self.query, self.data, self.node_eq, self.edge_eq, induced)
+        if !self.merges() {
+            self.config.mergeable.clear();
+        }
+        if self.config.node_mismatch_budget > 0 {
+            self.config.label_index = None;
+        }
+        if let Some(node_classes) = self.config.node_classes.take() {
+            self.restrict_to_classes(&node_classes);
+        }
+        if self.config.feasibility_sets {
+            self.restrict_to_feasibility_sets();
+        }
+        let empty_query = self.config.empty_query;
+        let empty = self.query.node_count() == 0;
+        if empty && empty_query == EmptyBehavior::Panic {
+            panic!("query graph cannot be empty");
+        }
+        let mut iter = IsomorphismIter::new(
+            self.query,
+            self.data,
+            self.node_eq,
+            self.edge_eq,
+            induced,
+            self.config,
+            self.callbacks,
+        );
+        if mismatched || (empty && empty_query == EmptyBehavior::NoMatch) {
+            iter.finish();
+        }
+        iter
+    }
+
+    /// Restricts the candidates of each query node
+    /// to the data nodes of its class.
+    fn restrict_to_classes(&mut self, node_classes: &LabelIndex) {
+        if self.config.candidates.is_empty() {
+            self.config.candidates = vec![None; self.query.node_count()];
+        }
+        for (query_node, candidates) in self.config.candidates.iter_mut().enumerate() {
+            let class = node_classes.bucket(query_node);
+            *candidates = Some(match candidates.take() {
+                Some(data_nodes) => data_nodes
+                    .into_iter()
+                    .filter(|data_node| class.binary_search(data_node).is_ok())
+                    .collect(),
+                None => class.to_vec(),
+            });
+        }
+    }
+
+    /// Restricts the candidates of each query node to its feasibility set.
+    ///
+    /// This starts from the user candidates if set, or else the label index
+    /// bucket, and keeps the data nodes with a compatible degree.
+    fn restrict_to_feasibility_sets(&mut self) {
+        let query_len = self.query.node_count();
+        if self.config.candidates.is_empty() {
+            self.config.candidates = vec![None; query_len];
+        }
+        let label_index = self.config.label_index.clone();
+        for query_node in 0..query_len {
+            let wildcard = self.config.wildcards.get(query_node) == Some(&true);
+            let bucket = label_index
+                .as_deref()
+                .filter(|_| !wildcard)
+                .map(|label_index| label_index.bucket(query_node));
+            let nodes: Vec<NodeIndex> = match (&self.config.candidates[query_node], bucket) {
+                (Some(candidates), _) => candidates.clone(),
+                (None, Some(bucket)) => bucket.to_vec(),
+                (None, None) => (0..self.data.node_count()).collect(),
+            };
+            let set = nodes
+                .into_iter()
+                .filter(|&data_node| self.degrees_fit(query_node, data_node))
+                .collect();
+            self.config.candidates[query_node] = Some(set);
+        }
     }
+
+    /// Returns `false` if the degrees alone rule out
+    /// mapping `query_node` to `data_node`.
+    fn degrees_fit(&self, query_node: NodeIndex, data_node: NodeIndex) -> bool {
+        if self.merges() || !self.config.optional_edges.is_empty() || self.config.ignore_direction {
+            return true;
+        }
+        let (query, data) = (&*self.query, &*self.data);
+        let fits = |query_degree: usize, data_degree: usize| {
+            if self.matches_edges_exactly() {
+                query_degree == data_degree
+            } else {
+                query_degree <= data_degree
+            }
+        };
+        // If the graph is undirected, the out-degree is the degree.
+        fits(query.out_degree(query_node), data.out_degree(data_node))
+            && (!query.is_directed()
+                || fits(query.in_degree(query_node), data.in_degree(data_node)))
+    }
+
+    /// Returns an error if `query_node` is not in the query graph.
+    fn check_query_node(&self, query_node: NodeIndex) -> Result<(), Vf2Error> {
+        if self.query.contains_node(query_node) {
+            Ok(())
+        } else {
+            Err(Vf2Error::QueryNodeOutOfRange(query_node))
+        }
+    }
+
+    /// Returns an error if `data_node` is not in the data graph.
+    fn check_data_node(&self, data_node: NodeIndex) -> Result<(), Vf2Error> {
+        if self.data.contains_node(data_node) {
+            Ok(())
+        } else {
+            Err(Vf2Error::DataNodeOutOfRange(data_node))
+        }
+    }
+
+    /// Returns an error if a graph is too large to search
+    /// or the graphs disagree on direction.
+    fn check_graphs(&self) -> Result<(), Vf2Error> {
+        if !fits_reserved_values(self.query.node_count())
+            || !fits_reserved_values(self.data.node_count())
+        {
+            Err(Vf2Error::GraphTooLarge)
+        } else if self.query.is_directed() != self.data.is_directed()
+            && !self.config.ignore_direction
+        {
+            // The search reads both graphs with the query's direction,
+            // so it would miss or invent edges of the data graph.
+            Err(Vf2Error::DirectionMismatch)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns `true` if the query and data graphs must have
+    /// the same edge counts and degrees.
+    ///
+    /// Graph isomorphisms map every data edge to a query edge,
+    /// unless some extra data edges are allowed by
+    /// [`transparent_edge`](Self::transparent_edge)
+    /// or [`induced_edge_slack`](Self::induced_edge_slack).
+    fn matches_edges_exactly(&self) -> bool {
+        self.problem == Problem::Isomorphism
+            && self.config.transparent_edge.is_none()
+            && self.config.induced_edge_slack == 0
+    }
+
+    /// Returns `true` if some query nodes may be merged.
+    /// Only subgraph isomorphisms may merge query nodes.
+    fn merges(&self) -> bool {
+        self.problem == Problem::SubgraphIsomorphism && !self.config.mergeable.is_empty()
+    }
+
+    /// Returns `false` if the query graph has more nodes than the data graph,
+    /// which rules out any isomorphism.
+    fn node_counts_compatible(&self) -> bool {
+        self.query.node_count() <= self.data.node_count()
+    }
+
+    /// Returns `false` if the node labels alone rule out any isomorphism.
+    ///
+    /// With a label index, each label must be on at least as many
+    /// data nodes as query nodes. Wildcard query nodes and query nodes
+    /// with [`candidates`](Self::candidates) are not counted,
+    /// since they may map to data nodes with other labels.
+    fn labels_compatible(&self) -> bool {
+        let Some(label_index) = self.config.label_index.as_deref() else {
+            return true;
+        };
+        if self.config.node_mismatch_budget > 0 {
+            return true;
+        }
+        let config = &self.config;
+        label_index.contains_query_labels(|query_node| {
+            config.wildcards.get(query_node) == Some(&true)
+                || matches!(config.candidates.get(query_node), Some(Some(_)))
+        })
+    }
+
+    /// Returns `false` if the node classes alone rule out any isomorphism.
+    ///
+    /// Each class must have at least as many data nodes as query nodes.
+    fn classes_compatible(&self) -> bool {
+        self.config
+            .node_classes
+            .as_deref()
+            .is_none_or(|node_classes| node_classes.contains_query_labels(|_| false))
+    }
+
+    /// Returns `false` if the edge counts alone rule out any isomorphism.
+    ///
+    /// The query cannot have more edges than the data graph,
+    /// and graph isomorphisms require the same number of edges.
+    fn edge_counts_compatible(&self) -> bool {
+        let query_edges = self.query.edge_count();
+        let data_edges = self.data.edge_count();
+        if self.matches_edges_exactly() {
+            query_edges == data_edges
+        } else {
+            query_edges <= data_edges
+        }
+    }
+
+    /// Returns `false` if the degree sequences in `direction`
+    /// alone rule out any isomorphism.
+    ///
+    /// With both sequences sorted in descending order, each query degree
+    /// cannot exceed the data degree at the same position,
+    /// and graph isomorphisms require the same sequences.
+    fn degrees_compatible(&self, direction: Direction) -> bool {
+        let (query_computed, data_computed);
+        let cached = self.config.query_cache.as_ref();
+        let query_degrees = match cached.and_then(|cache| cache.degree_sequence(direction)) {
+            Some(degrees) => degrees,
+            None => {
+                query_computed = degree_sequence(&*self.query, direction);
+                &query_computed
+            }
+        };
+        let cached = self.config.data_cache.as_ref();
+        let data_degrees = match cached.and_then(|cache| cache.degree_sequence(direction)) {
+            Some(degrees) => degrees,
+            None => {
+                data_computed = degree_sequence(&*self.data, direction);
+                &data_computed
+            }
+        };
+        if self.matches_edges_exactly() {
+            query_degrees == data_degrees
+        } else {
+            query_degrees
+                .iter()
+                .zip(data_degrees)
+                .all(|(query, data)| query <= data)
+        }
+    }
+}
+
+/// An isomorphism ranked by its score, then by how early it was found.
+#[derive(Debug)]
+struct Scored {
+    /// Score of the isomorphism.
+    score: f64,
+    /// Number of isomorphisms found before this one.
+    found: usize,
+    /// The isomorphism.
+    isomorphism: Isomorphism,
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.found.cmp(&self.found))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Scored {}
+
+/// Returns pairs of nodes of `graph` whose images must increase
+/// to map identical connected components in only one order.
+///
+/// Each component is matched against the earlier components.
+/// For components isomorphic to the same earliest component,
+/// the images of its lowest node under those isomorphisms are chained,
+/// so every permutation of the components is an automorphism
+/// that reorders the chain.
+fn component_order<G>(graph: &G) -> Vec<(NodeIndex, NodeIndex)>
+where
+    G: Graph,
+    G::NodeLabel: PartialEq,
+    G::EdgeLabel: PartialEq,
+{
+    let mut sets = UnionFind::new(graph.node_count());
+    for node in graph.node_indices() {
+        for neighbor in graph.neighbors(node, Direction::Outgoing) {
+            sets.union(node, neighbor);
+        }
+    }
+    let roots: Vec<_> = graph.node_indices().map(|node| sets.find(node)).collect();
+    // The lowest node of each component and the last node in its chain.
+    let mut chains: Vec<(NodeIndex, NodeIndex)> = Vec::new();
+    let mut order = Vec::new();
+    for node in graph.node_indices() {
+        if roots[..node].contains(&roots[node]) {
+            continue;
+        }
+        let component = NodeFiltered::new(graph, |other| roots[other] == roots[node]);
+        let chain = chains.iter_mut().find_map(|(first, last)| {
+            let earlier = NodeFiltered::new(graph, |other| roots[other] == roots[*first]);
+            if earlier.node_count() != component.node_count() {
+                return None;
+            }
+            // Node 0 of each view is the lowest node of its component.
+            let isomorphism = isomorphisms(&earlier, &component).default_eq().first()?;
+            Some((last, component.original_index(isomorphism[0])))
+        });
+        match chain {
+            Some((last, image)) => {
+                order.push((*last, image));
+                *last = image;
+            }
+            None => chains.push((node, node)),
+        }
+    }
+    order
+}
+
+/// Returns the order the default search adds the nodes of `graph`
+/// to the partial map, starting at `first`.
+///
+/// Like the search, this takes the lowest-index node that is a successor
+/// of a covered node, or else a predecessor, or else any uncovered node.
+/// If `undirected` is `true`, every neighbor counts as a successor.
+fn terminal_set_order<G: Graph>(
+    graph: &G,
+    first: NodeIndex,
+    preference: SourcePreference,
+    undirected: bool,
+) -> Vec<NodeIndex> {
+    let node_count = graph.node_count();
+    let mut covered = vec![false; node_count];
+    let mut outgoing = BTreeSet::new();
+    let mut incoming = BTreeSet::new();
+    let mut order = Vec::with_capacity(node_count);
+    // Nodes below this are all covered.
+    let mut uncovered = 0;
+    let mut next = (node_count > 0).then_some(first);
+    while let Some(node) = next {
+        covered[node] = true;
+        order.push(node);
+        outgoing.remove(&node);
+        incoming.remove(&node);
+        for direction in [Direction::Outgoing, Direction::Incoming] {
+            let set = match direction {
+                Direction::Incoming if !undirected => &mut incoming,
+                _ => &mut outgoing,
+            };
+            set.extend(
+                graph
+                    .neighbors(node, direction)
+                    .filter(|&neighbor| !covered[neighbor]),
+            );
+        }
+        while uncovered < node_count && covered[uncovered] {
+            uncovered += 1;
+        }
+        let (preferred, other) = match preference {
+            SourcePreference::Outgoing => (&outgoing, &incoming),
+            SourcePreference::Incoming => (&incoming, &outgoing),
+        };
+        next = preferred
+            .first()
+            .or(other.first())
+            .copied()
+            .or((uncovered < node_count).then_some(uncovered));
+    }
+    order
+}
+
+/// Returns the degrees of the nodes of `graph` in `direction`,
+/// sorted in descending order.
+///
+/// If the graph is undirected, ignores `direction` and uses all neighbors.
+fn degree_sequence<G: Graph>(graph: &G, direction: Direction) -> Vec<usize> {
+    let mut degrees: Vec<usize> = graph
+        .node_indices()
+        .map(|node| match direction {
+            Direction::Outgoing => graph.out_degree(node),
+            Direction::Incoming => graph.in_degree(node),
+        })
+        .collect();
+    degrees.sort_unstable_by(|left, right| right.cmp(left));
+    degrees
 }
 
 /// Problem type.
@@ -207,3 +2156,39 @@ pub enum Problem {
     /// Induced subgraph isomorphism.
     InducedSubgraphIsomorphism,
 }
+
+/// Which terminal set the search takes the next query node from first,
+/// set with [`Vf2Builder::terminal_preference`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SourcePreference {
+    /// Successors of covered nodes, then predecessors.
+    ///
+    /// This is the order in the original VF2 paper.
+    Outgoing,
+    /// Predecessors of covered nodes, then successors.
+    Incoming,
+}
+
+/// What the search does when the query graph has no nodes,
+/// set with [`Vf2Builder::empty_query_behavior`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EmptyBehavior {
+    /// Panic, since an empty query is usually a mistake.
+    Panic,
+    /// Find no isomorphisms.
+    NoMatch,
+    /// Find one isomorphism, the empty map.
+    EmptyMatch,
+}
+
+/// The result of a node equality function
+/// set with [`Vf2Builder::node_eq_abort`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NodeMatch {
+    /// The nodes match.
+    Match,
+    /// The nodes do not match.
+    NoMatch,
+    /// End the search.
+    Abort,
+}
@@ -0,0 +1,373 @@
+use crate::label_index::LabelIndex;
+use crate::state::GraphCache;
+use crate::{EdgeCtx, EmptyBehavior, Graph, NodeIndex, NodeMatch, SourcePreference};
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// Search options set on the builder and used by the state.
+#[derive(Debug)]
+pub(crate) struct Config<'a, Query, Data>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    /// Data edges for which this returns `true` are treated
+    /// as absent when checking that a subgraph is induced.
+    pub(crate) transparent_edge: Option<Predicate<EdgePredicate<'a, Data>>>,
+    /// Number of extra data edges allowed between covered nodes
+    /// when checking that a subgraph is induced.
+    pub(crate) induced_edge_slack: usize,
+    /// Number of node label mismatches allowed in each isomorphism.
+    pub(crate) node_mismatch_budget: usize,
+    /// Whether to try candidate data nodes with
+    /// the most covered neighbors first.
+    pub(crate) prefer_dense: bool,
+    /// Constrains the first pair in the SSR tree.
+    pub(crate) root: Option<Root>,
+    /// Whether to precompute a dense adjacency matrix of the data graph.
+    pub(crate) dense_adjacency: bool,
+    /// Whether to precompute the node equality of every node pair.
+    pub(crate) precompute_node_compat: bool,
+    /// Whether to precompute the edge equality of every edge pair.
+    pub(crate) precompute_edge_compat: bool,
+    /// Which terminal set the next query node is taken from first.
+    pub(crate) terminal_preference: SourcePreference,
+    /// What the search does when the query graph has no nodes.
+    pub(crate) empty_query: EmptyBehavior,
+    /// Number of search steps after which the search gives up,
+    /// or [`None`] if it is unlimited.
+    pub(crate) node_budget: Option<usize>,
+    /// Node equality function that can abort the search.
+    pub(crate) node_match: Option<Predicate<NodeMatchFn<'a, Query, Data>>>,
+    /// Edge equality function called with both query edge orientations
+    /// on undirected graphs.
+    pub(crate) edge_eq_symmetric: Option<Predicate<SymmetricEdgeEqFn<'a, Query, Data>>>,
+    /// Edge equality function that also sees the endpoint labels.
+    pub(crate) edge_eq_ctx: Option<Predicate<EdgeEqCtxFn<'a, Query, Data>>>,
+    /// The value at index `i` is `true` if query node `i`
+    /// matches any data node regardless of label.
+    ///
+    /// Empty if there are no wildcard query nodes.
+    pub(crate) wildcards: Vec<bool>,
+    /// The value at index `i` is `true` if query node `i`
+    /// may map to a data node another query node maps to.
+    ///
+    /// Empty if there are no mergeable query nodes.
+    pub(crate) mergeable: Vec<bool>,
+    /// The value at index `i` is `true` if data node `i`
+    /// is occupied and may not be mapped to.
+    ///
+    /// Empty if no data node is occupied.
+    pub(crate) occupied: Vec<bool>,
+    /// Whether to read both graphs as undirected.
+    pub(crate) ignore_direction: bool,
+    /// Query edges that need not map to data edges, as `(source, target)`
+    /// pairs with `source <= target` if the graphs are undirected.
+    pub(crate) optional_edges: BTreeSet<(NodeIndex, NodeIndex)>,
+    /// Pairs of query nodes `(earlier, later)` where `earlier` must map
+    /// to a lower data node than `later`, so identical components
+    /// of the query graph are mapped in only one order.
+    ///
+    /// Empty if identical components are not deduplicated.
+    pub(crate) component_order: Vec<(NodeIndex, NodeIndex)>,
+    /// Whether to match each connected component of the query graph
+    /// on its own and combine the matches.
+    pub(crate) decompose_components: bool,
+    /// Pairs of query nodes `(earlier, later)` given by the user
+    /// where `earlier` must map to a lower data node than `later`.
+    pub(crate) index_order: Vec<(NodeIndex, NodeIndex)>,
+    /// The value at index `i` is the sorted data nodes
+    /// query node `i` may map to, or [`None`] if it is not restricted.
+    ///
+    /// Empty if no query node is restricted.
+    pub(crate) candidates: Vec<Option<Vec<NodeIndex>>>,
+    /// The order query nodes are added to the partial map.
+    ///
+    /// Empty to choose the next query node from the terminal sets.
+    pub(crate) query_order: Vec<NodeIndex>,
+    /// Data nodes grouped by label to restrict candidates.
+    pub(crate) label_index: Option<Arc<LabelIndex>>,
+    /// Data nodes grouped by class, where each query node
+    /// may only map to a data node of its own class.
+    pub(crate) node_classes: Option<Arc<LabelIndex>>,
+    /// Whether to restrict the candidates of each query node to
+    /// the data nodes with the same label and a compatible degree.
+    pub(crate) feasibility_sets: bool,
+    /// Neighbors of the query graph shared with other searches, if any.
+    pub(crate) query_cache: Option<Arc<GraphCache>>,
+    /// Neighbors of the data graph shared with other searches, if any.
+    pub(crate) data_cache: Option<Arc<GraphCache>>,
+    /// Query type marker.
+    query: PhantomData<&'a Query>,
+}
+
+impl<'a, Query, Data> Default for Config<'a, Query, Data>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    fn default() -> Self {
+        Self {
+            transparent_edge: None,
+            induced_edge_slack: 0,
+            node_mismatch_budget: 0,
+            prefer_dense: false,
+            root: None,
+            dense_adjacency: false,
+            precompute_node_compat: false,
+            precompute_edge_compat: false,
+            terminal_preference: SourcePreference::Outgoing,
+            empty_query: EmptyBehavior::Panic,
+            node_budget: None,
+            node_match: None,
+            edge_eq_symmetric: None,
+            edge_eq_ctx: None,
+            wildcards: Vec::new(),
+            mergeable: Vec::new(),
+            occupied: Vec::new(),
+            ignore_direction: false,
+            optional_edges: BTreeSet::new(),
+            component_order: Vec::new(),
+            decompose_components: false,
+            index_order: Vec::new(),
+            candidates: Vec::new(),
+            query_order: Vec::new(),
+            label_index: None,
+            node_classes: None,
+            feasibility_sets: false,
+            query_cache: None,
+            data_cache: None,
+            query: PhantomData,
+        }
+    }
+}
+
+// The graphs need not implement `Clone`, so this is not derived.
+impl<Query, Data> Clone for Config<'_, Query, Data>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    fn clone(&self) -> Self {
+        Self {
+            transparent_edge: self.transparent_edge.clone(),
+            induced_edge_slack: self.induced_edge_slack,
+            node_mismatch_budget: self.node_mismatch_budget,
+            prefer_dense: self.prefer_dense,
+            root: self.root,
+            dense_adjacency: self.dense_adjacency,
+            precompute_node_compat: self.precompute_node_compat,
+            precompute_edge_compat: self.precompute_edge_compat,
+            terminal_preference: self.terminal_preference,
+            empty_query: self.empty_query,
+            node_budget: self.node_budget,
+            node_match: self.node_match.clone(),
+            edge_eq_symmetric: self.edge_eq_symmetric.clone(),
+            edge_eq_ctx: self.edge_eq_ctx.clone(),
+            wildcards: self.wildcards.clone(),
+            mergeable: self.mergeable.clone(),
+            occupied: self.occupied.clone(),
+            ignore_direction: self.ignore_direction,
+            optional_edges: self.optional_edges.clone(),
+            component_order: self.component_order.clone(),
+            decompose_components: self.decompose_components,
+            index_order: self.index_order.clone(),
+            candidates: self.candidates.clone(),
+            query_order: self.query_order.clone(),
+            label_index: self.label_index.clone(),
+            node_classes: self.node_classes.clone(),
+            feasibility_sets: self.feasibility_sets,
+            query_cache: self.query_cache.clone(),
+            data_cache: self.data_cache.clone(),
+            query: PhantomData,
+        }
+    }
+}
+
+impl<'a, Query, Data> Config<'a, Query, Data>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    /// Returns these options for the query graph restricted to `nodes`,
+    /// where `restricted` returns the new index of an original query node.
+    ///
+    /// Options on query nodes that are not kept are dropped, and identical
+    /// components are no longer deduplicated, since the components change.
+    /// The caller must check that an anchored query node is kept.
+    pub(crate) fn restrict_query<Restricted>(
+        self,
+        nodes: &[NodeIndex],
+        restricted: impl Fn(NodeIndex) -> Option<NodeIndex>,
+        directed: bool,
+    ) -> Config<'a, Restricted, Data>
+    where
+        Restricted: Graph<NodeLabel = Query::NodeLabel, EdgeLabel = Query::EdgeLabel>,
+        Data: 'a,
+    {
+        let select = |values: &[bool]| -> Vec<bool> {
+            if values.is_empty() {
+                Vec::new()
+            } else {
+                nodes.iter().map(|&node| values[node]).collect()
+            }
+        };
+        let edge_eq_ctx = self.edge_eq_ctx.map(|edge_eq_ctx| {
+            let restricted: Arc<EdgeEqCtxFn<'a, Restricted, Data>> =
+                Arc::new(move |query: EdgeCtx<'_, Restricted>, data| {
+                    let query = EdgeCtx {
+                        label: query.label,
+                        source: query.source,
+                        target: query.target,
+                    };
+                    edge_eq_ctx(query, data)
+                });
+            Predicate::new(restricted)
+        });
+        Config {
+            transparent_edge: self.transparent_edge,
+            induced_edge_slack: self.induced_edge_slack,
+            node_mismatch_budget: self.node_mismatch_budget,
+            prefer_dense: self.prefer_dense,
+            root: self.root.and_then(|root| match root {
+                Root::Pair(query_node, data_node) => {
+                    Some(Root::Pair(restricted(query_node)?, data_node))
+                }
+                Root::DataNode(data_node) => Some(Root::DataNode(data_node)),
+            }),
+            dense_adjacency: self.dense_adjacency,
+            precompute_node_compat: self.precompute_node_compat,
+            precompute_edge_compat: self.precompute_edge_compat,
+            terminal_preference: self.terminal_preference,
+            empty_query: self.empty_query,
+            node_budget: self.node_budget,
+            node_match: self.node_match,
+            edge_eq_symmetric: self.edge_eq_symmetric,
+            edge_eq_ctx,
+            wildcards: select(&self.wildcards),
+            mergeable: select(&self.mergeable),
+            occupied: self.occupied,
+            ignore_direction: self.ignore_direction,
+            optional_edges: self
+                .optional_edges
+                .iter()
+                .filter_map(|&(source, target)| {
+                    let (source, target) = (restricted(source)?, restricted(target)?);
+                    Some(if directed {
+                        (source, target)
+                    } else {
+                        (source.min(target), source.max(target))
+                    })
+                })
+                .collect(),
+            component_order: Vec::new(),
+            decompose_components: self.decompose_components,
+            index_order: self
+                .index_order
+                .iter()
+                .filter_map(|&(earlier, later)| Some((restricted(earlier)?, restricted(later)?)))
+                .collect(),
+            candidates: if self.candidates.is_empty() {
+                Vec::new()
+            } else {
+                nodes
+                    .iter()
+                    .map(|&node| self.candidates[node].clone())
+                    .collect()
+            },
+            query_order: self
+                .query_order
+                .iter()
+                .filter_map(|&node| restricted(node))
+                .collect(),
+            label_index: self
+                .label_index
+                .map(|label_index| Arc::new(label_index.restrict_query(nodes))),
+            node_classes: self
+                .node_classes
+                .map(|node_classes| Arc::new(node_classes.restrict_query(nodes))),
+            feasibility_sets: self.feasibility_sets,
+            query_cache: None,
+            data_cache: self.data_cache,
+            query: PhantomData,
+        }
+    }
+}
+
+/// User callbacks set on the builder and called by the state.
+#[derive(Clone, Debug)]
+pub(crate) struct Callbacks<OnPartial, OnProgress> {
+    /// Called with the partial map and depth when
+    /// the search backtracks from a leaf of the SSR tree.
+    pub(crate) on_partial: Option<OnPartial>,
+    /// Called with the fraction of root candidates explored
+    /// whenever the search finishes a root branch.
+    pub(crate) on_progress: Option<OnProgress>,
+}
+
+impl<OnPartial, OnProgress> Default for Callbacks<OnPartial, OnProgress> {
+    fn default() -> Self {
+        Self {
+            on_partial: None,
+            on_progress: None,
+        }
+    }
+}
+
+/// A constraint on the first pair in the SSR tree.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Root {
+    /// The query node must map to the data node.
+    Pair(NodeIndex, NodeIndex),
+    /// Some query node must map to the data node.
+    DataNode(NodeIndex),
+}
+
+/// Edge label predicate type.
+pub(crate) type EdgePredicate<'a, G> = dyn Fn(&<G as Graph>::EdgeLabel) -> bool + 'a;
+
+/// Aborting node equality function type.
+pub(crate) type NodeMatchFn<'a, Query, Data> =
+    dyn Fn(&<Query as Graph>::NodeLabel, &<Data as Graph>::NodeLabel) -> NodeMatch + 'a;
+
+/// Symmetric edge equality function type.
+pub(crate) type SymmetricEdgeEqFn<'a, Query, Data> =
+    dyn Fn(&<Query as Graph>::EdgeLabel, &<Data as Graph>::EdgeLabel, bool) -> bool + 'a;
+
+/// Edge equality function type with endpoint labels.
+pub(crate) type EdgeEqCtxFn<'a, Query, Data> =
+    dyn Fn(EdgeCtx<'_, Query>, EdgeCtx<'_, Data>) -> bool + 'a;
+
+/// A user predicate shared between clones of the builder and iterator.
+pub(crate) struct Predicate<F: ?Sized>(Arc<F>);
+
+impl<F: ?Sized> Predicate<F> {
+    /// Creates a new [`Predicate`].
+    pub(crate) fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F: ?Sized> Deref for Predicate<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
+
+impl<F: ?Sized> Clone for Predicate<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> Debug for Predicate<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Predicate")
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{Direction, EdgeId, Graph, NodeIndex};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A read-only graph stored in compressed sparse row format.
+///
+/// The neighbors of each node are stored contiguously and sorted,
+/// so iterating neighbors is cache friendly and checking
+/// whether an edge exists is a binary search.
+/// Parallel edges are allowed, and each keeps its own label.
+///
+/// # Example
+///
+/// ```
+/// use vf2::CsrGraph;
+///
+/// let query = CsrGraph::new(true, vec![(); 2], [(0, 1, ())]);
+/// let data = CsrGraph::new(true, vec![(); 3], [(0, 1, ()), (1, 2, ())]);
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).vec();
+/// assert_eq!(isomorphisms, vec![vec![0, 1], vec![1, 2]]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CsrGraph<N, E> {
+    /// Whether the graph is directed.
+    directed: bool,
+    /// Node labels.
+    node_labels: Vec<N>,
+    /// Edge labels, indexed by edge id.
+    edge_labels: Vec<E>,
+    /// Outgoing neighbors.
+    ///
+    /// If the graph is undirected, this contains all neighbors.
+    outgoing: Rows,
+    /// Incoming neighbors.
+    ///
+    /// Unused if the graph is undirected.
+    incoming: Rows,
+}
+
+impl<N, E> CsrGraph<N, E> {
+    /// Creates a new [`CsrGraph`] with `node_labels`
+    /// and `edges` of the form `(source, target, label)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an edge refers to a node that does not exist.
+    pub fn new<I>(directed: bool, node_labels: Vec<N>, edges: I) -> Self
+    where
+        I: IntoIterator<Item = (NodeIndex, NodeIndex, E)>,
+    {
+        let node_count = node_labels.len();
+        let mut edge_labels = Vec::new();
+        let mut outgoing = Vec::new();
+        let mut incoming = Vec::new();
+        for (source, target, label) in edges {
+            assert!(
+                source < node_count && target < node_count,
+                "edge refers to a node that does not exist"
+            );
+            let edge = edge_labels.len();
+            edge_labels.push(label);
+            outgoing.push((source, target, edge));
+            if directed {
+                incoming.push((target, source, edge));
+            } else if source != target {
+                outgoing.push((target, source, edge));
+            }
+        }
+        Self {
+            directed,
+            node_labels,
+            edge_labels,
+            outgoing: Rows::new(node_count, outgoing),
+            incoming: Rows::new(node_count, incoming),
+        }
+    }
+
+    /// Returns the neighbors of `node` in `direction` as a sorted slice.
+    ///
+    /// If the graph is undirected, ignores `direction` and returns all neighbors.
+    pub fn neighbor_slice(&self, node: NodeIndex, direction: Direction) -> &[NodeIndex] {
+        let rows = self.rows(direction);
+        &rows.neighbors[rows.range(node)]
+    }
+
+    /// Returns the rows of neighbors in `direction`.
+    fn rows(&self, direction: Direction) -> &Rows {
+        match direction {
+            Direction::Incoming if self.directed => &self.incoming,
+            _ => &self.outgoing,
+        }
+    }
+
+    /// Returns the index of the edge from `source` to `target`.
+    fn find_edge(&self, source: NodeIndex, target: NodeIndex) -> Option<usize> {
+        let range = self.outgoing.range(source);
+        self.outgoing.neighbors[range.clone()]
+            .binary_search(&target)
+            .ok()
+            .map(|offset| self.outgoing.edges[range.start + offset])
+    }
+}
+
+impl<N, E> Graph for CsrGraph<N, E> {
+    type NodeLabel = N;
+    type EdgeLabel = E;
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.node_labels.len()
+    }
+
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edge_labels.len()
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.node_labels.get(node)
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.neighbor_slice(node, direction).iter().copied()
+    }
+
+    #[inline]
+    fn in_degree(&self, node: NodeIndex) -> usize {
+        self.neighbor_slice(node, Direction::Incoming).len()
+    }
+
+    #[inline]
+    fn out_degree(&self, node: NodeIndex) -> usize {
+        self.neighbor_slice(node, Direction::Outgoing).len()
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.find_edge(source, target).is_some()
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        self.find_edge(source, target)
+            .map(|edge| &self.edge_labels[edge])
+    }
+
+    #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        // Parallel edges are adjacent in the sorted row.
+        let row = self.neighbor_slice(source, Direction::Outgoing);
+        let start = row.partition_point(|&neighbor| neighbor < target);
+        row[start..].partition_point(|&neighbor| neighbor == target)
+    }
+
+    #[inline]
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        self.neighbors_with_edge(node, direction)
+            .map(|(neighbor, edge)| (neighbor, &self.edge_labels[edge]))
+    }
+
+    #[inline]
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        let rows = self.rows(direction);
+        let range = rows.range(node);
+        rows.neighbors[range.clone()]
+            .iter()
+            .copied()
+            .zip(rows.edges[range].iter().copied())
+    }
+
+    #[inline]
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        self.edge_labels.get(edge)
+    }
+}
+
+/// Neighbor rows in compressed sparse row format.
+#[derive(Clone, Debug)]
+struct Rows {
+    /// The neighbors of node `i` are at `offsets[i]..offsets[i + 1]`.
+    offsets: Vec<usize>,
+    /// Neighbors, sorted within each row.
+    neighbors: Vec<NodeIndex>,
+    /// The value at index `i` is the id of the edge to `neighbors[i]`.
+    edges: Vec<EdgeId>,
+}
+
+impl Rows {
+    /// Creates new [`Rows`] from `entries` of the form `(node, neighbor, edge)`.
+    fn new(node_count: usize, mut entries: Vec<(NodeIndex, NodeIndex, EdgeId)>) -> Self {
+        entries.sort_unstable();
+        let mut offsets = vec![0; node_count + 1];
+        for &(node, _, _) in &entries {
+            offsets[node + 1] += 1;
+        }
+        for node in 0..node_count {
+            offsets[node + 1] += offsets[node];
+        }
+        Self {
+            offsets,
+            neighbors: entries.iter().map(|&(_, neighbor, _)| neighbor).collect(),
+            edges: entries.iter().map(|&(_, _, edge)| edge).collect(),
+        }
+    }
+
+    /// Returns the range of `node`'s row.
+    fn range(&self, node: NodeIndex) -> Range<usize> {
+        self.offsets[node]..self.offsets[node + 1]
+    }
+}
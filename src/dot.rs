@@ -0,0 +1,415 @@
+use crate::CsrGraph;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+/// Parses a graph from Graphviz DOT text.
+///
+/// This is meant for experiments and bug reports, so it supports
+/// the common subset of DOT: an optional `strict`, `graph`, or `digraph`
+/// header with an optional name, node statements, edge chains
+/// such as `a -> b -> c`, attribute lists, and comments.
+/// Without a header, the graph is directed if its edges use `->`.
+/// Subgraphs are not supported.
+///
+/// Nodes are numbered in the order they first appear.
+/// Node and edge labels are the values of their `label` attributes,
+/// or empty if they have none. Other attributes,
+/// ports, and `graph`, `node`, and `edge` defaults are ignored.
+///
+/// # Errors
+///
+/// Returns a [`DotError`] if the text is not valid DOT
+/// or uses an unsupported feature.
+///
+/// # Example
+///
+/// ```
+/// let query = vf2::from_dot("digraph { a -> b [label=x] }").unwrap();
+/// let data = vf2::from_dot(
+///     r#"digraph {
+///         0 [label="start"];
+///         0 -> 1 [label=x];
+///         1 -> 2 [label=y];
+///     }"#,
+/// )
+/// .unwrap();
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+///     .edge_eq(|query, data| query == data)
+///     .vec();
+/// assert_eq!(isomorphisms, vec![vec![0, 1]]);
+/// ```
+pub fn from_dot(text: &str) -> Result<CsrGraph<String, String>, DotError> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        directed: None,
+        names: BTreeMap::new(),
+        node_labels: Vec::new(),
+        edges: Vec::new(),
+    };
+    parser.parse_graph()?;
+    Ok(CsrGraph::new(
+        parser.directed.unwrap_or(false),
+        parser.node_labels,
+        parser.edges,
+    ))
+}
+
+/// An error parsing DOT text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DotError {
+    /// Line of the error, starting at 1.
+    line: usize,
+    /// Description of the error.
+    message: String,
+}
+
+impl DotError {
+    /// Creates a new [`DotError`].
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the line of the error, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for DotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl core::error::Error for DotError {}
+
+/// A DOT token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    /// An identifier, numeral, quoted string, or HTML string.
+    Id(String),
+    /// An edge operator, `->` if directed or `--` if undirected.
+    EdgeOp(bool),
+    /// One of `{`, `}`, `[`, `]`, `=`, `;`, `,`, and `:`.
+    Punct(char),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Token::Id(id) => write!(f, "`{id}`"),
+            Token::EdgeOp(true) => f.write_str("`->`"),
+            Token::EdgeOp(false) => f.write_str("`--`"),
+            Token::Punct(punct) => write!(f, "`{punct}`"),
+        }
+    }
+}
+
+/// Splits `text` into tokens, each with its line.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, DotError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        let start_line = line;
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' => {
+                // Preprocessor output lines are ignored like comments.
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            previous = c;
+                        }
+                        None => return Err(DotError::new(start_line, "unterminated comment")),
+                    }
+                }
+                continue;
+            }
+            '-' if chars.next_if_eq(&'>').is_some() => Token::EdgeOp(true),
+            '-' if chars.next_if_eq(&'-').is_some() => Token::EdgeOp(false),
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' => Token::Punct(c),
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.next_if_eq(&'"').is_some() => id.push('"'),
+                        Some('\\') if chars.next_if_eq(&'\n').is_some() => line += 1,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            id.push(c);
+                        }
+                        None => return Err(DotError::new(start_line, "unterminated string")),
+                    }
+                }
+                Token::Id(id)
+            }
+            '<' => {
+                let mut id = String::new();
+                let mut depth = 1;
+                loop {
+                    match chars.next() {
+                        Some('>') if depth == 1 => break,
+                        Some(c) => {
+                            depth += usize::from(c == '<');
+                            depth -= usize::from(c == '>');
+                            line += usize::from(c == '\n');
+                            id.push(c);
+                        }
+                        None => return Err(DotError::new(start_line, "unterminated HTML string")),
+                    }
+                }
+                Token::Id(id)
+            }
+            c if is_id_char(c) || c == '-' || c == '.' => {
+                let mut id = c.to_string();
+                while let Some(c) = chars.next_if(|&c| is_id_char(c) || c == '.') {
+                    id.push(c);
+                }
+                Token::Id(id)
+            }
+            c => return Err(DotError::new(line, format!("unexpected character `{c}`"))),
+        };
+        tokens.push((token, start_line));
+    }
+    Ok(tokens)
+}
+
+/// Returns `true` if `c` may appear in an unquoted identifier.
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+/// A DOT parser that collects nodes and edges.
+struct Parser {
+    /// Tokens, each with its line.
+    tokens: Vec<(Token, usize)>,
+    /// Index of the next token.
+    position: usize,
+    /// Whether the graph is directed,
+    /// or [`None`] if neither the header nor an edge has said yet.
+    directed: Option<bool>,
+    /// Index of each node name.
+    names: BTreeMap<String, usize>,
+    /// Label of each node.
+    node_labels: Vec<String>,
+    /// Edges as `(source, target, label)`.
+    edges: Vec<(usize, usize, String)>,
+}
+
+impl Parser {
+    /// Parses the whole graph.
+    fn parse_graph(&mut self) -> Result<(), DotError> {
+        let strict = self.next_if_keyword("strict");
+        if strict || self.peek_keyword("graph") || self.peek_keyword("digraph") {
+            self.expect_header()?;
+            self.parse_statements(Some('}'))
+        } else {
+            self.parse_statements(None)
+        }
+    }
+
+    /// Parses the `graph` or `digraph` keyword,
+    /// an optional name, and the opening brace.
+    fn expect_header(&mut self) -> Result<(), DotError> {
+        if self.next_if_keyword("graph") {
+            self.directed = Some(false);
+        } else if self.next_if_keyword("digraph") {
+            self.directed = Some(true);
+        } else {
+            return Err(self.unexpected("`graph` or `digraph`"));
+        }
+        if let Some(Token::Id(_)) = self.peek() {
+            self.position += 1;
+        }
+        self.expect_punct('{')
+    }
+
+    /// Parses statements until `close`, or the end of the text if [`None`].
+    fn parse_statements(&mut self, close: Option<char>) -> Result<(), DotError> {
+        loop {
+            match self.peek() {
+                None if close.is_none() => return Ok(()),
+                Some(&Token::Punct(c)) if Some(c) == close => {
+                    self.position += 1;
+                    return match self.peek() {
+                        None => Ok(()),
+                        Some(_) => Err(self.unexpected("the end of the text")),
+                    };
+                }
+                Some(Token::Punct(';')) => self.position += 1,
+                _ => self.parse_statement()?,
+            }
+        }
+    }
+
+    /// Parses a node, edge, or attribute statement.
+    fn parse_statement(&mut self) -> Result<(), DotError> {
+        if self.peek_keyword("subgraph") || self.peek() == Some(&Token::Punct('{')) {
+            return Err(self.error("subgraphs are not supported"));
+        }
+        if self.peek_keyword("graph") || self.peek_keyword("node") || self.peek_keyword("edge") {
+            self.position += 1;
+            self.parse_attributes()?;
+            return Ok(());
+        }
+        let name = self.expect_id()?;
+        if self.next_if_punct('=') {
+            // A graph attribute.
+            self.expect_id()?;
+            return Ok(());
+        }
+        self.skip_port()?;
+        let mut nodes = Vec::from([self.node(name)]);
+        while let Some(&Token::EdgeOp(directed)) = self.peek() {
+            if *self.directed.get_or_insert(directed) != directed {
+                return Err(self.error("edge operator does not match the graph type"));
+            }
+            self.position += 1;
+            let name = self.expect_id()?;
+            self.skip_port()?;
+            nodes.push(self.node(name));
+        }
+        let label = self.parse_attributes()?;
+        if nodes.len() == 1 {
+            if let Some(label) = label {
+                self.node_labels[nodes[0]] = label;
+            }
+        } else {
+            let label = label.unwrap_or_default();
+            for pair in nodes.windows(2) {
+                self.edges.push((pair[0], pair[1], label.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses any attribute lists and returns the last `label` value.
+    fn parse_attributes(&mut self) -> Result<Option<String>, DotError> {
+        let mut label = None;
+        while self.next_if_punct('[') {
+            while !self.next_if_punct(']') {
+                let key = self.expect_id()?;
+                self.expect_punct('=')?;
+                let value = self.expect_id()?;
+                if key == "label" {
+                    label = Some(value);
+                }
+                if !self.next_if_punct(',') {
+                    self.next_if_punct(';');
+                }
+            }
+        }
+        Ok(label)
+    }
+
+    /// Skips a port and compass point after a node name, if any.
+    fn skip_port(&mut self) -> Result<(), DotError> {
+        while self.next_if_punct(':') {
+            self.expect_id()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the node named `name`, adding it if it is new.
+    fn node(&mut self, name: String) -> usize {
+        let next = self.node_labels.len();
+        let index = *self.names.entry(name).or_insert(next);
+        if index == next {
+            self.node_labels.push(String::new());
+        }
+        index
+    }
+
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    /// Returns `true` if the next token is the case-insensitive `keyword`.
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Consumes the next token if it is the case-insensitive `keyword`.
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        let matches = self.peek_keyword(keyword);
+        self.position += usize::from(matches);
+        matches
+    }
+
+    /// Consumes the next token if it is `punct`.
+    fn next_if_punct(&mut self, punct: char) -> bool {
+        let matches = self.peek() == Some(&Token::Punct(punct));
+        self.position += usize::from(matches);
+        matches
+    }
+
+    /// Consumes the next token, which must be `punct`.
+    fn expect_punct(&mut self, punct: char) -> Result<(), DotError> {
+        if self.next_if_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{punct}`")))
+        }
+    }
+
+    /// Consumes the next token, which must be an identifier, and returns it.
+    fn expect_id(&mut self) -> Result<String, DotError> {
+        match self.peek() {
+            Some(Token::Id(id)) => {
+                let id = id.clone();
+                self.position += 1;
+                Ok(id)
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    /// Returns an error at the next token saying `expected` was expected.
+    fn unexpected(&self, expected: &str) -> DotError {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {expected}, found {token}")),
+            None => self.error(format!("expected {expected}, found the end of the text")),
+        }
+    }
+
+    /// Returns an error at the next token.
+    fn error(&self, message: impl Into<String>) -> DotError {
+        let line = match self.tokens.get(self.position) {
+            Some(&(_, line)) => line,
+            None => self.tokens.last().map_or(1, |&(_, line)| line),
+        };
+        DotError::new(line, message)
+    }
+}
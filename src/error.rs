@@ -0,0 +1,81 @@
+use crate::NodeIndex;
+use core::fmt::{Display, Formatter};
+
+/// An error configuring VF2.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Vf2Error {
+    /// A query node index is not in the query graph.
+    QueryNodeOutOfRange(NodeIndex),
+    /// A data node index is not in the data graph.
+    DataNodeOutOfRange(NodeIndex),
+    /// A query node is listed more than once.
+    DuplicateQueryNode(NodeIndex),
+    /// A search snapshot does not fit the query and data graphs.
+    SnapshotMismatch,
+    /// A query node order is not a permutation of the query nodes.
+    InvalidQueryOrder,
+    /// A graph has so many nodes that a node index
+    /// would equal a value reserved by the search.
+    GraphTooLarge,
+    /// One graph is directed and the other is undirected.
+    DirectionMismatch,
+    /// A list of node classes does not have one class per node of its graph.
+    NodeClassesMismatch,
+    /// The node indices of a graph are not contiguous.
+    NonContiguousNodes,
+}
+
+impl Display for Vf2Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Vf2Error::QueryNodeOutOfRange(node) => {
+                write!(f, "query node {node} is not in the query graph")
+            }
+            Vf2Error::DataNodeOutOfRange(node) => {
+                write!(f, "data node {node} is not in the data graph")
+            }
+            Vf2Error::DuplicateQueryNode(node) => {
+                write!(f, "query node {node} is listed more than once")
+            }
+            Vf2Error::SnapshotMismatch => {
+                write!(f, "snapshot does not fit the query and data graphs")
+            }
+            Vf2Error::InvalidQueryOrder => {
+                write!(f, "query order is not a permutation of the query nodes")
+            }
+            Vf2Error::GraphTooLarge => {
+                write!(f, "graph is so large it uses reserved values")
+            }
+            Vf2Error::DirectionMismatch => {
+                write!(f, "one graph is directed and the other is undirected")
+            }
+            Vf2Error::NodeClassesMismatch => {
+                write!(f, "node classes do not have one class per node")
+            }
+            Vf2Error::NonContiguousNodes => {
+                write!(f, "node indices of the graph are not contiguous")
+            }
+        }
+    }
+}
+
+impl core::error::Error for Vf2Error {}
+
+/// An error returned when the search was cut short
+/// before it explored every state.
+///
+/// The search stops early when the budget set with
+/// [`Vf2Builder::node_budget`](crate::Vf2Builder::node_budget) runs out
+/// or the function set with
+/// [`Vf2Builder::node_eq_abort`](crate::Vf2Builder::node_eq_abort)
+/// returns [`NodeMatch::Abort`](crate::NodeMatch::Abort).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SearchIncomplete;
+
+impl Display for SearchIncomplete {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "search was cut short before it was complete")
+    }
+}
+
+impl core::error::Error for SearchIncomplete {}
@@ -1,4 +1,11 @@
+use core::fmt::{Debug, Formatter};
+
 /// A graph.
+///
+/// The graph must not change during a search.
+/// The neighbors of every node are read once when the search starts
+/// and reused for the rest of the search,
+/// so [`neighbors`](Self::neighbors) may be slow or allocate.
 pub trait Graph {
     /// Node label type.
     type NodeLabel;
@@ -13,6 +20,58 @@ pub trait Graph {
     /// Returns the number of nodes in the graph.
     fn node_count(&self) -> usize;
 
+    /// Returns an iterator of the indices of all nodes.
+    ///
+    /// The default implementation returns `0..node_count()`.
+    /// Node indices must be contiguous, since the search keeps
+    /// per-node state in vectors of length [`node_count`](Self::node_count).
+    fn node_indices(&self) -> impl Iterator<Item = NodeIndex> {
+        0..self.node_count()
+    }
+
+    /// Returns `true` if `node` is in the graph.
+    ///
+    /// The default implementation checks that `node` is below
+    /// [`node_count`](Self::node_count).
+    fn contains_node(&self, node: NodeIndex) -> bool {
+        node < self.node_count()
+    }
+
+    /// Returns the number of edges in the graph.
+    ///
+    /// The default implementation sums the outgoing degrees of all nodes.
+    /// If the graph is undirected, an edge between two nodes is listed
+    /// at both ends and a self-loop only at its node, so each self-loop
+    /// is counted once more before the sum is halved.
+    fn edge_count(&self) -> usize {
+        let (mut degrees, mut loops) = (0, 0);
+        for node in self.node_indices() {
+            for neighbor in self.neighbors(node, Direction::Outgoing) {
+                degrees += 1;
+                loops += usize::from(neighbor == node);
+            }
+        }
+        if self.is_directed() {
+            degrees
+        } else {
+            (degrees + loops) / 2
+        }
+    }
+
+    /// Returns the number of neighbors of `node` that are edge sources.
+    ///
+    /// If the graph is undirected, returns the number of all neighbors.
+    fn in_degree(&self, node: NodeIndex) -> usize {
+        self.neighbors(node, Direction::Incoming).count()
+    }
+
+    /// Returns the number of neighbors of `node` that are edge destinations.
+    ///
+    /// If the graph is undirected, returns the number of all neighbors.
+    fn out_degree(&self, node: NodeIndex) -> usize {
+        self.neighbors(node, Direction::Outgoing).count()
+    }
+
     /// Returns a reference to the label of `node`;
     fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel>;
 
@@ -28,19 +87,152 @@ pub trait Graph {
     /// If undirected, an edge must exist between `source` and `target`.
     fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool;
 
+    /// Returns the number of edges from `source` to `target`.
+    ///
+    /// If the graph is directed, the edges must go from `source` to `target`.
+    /// If undirected, the edges must be between `source` and `target`.
+    ///
+    /// The default implementation returns 1 if
+    /// [`contains_edge`](Self::contains_edge) is `true`, or else 0.
+    /// Override it for multigraphs, where [`neighbors`](Self::neighbors)
+    /// returns a neighbor once for each parallel edge.
+    /// Multiplicities are only compared if a neighbor is returned
+    /// more than once in the query or data graph. Mapped edges must then
+    /// have equal multiplicities, or for subgraph isomorphisms,
+    /// a data multiplicity at least that of the query edge.
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        usize::from(self.contains_edge(source, target))
+    }
+
+    /// Returns `true` if there is an edge from `node` to itself.
+    ///
+    /// A query node with a self-loop only maps to data nodes with a self-loop,
+    /// and the edge equality function compares the self-loop labels.
+    /// If the subgraph is induced, a query node without a self-loop
+    /// also only maps to data nodes without a self-loop.
+    fn has_self_loop(&self, node: NodeIndex) -> bool {
+        self.contains_edge(node, node)
+    }
+
     /// Returns a reference to the label of the edge from `source` to `target`.
     ///
     /// If the graph is directed, the edge must go from `source` to `target`.
     /// If undirected, the edge must be between `source` and `target`.
+    ///
+    /// The label of an undirected edge may depend on the orientation.
+    /// The label of the query edge from `a` to `b` is compared with
+    /// the label of the data edge from the node `a` maps to,
+    /// to the node `b` maps to.
     fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel>;
+
+    /// Returns an iterator of neighbors of `node`
+    /// together with the labels of the edges to them.
+    ///
+    /// If the graph is directed, returns neighbors in `direction` only.
+    /// If undirected, ignores `direction` and returns all neighbors.
+    /// The label is that of the edge from `node` to the neighbor,
+    /// or from the neighbor to `node` if `direction` is incoming.
+    ///
+    /// The default implementation looks up the label of each edge
+    /// from [`neighbors`](Self::neighbors) with [`edge_label`](Self::edge_label).
+    /// Override it if the labels can be read along with the neighbors.
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        self.neighbors(node, direction).filter_map(move |neighbor| {
+            let label = match direction {
+                Direction::Outgoing => self.edge_label(node, neighbor),
+                Direction::Incoming => self.edge_label(neighbor, node),
+            };
+            label.map(|label| (neighbor, label))
+        })
+    }
+
+    /// Returns an iterator of neighbors of `node`
+    /// together with the ids of the edges to them.
+    ///
+    /// If the graph is directed, returns neighbors in `direction` only.
+    /// If undirected, ignores `direction` and returns all neighbors.
+    /// Parallel edges have different ids, so in a multigraph,
+    /// where [`edge_label`](Self::edge_label) only returns the label
+    /// of one of the edges between two nodes, each edge can be told apart.
+    ///
+    /// The default implementation numbers the neighbors from
+    /// [`neighbors`](Self::neighbors) in order, so the ids are only unique
+    /// among the edges of `node` in `direction`. Override it together with
+    /// [`edge_label_by_id`](Self::edge_label_by_id) for multigraphs
+    /// whose parallel edges have different labels.
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        self.neighbors(node, direction)
+            .enumerate()
+            .map(|(edge, neighbor)| (neighbor, edge))
+    }
+
+    /// Returns a reference to the label of the edge with id `edge`
+    /// from [`neighbors_with_edge`](Self::neighbors_with_edge).
+    ///
+    /// The default implementation returns [`None`], since the default ids
+    /// do not name edges. When both graphs look up labels by id and one
+    /// has parallel edges, the labels of the parallel edges between mapped
+    /// nodes are matched one to one, so each query edge needs a data edge
+    /// of its own with an equal label. Otherwise, only the labels from
+    /// [`edge_label`](Self::edge_label) are compared.
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        let _ = edge;
+        None
+    }
 }
 
 /// A node index.
 pub type NodeIndex = usize;
 
+/// An edge id, returned by [`Graph::neighbors_with_edge`].
+pub type EdgeId = usize;
+
 /// Edge direction, either outgoing or incoming.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Direction {
     Outgoing,
     Incoming,
 }
+
+/// An edge together with the labels of its endpoints,
+/// passed to the function set with
+/// [`Vf2Builder::edge_eq_ctx`](crate::Vf2Builder::edge_eq_ctx).
+pub struct EdgeCtx<'g, G: Graph + ?Sized> {
+    /// Edge label.
+    pub label: &'g G::EdgeLabel,
+    /// Label of the node the edge is read from.
+    pub source: &'g G::NodeLabel,
+    /// Label of the node the edge is read to.
+    pub target: &'g G::NodeLabel,
+}
+
+impl<G: Graph + ?Sized> Clone for EdgeCtx<'_, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<G: Graph + ?Sized> Copy for EdgeCtx<'_, G> {}
+
+impl<G> Debug for EdgeCtx<'_, G>
+where
+    G: Graph + ?Sized,
+    G::NodeLabel: Debug,
+    G::EdgeLabel: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EdgeCtx")
+            .field("label", self.label)
+            .field("source", self.source)
+            .field("target", self.target)
+            .finish()
+    }
+}
@@ -0,0 +1,34 @@
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+/// A graph that is either borrowed or shared.
+///
+/// Sharing the graph lets the builder and iterator
+/// outlive the scope that created the graph.
+#[derive(Debug)]
+pub(crate) enum GraphRef<'a, G> {
+    /// A borrowed graph.
+    Borrowed(&'a G),
+    /// A shared graph.
+    Shared(Arc<G>),
+}
+
+impl<G> Deref for GraphRef<'_, G> {
+    type Target = G;
+
+    fn deref(&self) -> &G {
+        match self {
+            GraphRef::Borrowed(graph) => graph,
+            GraphRef::Shared(graph) => graph,
+        }
+    }
+}
+
+impl<G> Clone for GraphRef<'_, G> {
+    fn clone(&self) -> Self {
+        match self {
+            GraphRef::Borrowed(graph) => GraphRef::Borrowed(graph),
+            GraphRef::Shared(graph) => GraphRef::Shared(Arc::clone(graph)),
+        }
+    }
+}
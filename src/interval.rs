@@ -0,0 +1,56 @@
+use core::cmp::Ordering;
+
+/// A node label that is a closed interval from `start` to `end`.
+///
+/// Use [`Vf2Builder::node_eq_contains`](crate::Vf2Builder::node_eq_contains)
+/// to match a query node to data nodes whose intervals it contains.
+///
+/// # Example
+///
+/// ```
+/// use vf2::IntervalLabel;
+///
+/// let range = IntervalLabel::new(1, 10);
+/// assert!(range.contains(&IntervalLabel::new(2, 10)));
+/// assert!(!range.contains(&IntervalLabel::new(0, 5)));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct IntervalLabel<T> {
+    /// Lower bound, included in the interval.
+    pub start: T,
+    /// Upper bound, included in the interval.
+    pub end: T,
+}
+
+impl<T> IntervalLabel<T>
+where
+    T: PartialOrd,
+{
+    /// Creates a new [`IntervalLabel`] from `start` to `end`.
+    ///
+    /// The interval is empty if `start` is greater than `end`.
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns `true` if the interval is empty.
+    pub fn is_empty(&self) -> bool {
+        // Incomparable bounds, such as NaN, also make the interval empty.
+        !matches!(
+            self.start.partial_cmp(&self.end),
+            Some(Ordering::Less | Ordering::Equal)
+        )
+    }
+
+    /// Returns `true` if `value` is in the interval.
+    pub fn contains_value(&self, value: &T) -> bool {
+        self.start <= *value && *value <= self.end
+    }
+
+    /// Returns `true` if every value in `other` is in this interval.
+    ///
+    /// An empty interval is contained in every interval.
+    pub fn contains(&self, other: &Self) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+}
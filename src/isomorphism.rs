@@ -1,7 +1,407 @@
-use crate::NodeIndex;
+use crate::state::GraphCache;
+use crate::{Direction, EmptyBehavior, Graph, NodeFiltered, NodeIndex, Problem};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Write};
 
 /// An isomorphism mapping query nodes to data nodes.
 ///
 /// The value at index `i` is the data node index
 /// that query node index `i` maps to.
 pub type Isomorphism = Vec<NodeIndex>;
+
+/// Returns `true` if `mapping` is a valid isomorphism
+/// of type `problem` from `query` to `data`.
+///
+/// The mapping is valid if it maps every query node to a distinct data node,
+/// every query edge to a data edge, and, for graph and induced subgraph
+/// isomorphisms, every data edge between mapped nodes to a query edge.
+/// Node and edge labels are compared with `node_eq` and `edge_eq`.
+///
+/// This does not run a search, so it can check mappings found elsewhere.
+pub fn is_valid_isomorphism<Query, Data, NodeEq, EdgeEq>(
+    query: &Query,
+    data: &Data,
+    mapping: &[NodeIndex],
+    problem: Problem,
+    node_eq: NodeEq,
+    edge_eq: EdgeEq,
+) -> bool
+where
+    Query: Graph,
+    Data: Graph,
+    NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
+    EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+{
+    if mapping.len() != query.node_count()
+        || (problem == Problem::Isomorphism && query.node_count() != data.node_count())
+    {
+        return false;
+    }
+    let mut inverse = vec![None; data.node_count()];
+    for (query_node, &data_node) in mapping.iter().enumerate() {
+        if !data.contains_node(data_node) || inverse[data_node].is_some() {
+            return false;
+        }
+        inverse[data_node] = Some(query_node);
+        let labels_are_eq = match (query.node_label(query_node), data.node_label(data_node)) {
+            (Some(query_label), Some(data_label)) => node_eq(query_label, data_label),
+            _ => false,
+        };
+        if !labels_are_eq {
+            return false;
+        }
+    }
+    for (query_source, &data_source) in mapping.iter().enumerate() {
+        for query_target in query.neighbors(query_source, Direction::Outgoing) {
+            let data_target = mapping[query_target];
+            let labels_are_eq = match (
+                query.edge_label(query_source, query_target),
+                data.edge_label(data_source, data_target),
+            ) {
+                (Some(query_label), Some(data_label)) => edge_eq(query_label, data_label),
+                _ => false,
+            };
+            if !labels_are_eq {
+                return false;
+            }
+        }
+        if problem == Problem::SubgraphIsomorphism {
+            continue;
+        }
+        for data_target in data.neighbors(data_source, Direction::Outgoing) {
+            if let Some(query_target) = inverse[data_target] {
+                if !query.contains_edge(query_source, query_target) {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Returns `mapping` from `query` to `data` as readable text,
+/// with one line per query node.
+///
+/// Each line looks like `q0(Black) -> d3(Black)`, showing the node indices
+/// and labels. A label is printed as `?` if the graph has none for the node.
+/// A query node that maps to a node not in `data`, such as
+/// [`NodeIndex::MAX`] for an uncovered node, is printed as `q0(Black) -> ?`.
+pub fn format_mapping<Query, Data>(query: &Query, data: &Data, mapping: &[NodeIndex]) -> String
+where
+    Query: Graph,
+    Data: Graph,
+    Query::NodeLabel: Debug,
+    Data::NodeLabel: Debug,
+{
+    let mut text = String::new();
+    for (query_node, &data_node) in mapping.iter().enumerate() {
+        write!(text, "q{query_node}").unwrap();
+        write_label(&mut text, query.node_label(query_node));
+        if data.contains_node(data_node) {
+            write!(text, " -> d{data_node}").unwrap();
+            write_label(&mut text, data.node_label(data_node));
+            text.push('\n');
+        } else {
+            text.push_str(" -> ?\n");
+        }
+    }
+    text
+}
+
+/// Appends `label` in parentheses to `text`, or `(?)` if there is none.
+fn write_label(text: &mut String, label: Option<&impl Debug>) {
+    match label {
+        Some(label) => write!(text, "({label:?})").unwrap(),
+        None => text.push_str("(?)"),
+    }
+}
+
+/// Finds the subgraph isomorphisms from each of `queries` to `data`.
+///
+/// The value at index `i` is the isomorphisms from `queries[i]`.
+/// Node and edge labels are not compared.
+///
+/// This is faster than calling [`subgraph_isomorphisms`](crate::subgraph_isomorphisms)
+/// for each query, since the parts of the search setup that only depend
+/// on the data graph are done once and shared.
+/// These are the neighbor lists, the degree sequence,
+/// and a dense adjacency matrix if the data graph has at most 10,000 nodes.
+///
+/// # Panics
+///
+/// Panics if a query graph is empty.
+pub fn match_many<Query, Data>(queries: &[&Query], data: &Data) -> Vec<Vec<Isomorphism>>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    let cache = Arc::new(GraphCache::new(data, true).with_degree_sequences());
+    queries
+        .iter()
+        .map(|&query| {
+            crate::subgraph_isomorphisms(query, data)
+                .data_cache(Arc::clone(&cache))
+                .vec()
+        })
+        .collect()
+}
+
+/// Finds the automorphisms of `graph`, that is,
+/// the isomorphisms from `graph` to itself.
+///
+/// Together they form the automorphism group of `graph`,
+/// which always contains the identity map.
+/// An empty graph has only the empty map.
+/// Node and edge labels are not compared.
+///
+/// This is like [`isomorphisms`](crate::isomorphisms) with `graph`
+/// as both the query and data graph, but the neighbor lists,
+/// the degree sequence, and a dense adjacency matrix
+/// if the graph has at most 10,000 nodes are built once for both.
+pub fn automorphisms<G>(graph: &G) -> Vec<Isomorphism>
+where
+    G: Graph,
+{
+    let cache = Arc::new(GraphCache::new(graph, true).with_degree_sequences());
+    crate::isomorphisms(graph, graph)
+        .query_cache(Arc::clone(&cache))
+        .data_cache(cache)
+        .empty_query_behavior(EmptyBehavior::EmptyMatch)
+        .vec()
+}
+
+/// Finds the subgraph isomorphisms from each of `queries` to `data`,
+/// searching for each query on a separate [rayon](https://docs.rs/rayon) task.
+///
+/// This gives the same results as [`match_many`], in query order,
+/// and likewise shares the parts of the search setup
+/// that only depend on the data graph.
+///
+/// # Panics
+///
+/// Panics if a query graph is empty.
+#[cfg(feature = "parallel")]
+pub fn par_match_many<Query, Data>(queries: &[&Query], data: &Data) -> Vec<Vec<Isomorphism>>
+where
+    Query: Graph + Sync,
+    Data: Graph + Sync,
+{
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+    let cache = Arc::new(GraphCache::new(data, true).with_degree_sequences());
+    queries
+        .par_iter()
+        .map(|&query| {
+            crate::subgraph_isomorphisms(query, data)
+                .data_cache(Arc::clone(&cache))
+                .vec()
+        })
+        .collect()
+}
+
+/// Returns a maximum common induced subgraph of `a` and `b`,
+/// as a map from the nodes of `a` to the nodes of `b`.
+///
+/// This is the largest connected induced subgraph of `a`
+/// that is isomorphic to an induced subgraph of `b`.
+/// The value at index `i` is the node of `b` that node `i` of `a` maps to,
+/// or [`NodeIndex::MAX`] if node `i` is not in the subgraph.
+/// If the graphs are directed, the subgraph is weakly connected.
+/// Node and edge labels are not compared.
+///
+/// Finding a maximum common subgraph is not what VF2 is built for.
+/// This tries the connected subsets of the nodes of `a` in decreasing size
+/// until one embeds in `b`, searching for an induced subgraph isomorphism
+/// from each. That takes exponential time in the worst case,
+/// so only use it on small graphs.
+pub fn max_common_induced_subgraph<A, B>(a: &A, b: &B) -> Isomorphism
+where
+    A: Graph,
+    B: Graph,
+{
+    let node_count = a.node_count();
+    let mut members = vec![false; node_count];
+    for size in (1..=node_count.min(b.node_count())).rev() {
+        // Visit the subsets of `size` nodes in lexicographic order.
+        let mut subset: Vec<NodeIndex> = (0..size).collect();
+        loop {
+            members.fill(false);
+            for &node in &subset {
+                members[node] = true;
+            }
+            if is_connected(a, &subset, &members) {
+                let view = NodeFiltered::new(a, |node| members[node]);
+                if let Some(isomorphism) = crate::induced_subgraph_isomorphisms(&view, b).first() {
+                    let mut map = vec![NodeIndex::MAX; node_count];
+                    for (node, &mapped) in isomorphism.iter().enumerate() {
+                        map[view.original_index(node)] = mapped;
+                    }
+                    return map;
+                }
+            }
+            if !next_subset(&mut subset, node_count) {
+                break;
+            }
+        }
+    }
+    vec![NodeIndex::MAX; node_count]
+}
+
+/// Returns the maximal common connected induced subgraphs
+/// of `query` and `data` with at least `min_size` nodes,
+/// each as a map from the nodes of `query` to the nodes of `data`.
+///
+/// Each map is an induced subgraph isomorphism from a connected
+/// induced subgraph of `query` that cannot be extended to
+/// a larger connected induced subgraph by mapping another node.
+/// Every such map is returned, so symmetric subgraphs appear once
+/// for each way they embed, from the largest subgraphs to the smallest.
+/// As with [`max_common_induced_subgraph`], the value at index `i`
+/// is the node of `data` that node `i` of `query` maps to,
+/// or [`NodeIndex::MAX`] if node `i` is not in the subgraph.
+/// If the graphs are directed, the subgraphs are weakly connected.
+/// Node and edge labels are not compared.
+///
+/// This tries every connected subset of the nodes of `query`
+/// with at least `min_size` nodes, finding all its embeddings in `data`,
+/// and compares each embedding to those one node larger.
+/// That takes exponential time and may hold exponentially many maps
+/// in the worst case, so only use it on small graphs, and raise
+/// `min_size` to skip the many small common subgraphs.
+pub fn maximal_common_connected<Query, Data>(
+    query: &Query,
+    data: &Data,
+    min_size: usize,
+) -> Vec<Isomorphism>
+where
+    Query: Graph,
+    Data: Graph,
+{
+    let node_count = query.node_count();
+    let mut members = vec![false; node_count];
+    let mut maximal = Vec::new();
+    // Embeddings one node larger, which any extendable embedding extends.
+    let mut larger: Vec<Isomorphism> = Vec::new();
+    for size in (min_size.max(1)..=node_count.min(data.node_count())).rev() {
+        let mut found = Vec::new();
+        let mut subset: Vec<NodeIndex> = (0..size).collect();
+        loop {
+            members.fill(false);
+            for &node in &subset {
+                members[node] = true;
+            }
+            if is_connected(query, &subset, &members) {
+                let view = NodeFiltered::new(query, |node| members[node]);
+                for isomorphism in crate::induced_subgraph_isomorphisms(&view, data).iter() {
+                    let mut map = vec![NodeIndex::MAX; node_count];
+                    for (node, &mapped) in isomorphism.iter().enumerate() {
+                        map[view.original_index(node)] = mapped;
+                    }
+                    let extendable = larger
+                        .iter()
+                        .any(|extension| subset.iter().all(|&node| extension[node] == map[node]));
+                    if !extendable {
+                        maximal.push(map.clone());
+                    }
+                    found.push(map);
+                }
+            }
+            if !next_subset(&mut subset, node_count) {
+                break;
+            }
+        }
+        larger = found;
+    }
+    maximal
+}
+
+/// Advances `subset` to the next subset of the same size
+/// of the nodes below `node_count` in lexicographic order.
+///
+/// Returns `false` if `subset` was the last one.
+fn next_subset(subset: &mut [NodeIndex], node_count: usize) -> bool {
+    let size = subset.len();
+    let Some(index) = (0..size)
+        .rev()
+        .find(|&index| subset[index] < node_count - size + index)
+    else {
+        return false;
+    };
+    subset[index] += 1;
+    for next in index + 1..size {
+        subset[next] = subset[next - 1] + 1;
+    }
+    true
+}
+
+/// Returns `true` if the subgraph of `graph` induced by the nonempty
+/// `subset` is connected, ignoring edge directions.
+///
+/// `members` marks the nodes in `subset`.
+fn is_connected<G: Graph>(graph: &G, subset: &[NodeIndex], members: &[bool]) -> bool {
+    let mut visited = vec![false; members.len()];
+    let mut stack = vec![subset[0]];
+    visited[subset[0]] = true;
+    let mut reached = 1;
+    while let Some(node) = stack.pop() {
+        for direction in [Direction::Outgoing, Direction::Incoming] {
+            for neighbor in graph.neighbors(node, direction) {
+                if members[neighbor] && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    reached += 1;
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+    reached == subset.len()
+}
+
+/// Checks whether `left` and `right` are isomorphic
+/// and counts the isomorphisms between them.
+///
+/// Node and edge labels are not compared.
+///
+/// If the graphs are isomorphic, the number of isomorphisms
+/// equals the size of the automorphism group of either graph.
+/// Two empty graphs are isomorphic through the empty map.
+pub fn isomorphism_report<Left, Right>(left: &Left, right: &Right) -> IsomorphismReport
+where
+    Left: Graph,
+    Right: Graph,
+{
+    if left.node_count() != right.node_count() {
+        return IsomorphismReport {
+            witness: None,
+            count: 0,
+        };
+    }
+    let mut iter = crate::isomorphisms(left, right)
+        .empty_query_behavior(EmptyBehavior::EmptyMatch)
+        .iter();
+    let witness = iter.next();
+    let mut count = usize::from(witness.is_some());
+    while iter.next_ref().is_some() {
+        count += 1;
+    }
+    IsomorphismReport { witness, count }
+}
+
+/// The result of [`isomorphism_report`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IsomorphismReport {
+    /// The first isomorphism found, if any.
+    pub witness: Option<Isomorphism>,
+    /// The number of isomorphisms.
+    pub count: usize,
+}
+
+impl IsomorphismReport {
+    /// Returns `true` if the graphs are isomorphic.
+    pub fn is_isomorphic(&self) -> bool {
+        self.witness.is_some()
+    }
+}
@@ -1,32 +1,177 @@
-use crate::state::State;
-use crate::{Graph, Isomorphism};
-use std::fmt::Debug;
+use crate::config::{Callbacks, Config};
+use crate::graph_ref::GraphRef;
+use crate::state::{State, StateSnapshot};
+use crate::{
+    DefaultOnPartial, DefaultOnProgress, Direction, Graph, Isomorphism, NodeIndex, Vf2Error,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 /// An isomorphism iterator.
 ///
 /// This traverses the state space representation and yields
 /// isomorphisms as they are found.
 #[derive(Clone, Debug)]
-pub struct IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq> {
-    state: State<'a, Query, Data, NodeEq, EdgeEq>,
+pub struct IsomorphismIter<
+    'a,
+    Query,
+    Data,
+    NodeEq,
+    EdgeEq,
+    OnPartial = DefaultOnPartial,
+    OnProgress = DefaultOnProgress,
+> where
+    Query: Graph,
+    Data: Graph,
+{
+    state: State<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+    /// Number of isomorphisms yielded so far.
+    yielded: usize,
 }
 
-impl<'a, Query, Data, NodeEq, EdgeEq> IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq>
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+    IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
 where
     Query: Graph,
     Data: Graph,
     NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
     EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
 {
     pub(crate) fn new(
-        query: &'a Query,
-        data: &'a Data,
+        query: GraphRef<'a, Query>,
+        data: GraphRef<'a, Data>,
         node_eq: Option<NodeEq>,
         edge_eq: Option<EdgeEq>,
         induced: bool,
+        config: Config<'a, Query, Data>,
+        callbacks: Callbacks<OnPartial, OnProgress>,
     ) -> Self {
         Self {
-            state: State::new(query, data, node_eq, edge_eq, induced),
+            state: State::new(query, data, node_eq, edge_eq, induced, config, callbacks),
+            yielded: 0,
+        }
+    }
+
+    /// Restores the search frontier from `snapshot`.
+    pub(crate) fn restore(&mut self, snapshot: StateSnapshot) -> Result<(), Vf2Error> {
+        self.state.restore(snapshot)
+    }
+
+    /// Returns a checkpoint of the search that can be resumed later
+    /// with [`Vf2Builder::resume`](crate::Vf2Builder::resume).
+    ///
+    /// The resumed search yields exactly the isomorphisms
+    /// this iterator has not yielded yet.
+    pub fn checkpoint(&self) -> StateSnapshot {
+        self.state.snapshot()
+    }
+
+    /// Forbids mapping to `data_nodes` for the rest of the search.
+    pub(crate) fn forbid_data_nodes(&mut self, data_nodes: &[NodeIndex]) {
+        self.state.forbid_data_nodes(data_nodes);
+    }
+
+    /// Forbids mapping query node 0 to `data_node`
+    /// for the rest of the search.
+    pub(crate) fn forbid_root(&mut self, data_node: NodeIndex) {
+        self.state.forbid_root(data_node);
+    }
+
+    /// Forbids mapping to `data_edges` for the rest of the search.
+    pub(crate) fn forbid_data_edges(&mut self, data_edges: &[(NodeIndex, NodeIndex)]) {
+        self.state.forbid_data_edges(data_edges);
+    }
+
+    /// Ends the search so no further isomorphisms are yielded.
+    pub(crate) fn finish(&mut self) {
+        self.state.finish();
+    }
+
+    /// Returns an upper bound on the number of isomorphisms
+    /// this iterator has not yielded yet.
+    ///
+    /// This is a loose upper bound computed from the sizes of the
+    /// untried candidate sets in the search, assuming any remaining
+    /// query nodes could map to any remaining data nodes.
+    /// It can be far larger than the true count, or saturate at
+    /// [`usize::MAX`], so only use it as a hint, such as
+    /// for [`Vec::with_capacity`] when it is small.
+    pub fn remaining_upper_bound(&self) -> usize {
+        self.state.remaining_upper_bound()
+    }
+
+    /// Returns the depth and terminal set sizes of the current search state.
+    ///
+    /// After [`next_ref`](Self::next_ref) returns an isomorphism,
+    /// this describes the state in which it was found.
+    pub fn current_state_info(&self) -> StateInfo {
+        self.state.info()
+    }
+
+    /// Returns the deepest depth in the SSR tree the search has reached,
+    /// which is the most query nodes a partial map has covered.
+    ///
+    /// The search keeps one stack entry per depth, so this is the peak
+    /// stack size. It never exceeds the number of query nodes,
+    /// since the stacks are allocated to that size up front.
+    pub fn max_depth_reached(&self) -> usize {
+        self.state.max_depth()
+    }
+
+    /// Returns `true` if the search gave up because the budget set with
+    /// [`node_budget`](crate::Vf2Builder::node_budget) ran out.
+    ///
+    /// The isomorphisms yielded before then are still valid,
+    /// but there may be more.
+    pub fn budget_exhausted(&self) -> bool {
+        self.state.budget_exhausted()
+    }
+
+    /// Returns `true` if the function set with
+    /// [`node_eq_abort`](crate::Vf2Builder::node_eq_abort)
+    /// returned [`NodeMatch::Abort`](crate::NodeMatch::Abort).
+    ///
+    /// The isomorphisms yielded before then are still valid,
+    /// but there may be more.
+    pub fn aborted(&self) -> bool {
+        self.state.aborted()
+    }
+
+    /// Runs the search to the end and returns the isomorphisms
+    /// and the partial maps at leaves of the SSR tree
+    /// that cover at least `min` query nodes.
+    pub(crate) fn maximal_partials(mut self, min: usize) -> Vec<Isomorphism> {
+        self.state.yield_partials(min);
+        let mut maps = Vec::new();
+        loop {
+            let stop = self.state.step();
+            if stop && self.state.at_match() {
+                maps.push(self.state.query_map().clone());
+            }
+            if self.state.is_done() {
+                return maps;
+            }
+        }
+    }
+
+    /// Runs the search until the first isomorphism and returns
+    /// the deepest partial map reached along the way and its depth.
+    pub(crate) fn best_partial(mut self) -> Option<(Isomorphism, usize)> {
+        let mut best: Option<(Isomorphism, usize)> = None;
+        loop {
+            let stop = self.state.step();
+            let depth = self.state.depth();
+            if depth > best.as_ref().map_or(0, |&(_, best_depth)| best_depth) {
+                best = Some((self.state.query_map().clone(), depth));
+            }
+            if stop {
+                return best;
+            }
         }
     }
 
@@ -52,16 +197,65 @@ where
     /// [`next`]: Self::next
     pub fn next_ref(&mut self) -> Option<&Isomorphism> {
         while !self.state.step() {}
-        self.state.all_covered().then_some(self.state.query_map())
+        if !self.state.all_covered() {
+            return None;
+        }
+        self.yielded += 1;
+        Some(self.state.query_map())
+    }
+
+    /// Advances the search and returns the index of the next isomorphism
+    /// and a reference to it.
+    ///
+    /// Indices count the isomorphisms this iterator has yielded
+    /// by any method, starting at 0, so they are stable for logging
+    /// and correlating results. Unlike [`Iterator::enumerate`],
+    /// this does not allocate. A search resumed from a
+    /// [`checkpoint`](Self::checkpoint) counts from 0 again.
+    /// Returns [`None`] when the search is complete.
+    pub fn next_ref_with_id(&mut self) -> Option<(usize, &Isomorphism)> {
+        let id = self.yielded;
+        self.next_ref().map(|isomorphism| (id, isomorphism))
+    }
+
+    /// Advances the search and returns a reference to the next isomorphism
+    /// as a map from each data node to the query node that maps to it.
+    ///
+    /// The value at index `i` is the query node that maps to data node `i`,
+    /// or [`NodeIndex::MAX`] if no query node maps to data node `i`.
+    /// This is the inverse of [`next_ref`](Self::next_ref),
+    /// which the search keeps anyway, so it costs nothing to build.
+    /// If query nodes set with [`allow_merge`](crate::Vf2Builder::allow_merge)
+    /// share a data node, the value is the first of them to map to it.
+    /// Returns [`None`] when the search is complete.
+    pub fn next_ref_data_map(&mut self) -> Option<&Vec<NodeIndex>> {
+        self.next_ref()?;
+        Some(self.state.data_map())
+    }
+
+    /// Advances the search and returns the next isomorphism
+    /// as a map from each query node to the data node it maps to.
+    ///
+    /// This allocates and fills a hash map for each isomorphism,
+    /// which costs more than [`next`](Self::next)
+    /// and much more than [`next_ref`](Self::next_ref).
+    /// Returns [`None`] if the search is complete.
+    #[cfg(feature = "std")]
+    pub fn next_map(&mut self) -> Option<HashMap<NodeIndex, NodeIndex>> {
+        self.next_ref()
+            .map(|isomorphism| isomorphism.iter().copied().enumerate().collect())
     }
 }
 
-impl<'a, Query, Data, NodeEq, EdgeEq> Iterator for IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq>
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> Iterator
+    for IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
 where
     Query: Graph,
     Data: Graph,
     NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
     EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
 {
     type Item = Isomorphism;
 
@@ -69,3 +263,263 @@ where
         self.next_ref().cloned()
     }
 }
+
+/// A snapshot of the search state, returned by
+/// [`IsomorphismIter::current_state_info`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StateInfo {
+    /// Depth in the SSR tree, which is the number of covered query nodes.
+    pub depth: usize,
+    /// Query graph terminal set sizes.
+    pub query: TerminalSetSizes,
+    /// Data graph terminal set sizes.
+    pub data: TerminalSetSizes,
+}
+
+/// The number of uncovered nodes in each terminal set of a graph.
+///
+/// Terminal sets hold the uncovered neighbors of covered nodes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TerminalSetSizes {
+    /// Number of uncovered nodes that are edge destinations
+    /// from covered nodes.
+    ///
+    /// If the graph is undirected, this counts all terminal nodes.
+    pub outgoing: usize,
+    /// Number of uncovered nodes that are edge sources to covered nodes.
+    ///
+    /// Always zero if the graph is undirected.
+    pub incoming: usize,
+}
+
+/// An isomorphism iterator that also yields the data edge
+/// each query edge maps to.
+///
+/// Created by [`Vf2Builder::iter_with_edges`](crate::Vf2Builder::iter_with_edges).
+#[derive(Clone, Debug)]
+pub struct EdgeIsomorphismIter<
+    'a,
+    Query,
+    Data,
+    NodeEq,
+    EdgeEq,
+    OnPartial = DefaultOnPartial,
+    OnProgress = DefaultOnProgress,
+> where
+    Query: Graph,
+    Data: Graph,
+{
+    /// Node isomorphism iterator.
+    iter: IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+    /// Query edges as `(source, target)` pairs.
+    query_edges: Vec<(NodeIndex, NodeIndex)>,
+    /// Data edges of the current isomorphism.
+    ///
+    /// The value at index `i` is the data edge that query edge `i` maps to.
+    data_edges: Vec<(NodeIndex, NodeIndex)>,
+}
+
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+    EdgeIsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+where
+    Query: Graph,
+    Data: Graph,
+    NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
+    EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
+{
+    pub(crate) fn new(
+        query: &Query,
+        iter: IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+    ) -> Self {
+        let mut query_edges = Vec::with_capacity(query.edge_count());
+        for source in query.node_indices() {
+            for target in query.neighbors(source, Direction::Outgoing) {
+                // If the graph is undirected, each edge is seen from both ends.
+                if query.is_directed() || source <= target {
+                    query_edges.push((source, target));
+                }
+            }
+        }
+        Self {
+            iter,
+            data_edges: Vec::with_capacity(query_edges.len()),
+            query_edges,
+        }
+    }
+
+    /// Returns the query edges as `(source, target)` pairs.
+    ///
+    /// Edges are ordered by source, then by the order of
+    /// [`Graph::neighbors`]. If the graph is undirected,
+    /// each edge appears once with `source <= target`.
+    pub fn query_edges(&self) -> &[(NodeIndex, NodeIndex)] {
+        &self.query_edges
+    }
+
+    /// Advances the search and returns a reference
+    /// to the next isomorphism and its edges.
+    ///
+    /// Unlike [`next`], this returns a reference so as not to allocate.
+    /// Returns [`None`] when the search is complete.
+    ///
+    /// [`next`]: Self::next
+    pub fn next_ref(&mut self) -> Option<EdgeIsomorphismRef<'_>> {
+        let nodes = self.iter.next_ref()?;
+        self.data_edges.clear();
+        self.data_edges.extend(
+            self.query_edges
+                .iter()
+                .map(|&(source, target)| (nodes[source], nodes[target])),
+        );
+        Some(EdgeIsomorphismRef {
+            nodes,
+            edges: &self.data_edges,
+        })
+    }
+}
+
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> Iterator
+    for EdgeIsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+where
+    Query: Graph,
+    Data: Graph,
+    NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
+    EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
+{
+    type Item = EdgeIsomorphism;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_ref().map(|isomorphism| EdgeIsomorphism {
+            nodes: isomorphism.nodes.clone(),
+            edges: isomorphism.edges.to_vec(),
+        })
+    }
+}
+
+/// An isomorphism and the data edges its query edges map to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EdgeIsomorphism {
+    /// The value at index `i` is the data node
+    /// that query node `i` maps to.
+    pub nodes: Isomorphism,
+    /// The value at index `i` is the data edge that query edge `i` maps to.
+    ///
+    /// Query edges are ordered as in [`EdgeIsomorphismIter::query_edges`].
+    pub edges: Vec<(NodeIndex, NodeIndex)>,
+}
+
+/// A borrowed [`EdgeIsomorphism`] returned by [`EdgeIsomorphismIter::next_ref`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EdgeIsomorphismRef<'b> {
+    /// The value at index `i` is the data node
+    /// that query node `i` maps to.
+    pub nodes: &'b Isomorphism,
+    /// The value at index `i` is the data edge that query edge `i` maps to.
+    ///
+    /// Query edges are ordered as in [`EdgeIsomorphismIter::query_edges`].
+    pub edges: &'b [(NodeIndex, NodeIndex)],
+}
+
+/// An isomorphism iterator that also yields the number of data edges
+/// among the data nodes of each isomorphism.
+///
+/// Created by [`Vf2Builder::iter_with_edge_density`](crate::Vf2Builder::iter_with_edge_density).
+#[derive(Clone, Debug)]
+pub struct EdgeDensityIter<
+    'a,
+    Query,
+    Data,
+    NodeEq,
+    EdgeEq,
+    OnPartial = DefaultOnPartial,
+    OnProgress = DefaultOnProgress,
+> where
+    Query: Graph,
+    Data: Graph,
+{
+    /// Node isomorphism iterator.
+    iter: IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+    /// Data graph.
+    data: GraphRef<'a, Data>,
+    /// The value at index `i` is `true` if data node `i`
+    /// is in the current isomorphism.
+    ///
+    /// All `false` between isomorphisms.
+    matched: Vec<bool>,
+}
+
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+    EdgeDensityIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+where
+    Query: Graph,
+    Data: Graph,
+    NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
+    EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
+{
+    pub(crate) fn new(
+        data: GraphRef<'a, Data>,
+        iter: IsomorphismIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>,
+    ) -> Self {
+        Self {
+            iter,
+            matched: vec![false; data.node_count()],
+            data,
+        }
+    }
+
+    /// Advances the search and returns a reference
+    /// to the next isomorphism and its edge count.
+    ///
+    /// Unlike [`next`], this returns a reference so as not to allocate.
+    /// Returns [`None`] when the search is complete.
+    ///
+    /// [`next`]: Self::next
+    pub fn next_ref(&mut self) -> Option<(&Isomorphism, usize)> {
+        let nodes = self.iter.next_ref()?;
+        for &node in nodes {
+            self.matched[node] = true;
+        }
+        let directed = self.data.is_directed();
+        let mut edges = 0;
+        for (index, &source) in nodes.iter().enumerate() {
+            // Merged query nodes share a data node, whose edges count once.
+            if nodes[..index].contains(&source) {
+                continue;
+            }
+            edges += self
+                .data
+                .neighbors(source, Direction::Outgoing)
+                // If the graph is undirected, each edge is seen from both ends.
+                .filter(|&target| self.matched[target] && (directed || source <= target))
+                .count();
+        }
+        for &node in nodes {
+            self.matched[node] = false;
+        }
+        Some((nodes, edges))
+    }
+}
+
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress> Iterator
+    for EdgeDensityIter<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+where
+    Query: Graph,
+    Data: Graph,
+    NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
+    EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
+{
+    type Item = (Isomorphism, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_ref()
+            .map(|(isomorphism, edges)| (isomorphism.clone(), edges))
+    }
+}
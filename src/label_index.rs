@@ -0,0 +1,119 @@
+#[cfg(feature = "std")]
+use crate::Graph;
+use crate::NodeIndex;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// Data nodes grouped by label, and the group
+/// each query node's candidates are taken from.
+#[derive(Clone, Debug)]
+pub(crate) struct LabelIndex {
+    /// The value at index `i` is the bucket of data nodes
+    /// with the same label as query node `i`, if there is one.
+    query_buckets: Vec<Option<usize>>,
+    /// Data nodes with equal labels, sorted within each bucket.
+    buckets: Vec<Vec<NodeIndex>>,
+}
+
+impl LabelIndex {
+    /// Creates a new [`LabelIndex`] grouping
+    /// the nodes of `data` by label.
+    #[cfg(feature = "std")]
+    pub(crate) fn new<Query, Data>(query: &Query, data: &Data) -> Self
+    where
+        Query: Graph<NodeLabel = Data::NodeLabel>,
+        Data: Graph,
+        Data::NodeLabel: Eq + Hash,
+    {
+        let mut bucket_indices = HashMap::new();
+        let mut buckets: Vec<Vec<NodeIndex>> = Vec::new();
+        for node in data.node_indices() {
+            let label = data.node_label(node).expect("node should exist");
+            let index = *bucket_indices.entry(label).or_insert_with(|| {
+                buckets.push(Vec::new());
+                buckets.len() - 1
+            });
+            buckets[index].push(node);
+        }
+        let mut query_buckets = vec![None; query.node_count()];
+        for node in query.node_indices() {
+            let label = query.node_label(node).expect("node should exist");
+            query_buckets[node] = bucket_indices.get(label).copied();
+        }
+        Self {
+            query_buckets,
+            buckets,
+        }
+    }
+
+    /// Creates a new [`LabelIndex`] grouping data nodes by class,
+    /// where the value at index `i` is the class of node `i`.
+    pub(crate) fn from_classes(query_classes: &[usize], data_classes: &[usize]) -> Self {
+        let mut bucket_indices = BTreeMap::new();
+        let mut buckets: Vec<Vec<NodeIndex>> = Vec::new();
+        for (node, class) in data_classes.iter().enumerate() {
+            let index = *bucket_indices.entry(class).or_insert_with(|| {
+                buckets.push(Vec::new());
+                buckets.len() - 1
+            });
+            buckets[index].push(node);
+        }
+        let query_buckets = query_classes
+            .iter()
+            .map(|class| bucket_indices.get(class).copied())
+            .collect();
+        Self {
+            query_buckets,
+            buckets,
+        }
+    }
+
+    /// Returns this index for the query graph restricted to `nodes`,
+    /// where query node `i` of the restricted query is `nodes[i]`.
+    pub(crate) fn restrict_query(&self, nodes: &[NodeIndex]) -> Self {
+        Self {
+            query_buckets: nodes.iter().map(|&node| self.query_buckets[node]).collect(),
+            buckets: self.buckets.clone(),
+        }
+    }
+
+    /// Returns the data nodes with the same label as `query_node`.
+    pub(crate) fn bucket(&self, query_node: NodeIndex) -> &[NodeIndex] {
+        match self.query_buckets[query_node] {
+            None => &[],
+            Some(index) => &self.buckets[index],
+        }
+    }
+
+    /// Returns `true` if each label is on at least as many data nodes
+    /// as query nodes, not counting query nodes for which `skip` returns `true`.
+    ///
+    /// That is, the multiset of query labels is contained
+    /// in the multiset of data labels.
+    pub(crate) fn contains_query_labels(&self, skip: impl Fn(NodeIndex) -> bool) -> bool {
+        let mut counts = vec![0; self.buckets.len()];
+        for (query_node, &bucket) in self.query_buckets.iter().enumerate() {
+            if skip(query_node) {
+                continue;
+            }
+            let Some(index) = bucket else {
+                return false;
+            };
+            counts[index] += 1;
+            if counts[index] > self.buckets[index].len() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns `true` if `data_node` has the same label as `query_node`.
+    pub(crate) fn allows(&self, query_node: NodeIndex, data_node: NodeIndex) -> bool {
+        self.bucket(query_node).binary_search(&data_node).is_ok()
+    }
+}
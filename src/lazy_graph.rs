@@ -0,0 +1,202 @@
+use crate::{Direction, Graph, NodeIndex};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+
+/// A graph whose labels may be produced on demand.
+///
+/// This is like [`Graph`], but the label methods return a [`Cow`],
+/// so a backend that fetches labels from elsewhere, such as a database,
+/// can return owned labels instead of storing them all up front.
+/// Wrap the graph in [`LazyLabels`] to search it.
+pub trait LazyGraph {
+    /// Node label type.
+    type NodeLabel: Clone;
+
+    /// Edge label type.
+    type EdgeLabel: Clone;
+
+    /// Returns `true` if the graph is directed
+    /// or `false` if the graph is undirected.
+    fn is_directed(&self) -> bool;
+
+    /// Returns the number of nodes in the graph.
+    fn node_count(&self) -> usize;
+
+    /// Returns the label of `node`, borrowed or owned.
+    fn node_label(&self, node: NodeIndex) -> Option<Cow<'_, Self::NodeLabel>>;
+
+    /// Returns an iterator of neighbors of `node`.
+    ///
+    /// If the graph is directed, returns neighbors in `direction` only.
+    /// If undirected, ignores `direction` and returns all neighbors.
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex>;
+
+    /// Returns `true` if there is an edge from `source` to `target`.
+    ///
+    /// If the graph is directed, the edge must go from `source` to `target`.
+    /// If undirected, an edge must exist between `source` and `target`.
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool;
+
+    /// Returns the label of the edge from `source` to `target`,
+    /// borrowed or owned.
+    ///
+    /// If the graph is directed, the edge must go from `source` to `target`.
+    /// If undirected, the edge must be between `source` and `target`.
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<Cow<'_, Self::EdgeLabel>>;
+}
+
+/// A [`Graph`] over a [`LazyGraph`] that fetches each label
+/// the first time the search needs it and keeps it.
+///
+/// The search compares labels by reference, so labels are cached
+/// rather than fetched on every comparison. Node labels are cached
+/// one at a time. Edge labels are cached per source node: the first
+/// label read from a node fetches the labels of all its outgoing edges.
+/// Labels the search never reads are never fetched, but every fetched
+/// label stays in memory until the wrapper is dropped, and the wrapper
+/// keeps an empty cell per node from the start.
+/// Borrowed labels are cloned into the cache.
+///
+/// The search still reads the neighbors of every node once when it starts,
+/// so this saves fetching labels, not neighbors.
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+/// use vf2::{CsrGraph, Direction, LazyGraph, LazyLabels, NodeIndex};
+///
+/// /// A path whose node labels are computed from the node index.
+/// struct Path(usize);
+///
+/// impl LazyGraph for Path {
+///     type NodeLabel = usize;
+///     type EdgeLabel = ();
+///
+///     fn is_directed(&self) -> bool {
+///         true
+///     }
+///
+///     fn node_count(&self) -> usize {
+///         self.0
+///     }
+///
+///     fn node_label(&self, node: NodeIndex) -> Option<Cow<'_, usize>> {
+///         (node < self.0).then(|| Cow::Owned(node % 2))
+///     }
+///
+///     fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+///         let neighbor = match direction {
+///             Direction::Outgoing => node.checked_add(1).filter(|&next| next < self.0),
+///             Direction::Incoming => node.checked_sub(1),
+///         };
+///         neighbor.into_iter()
+///     }
+///
+///     fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+///         source + 1 == target && target < self.0
+///     }
+///
+///     fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<Cow<'_, ()>> {
+///         self.contains_edge(source, target).then_some(Cow::Borrowed(&()))
+///     }
+/// }
+///
+/// let query = CsrGraph::new(true, vec![1, 0], [(0, 1, ())]);
+/// let data = LazyLabels::new(Path(4));
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+/// assert_eq!(isomorphisms, vec![vec![1, 2]]);
+/// ```
+#[derive(Debug)]
+pub struct LazyLabels<G>
+where
+    G: LazyGraph,
+{
+    /// Underlying graph.
+    graph: G,
+    /// Fetched label of each node.
+    node_labels: Vec<OnceCell<Option<G::NodeLabel>>>,
+    /// Fetched outgoing edges of each node with their labels,
+    /// sorted by target.
+    edge_labels: Vec<OnceCell<EdgeLabels<G::EdgeLabel>>>,
+}
+
+/// Outgoing edges of a node with their labels.
+type EdgeLabels<E> = Vec<(NodeIndex, E)>;
+
+impl<G> LazyLabels<G>
+where
+    G: LazyGraph,
+{
+    /// Creates a wrapper around `graph` that has fetched no labels yet.
+    pub fn new(graph: G) -> Self {
+        let node_count = graph.node_count();
+        Self {
+            node_labels: (0..node_count).map(|_| OnceCell::new()).collect(),
+            edge_labels: (0..node_count).map(|_| OnceCell::new()).collect(),
+            graph,
+        }
+    }
+
+    /// Returns the underlying graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Returns the underlying graph and drops the fetched labels.
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+}
+
+impl<G> Graph for LazyLabels<G>
+where
+    G: LazyGraph,
+{
+    type NodeLabel = G::NodeLabel;
+    type EdgeLabel = G::EdgeLabel;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn node_count(&self) -> usize {
+        self.node_labels.len()
+    }
+
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.node_labels
+            .get(node)?
+            .get_or_init(|| self.graph.node_label(node).map(Cow::into_owned))
+            .as_ref()
+    }
+
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.graph.neighbors(node, direction)
+    }
+
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.graph.contains_edge(source, target)
+    }
+
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        let edges = self.edge_labels.get(source)?.get_or_init(|| {
+            let mut edges: Vec<_> = self
+                .graph
+                .neighbors(source, Direction::Outgoing)
+                .filter_map(|neighbor| {
+                    let label = self.graph.edge_label(source, neighbor)?;
+                    Some((neighbor, label.into_owned()))
+                })
+                .collect();
+            edges.sort_unstable_by_key(|&(neighbor, _)| neighbor);
+            edges
+        });
+        let index = edges
+            .binary_search_by_key(&target, |&(neighbor, _)| neighbor)
+            .ok()?;
+        Some(&edges[index].1)
+    }
+}
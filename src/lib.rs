@@ -7,6 +7,14 @@
 //!
 //! See the [repository](https://github.com/OwenTrokeBillard/vf2) for more information.
 //!
+//! # Features
+//!
+//! The `std` feature is enabled by default. Without it, the crate is `no_std`
+//! and only needs `alloc`. The label index, [`MapGraph`],
+//! and the methods that return hash maps require `std`.
+//! The `parallel` feature adds `par_match_many`,
+//! which searches for many queries at once with rayon.
+//!
 //! # Usage
 //!
 //! Add `vf2` to your dependencies in **Cargo.toml**.
@@ -30,11 +38,13 @@
 //! These return a [`Vf2Builder`] with the algorithm configured.
 //! Next, call one of the following on the builder to enumerate the isomorphisms.
 //!
-//! | Desired output           | Call                         |
-//! |--------------------------|------------------------------|
-//! | First isomorphism        | [`first`](Vf2Builder::first) |
-//! | Vector of isomorphisms   | [`vec`](Vf2Builder::vec)     |
-//! | Iterator of isomorphisms | [`iter`](Vf2Builder::iter)   |
+//! | Desired output           | Call                           |
+//! |--------------------------|--------------------------------|
+//! | Whether one exists       | [`exists`](Vf2Builder::exists) |
+//! | First isomorphism        | [`first`](Vf2Builder::first)   |
+//! | Vector of isomorphisms   | [`vec`](Vf2Builder::vec)       |
+//! | Number of isomorphisms   | [`count`](Vf2Builder::count)   |
+//! | Iterator of isomorphisms | [`iter`](Vf2Builder::iter)     |
 //!
 //! \
 //! Filling a vector can consume a significant amount of memory.
@@ -77,15 +87,50 @@
 //! assert_eq!(isomorphisms, vec![vec![0, 1], vec![1, 2]]);
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod builder;
+mod config;
+mod csr;
+#[cfg(feature = "dot")]
+mod dot;
+mod error;
 mod graph;
+mod graph_ref;
+mod interval;
 mod isomorphism;
 mod iter;
+mod label_index;
+mod lazy_graph;
+#[cfg(feature = "std")]
+mod map_graph;
+mod node_filtered;
 #[cfg(feature = "petgraph")]
 mod petgraph;
+mod restricted;
+mod reversed;
 mod state;
+mod structural;
+mod union_find;
 
+#[cfg(feature = "petgraph")]
+pub use crate::petgraph::{to_petgraph, DataMapGraph};
 pub use builder::*;
+pub use csr::*;
+#[cfg(feature = "dot")]
+pub use dot::*;
+pub use error::*;
 pub use graph::*;
+pub use interval::*;
 pub use isomorphism::*;
 pub use iter::*;
+pub use lazy_graph::*;
+#[cfg(feature = "std")]
+pub use map_graph::*;
+pub use node_filtered::*;
+pub use restricted::*;
+pub use reversed::*;
+pub use state::StateSnapshot;
+pub use structural::*;
@@ -0,0 +1,136 @@
+use crate::{Direction, Graph, NodeIndex};
+use std::collections::HashMap;
+
+/// An unlabeled graph stored as an adjacency map.
+///
+/// This is convenient for small scripts that already have
+/// a graph as a map from each node to its neighbors.
+/// Nodes must be numbered `0..n`, so there must be a key
+/// for every node, including nodes without neighbors.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use vf2::MapGraph;
+///
+/// let triangle = HashMap::from([(0, vec![1, 2]), (1, vec![2]), (2, vec![])]);
+/// let query = MapGraph::new(false, triangle);
+/// let data = MapGraph::new(
+///     false,
+///     HashMap::from([(0, vec![1, 2]), (1, vec![2]), (2, vec![3]), (3, vec![])]),
+/// );
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).vec();
+/// assert_eq!(isomorphisms.len(), 6);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapGraph {
+    /// Whether the graph is directed.
+    directed: bool,
+    /// Outgoing neighbors, sorted and without duplicates.
+    ///
+    /// If the graph is undirected, this contains all neighbors.
+    outgoing: HashMap<NodeIndex, Vec<NodeIndex>>,
+    /// Incoming neighbors, sorted and without duplicates.
+    ///
+    /// Unused if the graph is undirected.
+    incoming: HashMap<NodeIndex, Vec<NodeIndex>>,
+}
+
+impl MapGraph {
+    /// Creates a new [`MapGraph`] from a map of each node to its neighbors.
+    ///
+    /// If the graph is directed, each node maps to its outgoing neighbors.
+    /// If undirected, each edge only needs to be listed from one end.
+    /// Neighbors are sorted, and edges listed more than once are kept once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not `0..n` for some `n`,
+    /// or a neighbor is not a key.
+    pub fn new(directed: bool, adjacency: HashMap<NodeIndex, Vec<NodeIndex>>) -> Self {
+        let node_count = adjacency.len();
+        assert!(
+            adjacency.keys().all(|&node| node < node_count),
+            "nodes must be numbered from zero without gaps"
+        );
+        let mut outgoing: HashMap<_, Vec<_>> =
+            (0..node_count).map(|node| (node, Vec::new())).collect();
+        let mut incoming = outgoing.clone();
+        for (&source, targets) in &adjacency {
+            for &target in targets {
+                assert!(
+                    target < node_count,
+                    "neighbor refers to a node that does not exist"
+                );
+                outgoing.get_mut(&source).unwrap().push(target);
+                if directed {
+                    incoming.get_mut(&target).unwrap().push(source);
+                } else {
+                    outgoing.get_mut(&target).unwrap().push(source);
+                }
+            }
+        }
+        if !directed {
+            incoming.clear();
+        }
+        for neighbors in outgoing.values_mut().chain(incoming.values_mut()) {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+        Self {
+            directed,
+            outgoing,
+            incoming,
+        }
+    }
+
+    /// Returns the neighbors of `node` in `direction` as a slice.
+    ///
+    /// If the graph is undirected, ignores `direction` and returns all neighbors.
+    fn neighbor_slice(&self, node: NodeIndex, direction: Direction) -> &[NodeIndex] {
+        let map = match direction {
+            Direction::Incoming if self.directed => &self.incoming,
+            _ => &self.outgoing,
+        };
+        map.get(&node).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl Graph for MapGraph {
+    type NodeLabel = ();
+    type EdgeLabel = ();
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.contains_node(node).then_some(&())
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.neighbor_slice(node, direction).iter().copied()
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.neighbor_slice(source, Direction::Outgoing)
+            .binary_search(&target)
+            .is_ok()
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        self.contains_edge(source, target).then_some(&())
+    }
+}
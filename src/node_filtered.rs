@@ -0,0 +1,186 @@
+use crate::{Direction, EdgeId, Graph, NodeIndex};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A view of a graph with only the nodes that satisfy a predicate.
+///
+/// Filtered-out nodes and their edges are treated as absent.
+/// Since node indices must be contiguous, the remaining nodes are renumbered
+/// `0..n` in their original order. Isomorphisms found with this view
+/// use the new indices; map them back with
+/// [`original_index`](Self::original_index).
+///
+/// # Example
+///
+/// ```
+/// use petgraph::graph::DiGraph;
+/// use vf2::NodeFiltered;
+///
+/// let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+/// let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+/// let active = NodeFiltered::new(&data, |node| node != 1);
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &active).vec();
+/// assert_eq!(isomorphisms, vec![vec![1, 2]]);
+/// assert_eq!(active.original_index(1), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct NodeFiltered<'a, G, F> {
+    /// Underlying graph.
+    graph: &'a G,
+    /// Predicate on original node indices.
+    predicate: F,
+    /// Original index of each remaining node.
+    original: Vec<NodeIndex>,
+    /// New index of each original node,
+    /// or [`None`] if it is filtered out.
+    filtered: Vec<Option<NodeIndex>>,
+}
+
+impl<'a, G, F> NodeFiltered<'a, G, F>
+where
+    G: Graph,
+    F: Fn(NodeIndex) -> bool,
+{
+    /// Creates a view of `graph` with only the nodes
+    /// whose original index satisfies `predicate`.
+    pub fn new(graph: &'a G, predicate: F) -> Self {
+        let mut original = Vec::new();
+        let mut filtered = vec![None; graph.node_count()];
+        for node in graph.node_indices() {
+            if predicate(node) {
+                filtered[node] = Some(original.len());
+                original.push(node);
+            }
+        }
+        Self {
+            graph,
+            predicate,
+            original,
+            filtered,
+        }
+    }
+
+    /// Returns the predicate.
+    pub fn predicate(&self) -> &F {
+        &self.predicate
+    }
+
+    /// Returns the index in the underlying graph of `node`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the view.
+    pub fn original_index(&self, node: NodeIndex) -> NodeIndex {
+        self.original[node]
+    }
+
+    /// Returns the index in the view of the underlying graph node `node`,
+    /// or [`None`] if it is filtered out.
+    pub fn filtered_index(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.filtered.get(node).copied().flatten()
+    }
+
+    /// Returns the underlying graph nodes of `source` and `target`,
+    /// or [`None`] if either is not in the view.
+    fn original_pair(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Option<(NodeIndex, NodeIndex)> {
+        Some((*self.original.get(source)?, *self.original.get(target)?))
+    }
+}
+
+impl<G, F> Graph for NodeFiltered<'_, G, F>
+where
+    G: Graph,
+    F: Fn(NodeIndex) -> bool,
+{
+    type NodeLabel = G::NodeLabel;
+    type EdgeLabel = G::EdgeLabel;
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.original.len()
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.graph.node_label(*self.original.get(node)?)
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        let neighbors = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.neighbors(node, direction));
+        neighbors
+            .into_iter()
+            .flatten()
+            .filter_map(|neighbor| self.filtered_index(neighbor))
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.original_pair(source, target)
+            .is_some_and(|(source, target)| self.graph.contains_edge(source, target))
+    }
+
+    #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.original_pair(source, target)
+            .map_or(0, |(source, target)| {
+                self.graph.edge_multiplicity(source, target)
+            })
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        let (source, target) = self.original_pair(source, target)?;
+        self.graph.edge_label(source, target)
+    }
+
+    #[inline]
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        let edges = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.incident_edges(node, direction));
+        edges
+            .into_iter()
+            .flatten()
+            .filter_map(|(neighbor, label)| Some((self.filtered_index(neighbor)?, label)))
+    }
+
+    #[inline]
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        let edges = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.neighbors_with_edge(node, direction));
+        edges
+            .into_iter()
+            .flatten()
+            .filter_map(|(neighbor, edge)| Some((self.filtered_index(neighbor)?, edge)))
+    }
+
+    #[inline]
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        self.graph.edge_label_by_id(edge)
+    }
+}
@@ -1,7 +1,120 @@
-use crate::{Direction, Graph, NodeIndex};
+use crate::{CsrGraph, Direction, EdgeId, Graph, NodeIndex, Vf2Error};
+use core::fmt::Debug;
 use petgraph::adj::IndexType;
-use petgraph::EdgeType;
-use std::fmt::Debug;
+use petgraph::data::DataMap;
+use petgraph::matrix_graph::{self, MatrixGraph, Nullable};
+use petgraph::visit::{
+    EdgeRef, GraphProp, IntoEdgesDirected, IntoNeighborsDirected, NodeCount, NodeIndexable,
+};
+use petgraph::{Directed, EdgeType, Undirected};
+
+/// Copies `graph` into a [`petgraph::Graph`] with cloned labels.
+///
+/// Node indices are preserved.
+/// The edge type `Ty` must match the directedness of `graph`.
+///
+/// # Panics
+///
+/// Panics if `graph` is directed and `Ty` is undirected, or vice versa.
+pub fn to_petgraph<G, Ty>(graph: &G) -> petgraph::Graph<G::NodeLabel, G::EdgeLabel, Ty>
+where
+    G: Graph,
+    G::NodeLabel: Clone,
+    G::EdgeLabel: Clone,
+    Ty: EdgeType,
+{
+    assert_eq!(
+        graph.is_directed(),
+        Ty::is_directed(),
+        "edge type must match the graph directedness"
+    );
+    let mut petgraph = petgraph::Graph::with_capacity(graph.node_count(), graph.edge_count());
+    for node in graph.node_indices() {
+        let label = graph.node_label(node).expect("node should exist");
+        petgraph.add_node(label.clone());
+    }
+    for source in graph.node_indices() {
+        for (target, label) in outgoing_edges(graph, source) {
+            // If the graph is undirected, each edge is seen from both ends.
+            if graph.is_directed() || source <= target {
+                petgraph.add_edge(
+                    petgraph::graph::NodeIndex::new(source),
+                    petgraph::graph::NodeIndex::new(target),
+                    label.clone(),
+                );
+            }
+        }
+    }
+    petgraph
+}
+
+/// Copies the subgraph of `graph` induced by `nodes`
+/// into a [`petgraph::Graph`] with cloned labels.
+///
+/// Node `i` of the subgraph is node `nodes[i]` of `graph`.
+/// `positions` must have a [`None`] entry for each node of `graph`,
+/// and is left that way.
+pub(crate) fn induced_subgraph<G, Ty>(
+    graph: &G,
+    nodes: &[NodeIndex],
+    positions: &mut [Option<NodeIndex>],
+) -> petgraph::Graph<G::NodeLabel, G::EdgeLabel, Ty>
+where
+    G: Graph,
+    G::NodeLabel: Clone,
+    G::EdgeLabel: Clone,
+    Ty: EdgeType,
+{
+    assert_eq!(
+        graph.is_directed(),
+        Ty::is_directed(),
+        "edge type must match the graph directedness"
+    );
+    let mut petgraph = petgraph::Graph::with_capacity(nodes.len(), 0);
+    for (position, &node) in nodes.iter().enumerate() {
+        let label = graph.node_label(node).expect("node should exist");
+        petgraph.add_node(label.clone());
+        positions[node] = Some(position);
+    }
+    for (source_position, &source) in nodes.iter().enumerate() {
+        for (target, label) in outgoing_edges(graph, source) {
+            let Some(target_position) = positions[target] else {
+                continue;
+            };
+            // If the graph is undirected, each edge is seen from both ends.
+            if graph.is_directed() || source_position <= target_position {
+                petgraph.add_edge(
+                    petgraph::graph::NodeIndex::new(source_position),
+                    petgraph::graph::NodeIndex::new(target_position),
+                    label.clone(),
+                );
+            }
+        }
+    }
+    for &node in nodes {
+        positions[node] = None;
+    }
+    petgraph
+}
+
+/// Returns the targets and labels of the outgoing edges of `source`.
+///
+/// Labels are looked up by edge id where the graph names its edges,
+/// so parallel edges keep their own labels.
+fn outgoing_edges<G: Graph>(
+    graph: &G,
+    source: NodeIndex,
+) -> impl Iterator<Item = (NodeIndex, &G::EdgeLabel)> {
+    graph
+        .neighbors_with_edge(source, Direction::Outgoing)
+        .map(move |(target, edge)| {
+            let label = graph
+                .edge_label_by_id(edge)
+                .or_else(|| graph.edge_label(source, target))
+                .expect("edge should exist");
+            (target, label)
+        })
+}
 
 impl<N, E, Ty, Ix> Graph for petgraph::Graph<N, E, Ty, Ix>
 where
@@ -23,6 +136,11 @@ where
         self.node_count()
     }
 
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edge_count()
+    }
+
     #[inline]
     fn node_label(&self, index: NodeIndex) -> Option<&Self::NodeLabel> {
         self.node_weight(petgraph::graph::NodeIndex::<Ix>::new(index))
@@ -49,6 +167,14 @@ where
     }
 
     #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.edges_connecting(
+            petgraph::graph::NodeIndex::<Ix>::new(source),
+            petgraph::graph::NodeIndex::<Ix>::new(target),
+        )
+        .count()
+    }
+
     fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
         self.find_edge(
             petgraph::graph::NodeIndex::<Ix>::new(source),
@@ -56,4 +182,352 @@ where
         )
         .and_then(|index| self.edge_weight(index))
     }
+
+    #[inline]
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        self.edges_directed(
+            petgraph::graph::NodeIndex::<Ix>::new(node),
+            match direction {
+                Direction::Outgoing => petgraph::Direction::Outgoing,
+                Direction::Incoming => petgraph::Direction::Incoming,
+            },
+        )
+        .map(move |edge| {
+            // The node may be either end of an undirected edge.
+            let neighbor = if edge.source().index() == node {
+                edge.target()
+            } else {
+                edge.source()
+            };
+            (neighbor.index(), edge.weight())
+        })
+    }
+
+    #[inline]
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        self.edges_directed(
+            petgraph::graph::NodeIndex::<Ix>::new(node),
+            match direction {
+                Direction::Outgoing => petgraph::Direction::Outgoing,
+                Direction::Incoming => petgraph::Direction::Incoming,
+            },
+        )
+        .map(move |edge| {
+            // The node may be either end of an undirected edge.
+            let neighbor = if edge.source().index() == node {
+                edge.target()
+            } else {
+                edge.source()
+            };
+            (neighbor.index(), edge.id().index())
+        })
+    }
+
+    #[inline]
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        self.edge_weight(petgraph::graph::EdgeIndex::<Ix>::new(edge))
+    }
+}
+
+/// Node indices must be contiguous, so nodes must not have been removed.
+/// Checking whether an edge exists takes constant time,
+/// which suits small dense data graphs.
+impl<N, E, Ty, Null, Ix> Graph for MatrixGraph<N, E, Ty, Null, Ix>
+where
+    N: Debug,
+    E: Debug,
+    Ty: MatrixEdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+{
+    type NodeLabel = N;
+    type EdgeLabel = E;
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.is_directed()
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.node_count()
+    }
+
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.edge_count()
+    }
+
+    #[inline]
+    fn node_label(&self, index: NodeIndex) -> Option<&Self::NodeLabel> {
+        (index < self.node_count())
+            .then(|| self.node_weight(petgraph::graph::NodeIndex::<Ix>::new(index)))
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        let node = petgraph::graph::NodeIndex::<Ix>::new(node);
+        match direction {
+            Direction::Outgoing => self.neighbors(node),
+            Direction::Incoming => Ty::incoming_neighbors(self, node),
+        }
+        .map(|neighbor| neighbor.index())
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.has_edge(
+            petgraph::graph::NodeIndex::<Ix>::new(source),
+            petgraph::graph::NodeIndex::<Ix>::new(target),
+        )
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        Graph::contains_edge(self, source, target).then(|| {
+            self.edge_weight(
+                petgraph::graph::NodeIndex::<Ix>::new(source),
+                petgraph::graph::NodeIndex::<Ix>::new(target),
+            )
+        })
+    }
+}
+
+mod sealed {
+    use petgraph::adj::IndexType;
+    use petgraph::matrix_graph::{MatrixGraph, Neighbors, Nullable};
+    use petgraph::EdgeType;
+
+    /// An edge type of a [`MatrixGraph`] whose incoming neighbors
+    /// petgraph can iterate.
+    ///
+    /// Petgraph only exposes incoming neighbors for directed matrix graphs,
+    /// and for undirected ones they are all neighbors.
+    pub trait MatrixEdgeType: EdgeType + Sized {
+        /// Returns the nodes with an edge to `node`.
+        fn incoming_neighbors<N, E, Null, Ix>(
+            graph: &MatrixGraph<N, E, Self, Null, Ix>,
+            node: petgraph::graph::NodeIndex<Ix>,
+        ) -> Neighbors<'_, Self, Null, Ix>
+        where
+            Null: Nullable<Wrapped = E>,
+            Ix: IndexType;
+    }
+}
+
+use sealed::MatrixEdgeType;
+
+impl MatrixEdgeType for Directed {
+    #[inline]
+    fn incoming_neighbors<N, E, Null, Ix>(
+        graph: &MatrixGraph<N, E, Self, Null, Ix>,
+        node: petgraph::graph::NodeIndex<Ix>,
+    ) -> matrix_graph::Neighbors<'_, Self, Null, Ix>
+    where
+        Null: Nullable<Wrapped = E>,
+        Ix: IndexType,
+    {
+        graph.neighbors_directed(node, petgraph::Direction::Incoming)
+    }
+}
+
+impl MatrixEdgeType for Undirected {
+    #[inline]
+    fn incoming_neighbors<N, E, Null, Ix>(
+        graph: &MatrixGraph<N, E, Self, Null, Ix>,
+        node: petgraph::graph::NodeIndex<Ix>,
+    ) -> matrix_graph::Neighbors<'_, Self, Null, Ix>
+    where
+        Null: Nullable<Wrapped = E>,
+        Ix: IndexType,
+    {
+        graph.neighbors(node)
+    }
+}
+
+impl<N, E, Ty, Ix> From<&petgraph::Graph<N, E, Ty, Ix>> for CsrGraph<N, E>
+where
+    N: Clone,
+    E: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn from(graph: &petgraph::Graph<N, E, Ty, Ix>) -> Self {
+        CsrGraph::new(
+            graph.is_directed(),
+            graph.node_weights().cloned().collect(),
+            graph.edge_references().map(|edge| {
+                (
+                    edge.source().index(),
+                    edge.target().index(),
+                    edge.weight().clone(),
+                )
+            }),
+        )
+    }
+}
+
+/// A [`Graph`] over a petgraph graph that implements [`DataMap`],
+/// along with the [`petgraph::visit`] traits that iterate neighbors and edges.
+///
+/// Wrap a reference to the graph, since petgraph implements
+/// the traits that iterate neighbors and edges for references.
+/// Node labels and edge labels are the node weights and edge weights
+/// from [`DataMap`]. In petgraph 0.6, [`petgraph::Graph`],
+/// [`StableGraph`](petgraph::stable_graph::StableGraph),
+/// [`List`](petgraph::adj::List) and
+/// [`NodeFiltered`](petgraph::visit::NodeFiltered) implement it,
+/// but `GraphMap`, [`MatrixGraph`]
+/// and [`Csr`](petgraph::csr::Csr) do not, so they cannot be wrapped.
+/// [`petgraph::Graph`] and [`MatrixGraph`] implement [`Graph`] directly,
+/// which is faster than this wrapper.
+///
+/// Node indices are taken from [`NodeIndexable`], and must be contiguous,
+/// so nodes must not have been removed from the graph.
+///
+/// # Example
+///
+/// ```
+/// use petgraph::stable_graph::StableDiGraph;
+/// use vf2::DataMapGraph;
+///
+/// let query = StableDiGraph::<(), ()>::from_edges([(0, 1)]);
+/// let data = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+///
+/// let query = DataMapGraph::new(&query).unwrap();
+/// let data = DataMapGraph::new(&data).unwrap();
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).vec();
+/// assert_eq!(isomorphisms, vec![vec![0, 1], vec![1, 2]]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct DataMapGraph<G> {
+    /// Underlying graph.
+    graph: G,
+}
+
+impl<G> DataMapGraph<G>
+where
+    G: NodeCount + NodeIndexable,
+{
+    /// Creates a new [`DataMapGraph`] over `graph`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::NonContiguousNodes`]
+    /// if the node indices of `graph` are not contiguous.
+    pub fn new(graph: G) -> Result<Self, Vf2Error> {
+        if graph.node_count() != graph.node_bound() {
+            return Err(Vf2Error::NonContiguousNodes);
+        }
+        Ok(Self { graph })
+    }
+
+    /// Returns the underlying graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+}
+
+impl<G> Graph for DataMapGraph<G>
+where
+    G: IntoNeighborsDirected + IntoEdgesDirected + NodeCount + NodeIndexable + DataMap + GraphProp,
+{
+    type NodeLabel = G::NodeWeight;
+    type EdgeLabel = G::EdgeWeight;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_bound()
+    }
+
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        if node >= self.graph.node_bound() {
+            return None;
+        }
+        self.graph.node_weight(self.graph.from_index(node))
+    }
+
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.graph
+            .neighbors_directed(
+                self.graph.from_index(node),
+                to_petgraph_direction(direction),
+            )
+            .map(|neighbor| self.graph.to_index(neighbor))
+    }
+
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.edges_between(source, target).next().is_some()
+    }
+
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.edges_between(source, target).count()
+    }
+
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        let edge = self.edges_between(source, target).next()?;
+        self.graph.edge_weight(edge.id())
+    }
+
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        self.graph
+            .edges_directed(
+                self.graph.from_index(node),
+                to_petgraph_direction(direction),
+            )
+            .filter_map(move |edge| {
+                let neighbor = self.other_end(node, edge);
+                Some((neighbor, self.graph.edge_weight(edge.id())?))
+            })
+    }
+}
+
+impl<G> DataMapGraph<G>
+where
+    G: IntoEdgesDirected + NodeIndexable,
+{
+    /// Returns an iterator of the edges from `source` to `target`.
+    fn edges_between(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> impl Iterator<Item = G::EdgeRef> + '_ {
+        self.graph
+            .edges_directed(self.graph.from_index(source), petgraph::Direction::Outgoing)
+            .filter(move |&edge| self.other_end(source, edge) == target)
+    }
+
+    /// Returns the end of `edge` that is not `node`.
+    fn other_end(&self, node: NodeIndex, edge: G::EdgeRef) -> NodeIndex {
+        // The node may be either end of an undirected edge.
+        let source = self.graph.to_index(edge.source());
+        if source == node {
+            self.graph.to_index(edge.target())
+        } else {
+            source
+        }
+    }
+}
+
+/// Returns the petgraph direction for `direction`.
+fn to_petgraph_direction(direction: Direction) -> petgraph::Direction {
+    match direction {
+        Direction::Outgoing => petgraph::Direction::Outgoing,
+        Direction::Incoming => petgraph::Direction::Incoming,
+    }
 }
@@ -0,0 +1,173 @@
+use crate::graph_ref::GraphRef;
+use crate::{Direction, EdgeId, Graph, NodeIndex, Vf2Error};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A query graph restricted to a subset of its nodes,
+/// made by [`Vf2Builder::restrict_query`](crate::Vf2Builder::restrict_query).
+///
+/// Only the chosen nodes and the edges among them remain.
+/// Node `i` of the view is the `i`-th chosen node, so isomorphisms
+/// found with the view are indexed by the chosen nodes in the order given.
+/// Map them back with [`original_index`](Self::original_index).
+#[derive(Clone, Debug)]
+pub struct RestrictedQuery<'a, G> {
+    /// Underlying graph.
+    graph: GraphRef<'a, G>,
+    /// Original index of each remaining node.
+    original: Vec<NodeIndex>,
+    /// New index of each original node,
+    /// or [`None`] if it is not chosen.
+    restricted: Vec<Option<NodeIndex>>,
+}
+
+impl<'a, G> RestrictedQuery<'a, G>
+where
+    G: Graph,
+{
+    /// Creates a view of `graph` with only `nodes`, in that order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Vf2Error::QueryNodeOutOfRange`] if a node is not in `graph`,
+    /// or [`Vf2Error::DuplicateQueryNode`] if a node is chosen more than once.
+    pub(crate) fn new(graph: GraphRef<'a, G>, nodes: &[NodeIndex]) -> Result<Self, Vf2Error> {
+        let mut restricted = vec![None; graph.node_count()];
+        for (index, &node) in nodes.iter().enumerate() {
+            match restricted.get_mut(node) {
+                Some(slot @ None) => *slot = Some(index),
+                Some(Some(_)) => return Err(Vf2Error::DuplicateQueryNode(node)),
+                None => return Err(Vf2Error::QueryNodeOutOfRange(node)),
+            }
+        }
+        Ok(Self {
+            graph,
+            original: nodes.to_vec(),
+            restricted,
+        })
+    }
+}
+
+impl<G> RestrictedQuery<'_, G> {
+    /// Returns the underlying graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Returns the index in the underlying graph of `node`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the view.
+    pub fn original_index(&self, node: NodeIndex) -> NodeIndex {
+        self.original[node]
+    }
+
+    /// Returns the index in the view of the underlying graph node `node`,
+    /// or [`None`] if it is not chosen.
+    pub fn restricted_index(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.restricted.get(node).copied().flatten()
+    }
+
+    /// Returns the underlying graph nodes of `source` and `target`,
+    /// or [`None`] if either is not in the view.
+    fn original_pair(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Option<(NodeIndex, NodeIndex)> {
+        Some((*self.original.get(source)?, *self.original.get(target)?))
+    }
+}
+
+impl<G> Graph for RestrictedQuery<'_, G>
+where
+    G: Graph,
+{
+    type NodeLabel = G::NodeLabel;
+    type EdgeLabel = G::EdgeLabel;
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.original.len()
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.graph.node_label(*self.original.get(node)?)
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        let neighbors = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.neighbors(node, direction));
+        neighbors
+            .into_iter()
+            .flatten()
+            .filter_map(|neighbor| self.restricted_index(neighbor))
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.original_pair(source, target)
+            .is_some_and(|(source, target)| self.graph.contains_edge(source, target))
+    }
+
+    #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.original_pair(source, target)
+            .map_or(0, |(source, target)| {
+                self.graph.edge_multiplicity(source, target)
+            })
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        let (source, target) = self.original_pair(source, target)?;
+        self.graph.edge_label(source, target)
+    }
+
+    #[inline]
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        let edges = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.incident_edges(node, direction));
+        edges
+            .into_iter()
+            .flatten()
+            .filter_map(|(neighbor, label)| Some((self.restricted_index(neighbor)?, label)))
+    }
+
+    #[inline]
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        let edges = self
+            .original
+            .get(node)
+            .map(|&node| self.graph.neighbors_with_edge(node, direction));
+        edges
+            .into_iter()
+            .flatten()
+            .filter_map(|(neighbor, edge)| Some((self.restricted_index(neighbor)?, edge)))
+    }
+
+    #[inline]
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        self.graph.edge_label_by_id(edge)
+    }
+}
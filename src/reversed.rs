@@ -0,0 +1,130 @@
+use crate::{Direction, EdgeId, Graph, NodeIndex};
+
+/// A view of a graph with every edge reversed.
+///
+/// This matches against the transpose of a graph without copying it.
+/// Reversing an undirected graph has no effect.
+///
+/// # Example
+///
+/// ```
+/// use petgraph::graph::DiGraph;
+/// use vf2::Reversed;
+///
+/// let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+/// let data = DiGraph::<(), ()>::from_edges([(1, 0), (2, 0)]);
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &Reversed(&data)).vec();
+/// assert_eq!(isomorphisms, vec![vec![0, 1], vec![0, 2]]);
+/// ```
+#[derive(Debug)]
+pub struct Reversed<'a, G>(pub &'a G);
+
+impl<G> Clone for Reversed<'_, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<G> Copy for Reversed<'_, G> {}
+
+/// Returns the opposite of `direction`.
+fn reverse(direction: Direction) -> Direction {
+    match direction {
+        Direction::Outgoing => Direction::Incoming,
+        Direction::Incoming => Direction::Outgoing,
+    }
+}
+
+impl<G: Graph> Graph for Reversed<'_, G> {
+    type NodeLabel = G::NodeLabel;
+    type EdgeLabel = G::EdgeLabel;
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.0.is_directed()
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.0.node_count()
+    }
+
+    #[inline]
+    fn node_indices(&self) -> impl Iterator<Item = NodeIndex> {
+        self.0.node_indices()
+    }
+
+    #[inline]
+    fn contains_node(&self, node: NodeIndex) -> bool {
+        self.0.contains_node(node)
+    }
+
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.0.edge_count()
+    }
+
+    #[inline]
+    fn in_degree(&self, node: NodeIndex) -> usize {
+        self.0.out_degree(node)
+    }
+
+    #[inline]
+    fn out_degree(&self, node: NodeIndex) -> usize {
+        self.0.in_degree(node)
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.0.node_label(node)
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.0.neighbors(node, reverse(direction))
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.0.contains_edge(target, source)
+    }
+
+    #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.0.edge_multiplicity(target, source)
+    }
+
+    #[inline]
+    fn has_self_loop(&self, node: NodeIndex) -> bool {
+        self.0.has_self_loop(node)
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        self.0.edge_label(target, source)
+    }
+
+    #[inline]
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, &Self::EdgeLabel)> {
+        self.0.incident_edges(node, reverse(direction))
+    }
+
+    #[inline]
+    fn neighbors_with_edge(
+        &self,
+        node: NodeIndex,
+        direction: Direction,
+    ) -> impl Iterator<Item = (NodeIndex, EdgeId)> {
+        self.0.neighbors_with_edge(node, reverse(direction))
+    }
+
+    #[inline]
+    fn edge_label_by_id(&self, edge: EdgeId) -> Option<&Self::EdgeLabel> {
+        self.0.edge_label_by_id(edge)
+    }
+}
@@ -1,19 +1,65 @@
-use crate::{Direction, Graph, NodeIndex};
-use std::fmt::Debug;
+use crate::config::{Callbacks, Config, Root, SymmetricEdgeEqFn};
+use crate::graph_ref::GraphRef;
+use crate::label_index::LabelIndex;
+use crate::union_find::UnionFind;
+use crate::{
+    DefaultOnPartial, DefaultOnProgress, Direction, EdgeCtx, Graph, NodeIndex, NodeMatch,
+    SourcePreference, StateInfo, TerminalSetSizes, Vf2Error,
+};
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt::Debug;
 
 /// A reserved value indicating the node is uncovered.
-/// Assumes the graph size is below [`NodeIndex::MAX`].
+///
+/// Maps hold node indices of the other graph,
+/// so both graphs must have fewer nodes than this.
+/// See [`fits_reserved_values`].
 const NOT_IN_MAP: NodeIndex = NodeIndex::MAX;
 
 /// A reserved value indicating the node is not in the set.
+///
+/// Terminal sets hold the depth a node was added at,
+/// which is at least one since depth is incremented before a push.
 const NOT_IN_SET: NodeIndex = 0;
 
+/// Returns `true` if a graph with `node_count` nodes
+/// has no node index equal to a reserved value.
+pub(crate) fn fits_reserved_values(node_count: usize) -> bool {
+    node_count < NOT_IN_MAP
+}
+
+/// The largest data graph for which a dense adjacency matrix is built.
+///
+/// The matrix uses one bit per node pair,
+/// or about 12 MiB at this size.
+const DENSE_ADJACENCY_LIMIT: usize = 10_000;
+
+/// The most entries in a precomputed node or edge compatibility matrix.
+///
+/// The matrix uses one bit per pair, or 16 MiB at this size.
+const COMPAT_MATRIX_LIMIT: usize = 1 << 27;
+
 #[derive(Clone, Debug)]
-pub(crate) struct State<'a, Query, Data, NodeEq, EdgeEq> {
+pub(crate) struct State<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+where
+    Query: Graph,
+    Data: Graph,
+{
     /// Whether the subgraph is induced.
     induced: bool,
     /// Depth in the SSR tree.
     depth: usize,
+    /// Deepest depth reached so far.
+    max_depth: usize,
+    /// Number of query nodes a complete map covers.
+    ///
+    /// This is every query node, unless a single component
+    /// of the query graph is searched on its own.
+    map_len: usize,
     /// Query graph state.
     query: GraphState<'a, Query>,
     /// Data graph state.
@@ -24,80 +70,619 @@ pub(crate) struct State<'a, Query, Data, NodeEq, EdgeEq> {
     source_stack: Vec<Source>,
     /// The previous candidate pair at the current depth.
     previous: Option<Pair>,
+    /// A stack of candidate pairs, used when candidates
+    /// are not tried in index order.
+    ///
+    /// The value at index `i` holds the remaining candidates at depth `i`,
+    /// with the next candidate last.
+    candidate_stack: Vec<Vec<Pair>>,
+    /// A stack of extra data edge counts, used when
+    /// the induced check allows some extra data edges.
+    ///
+    /// The value at index `i` is the number of extra data edges
+    /// in the partial map at depth `i + 1`.
+    extra_edge_stack: Vec<usize>,
+    /// A stack of node label mismatch counts, used when
+    /// some node label mismatches are allowed.
+    ///
+    /// The value at index `i` is the number of mismatched node labels
+    /// in the partial map at depth `i + 1`.
+    mismatch_stack: Vec<usize>,
+    /// A stack of flags, used when query nodes may be merged.
+    ///
+    /// The value at index `i` is `true` if the pair at depth `i + 1`
+    /// reuses a data node already in the partial map.
+    merge_stack: Vec<bool>,
+    /// The value at index `i` is `true` if data node `i` is covered
+    /// by a query node that is not mergeable.
+    ///
+    /// Empty if no query node is mergeable.
+    held: Vec<bool>,
+    /// The value at index `i` is `true` if data node `i`
+    /// may no longer be mapped to.
+    ///
+    /// Empty if no data node is forbidden.
+    forbidden: Vec<bool>,
+    /// The value at index `i` is `true` if query node 0
+    /// may no longer map to data node `i`.
+    ///
+    /// Empty if no data node is forbidden for query node 0.
+    forbidden_roots: Vec<bool>,
+    /// Data edges that may no longer be mapped to.
+    ///
+    /// Undirected edges are stored with the lower node index first.
+    forbidden_edges: BTreeSet<(NodeIndex, NodeIndex)>,
+    /// Whether the current state has no successors so far.
+    leaf: bool,
+    /// Least depth of leaves whose partial maps are yielded,
+    /// or [`None`] to only yield isomorphisms.
+    min_matched: Option<usize>,
+    /// Whether the last step stopped at a leaf to yield its partial map.
+    at_partial: bool,
+    /// Whether the search is complete.
+    done: bool,
+    /// Number of steps left before the search gives up,
+    /// or [`None`] if it is unlimited.
+    budget: Option<usize>,
+    /// Whether the search gave up because the budget ran out.
+    budget_exhausted: bool,
+    /// Whether the search ended because the node equality function aborted it.
+    aborted: bool,
+    /// Number of candidate pairs at the root of the SSR tree.
+    root_total: usize,
+    /// Number of candidate pairs at the root of the SSR tree tried so far.
+    root_tried: usize,
     /// Node equality function.
     node_eq: Option<NodeEq>,
     /// Edge equality function.
     edge_eq: Option<EdgeEq>,
+    /// User callbacks.
+    callbacks: Callbacks<OnPartial, OnProgress>,
+    /// Node equality of each query and data node pair, if precomputed.
+    node_compat: Option<BitMatrix>,
+    /// Edge equality of each query and data edge pair, if precomputed.
+    ///
+    /// Rows and columns are positions in the outgoing neighbor lists.
+    edge_compat: Option<BitMatrix>,
+    /// The connected components of the query graph, if it is decomposed.
+    components: Option<Components>,
+    /// Search options.
+    config: Config<'a, Query, Data>,
 }
 
-impl<'a, Query, Data, NodeEq, EdgeEq> State<'a, Query, Data, NodeEq, EdgeEq>
+impl<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
+    State<'a, Query, Data, NodeEq, EdgeEq, OnPartial, OnProgress>
 where
     Query: Graph,
     Data: Graph,
     NodeEq: Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool,
     EdgeEq: Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool,
+    OnPartial: FnMut(&[NodeIndex], usize),
+    OnProgress: FnMut(f64),
 {
     /// Creates a new [`State`].
     pub(crate) fn new(
-        query: &'a Query,
-        data: &'a Data,
+        query: GraphRef<'a, Query>,
+        data: GraphRef<'a, Data>,
         node_eq: Option<NodeEq>,
         edge_eq: Option<EdgeEq>,
         induced: bool,
+        config: Config<'a, Query, Data>,
+        callbacks: Callbacks<OnPartial, OnProgress>,
     ) -> Self {
-        assert!(query.node_count() > 0, "query graph cannot be empty");
-        assert!(
-            query.node_count() <= data.node_count(),
-            "query graph cannot have more nodes than data graph"
-        );
-        assert!(
-            data.node_count() < NOT_IN_MAP,
-            "data graph is so large it uses reserved values"
+        debug_assert!(
+            fits_reserved_values(query.node_count()) && fits_reserved_values(data.node_count()),
+            "graphs should be checked before searching"
         );
-        Self {
+        let query_len = query.node_count();
+        let held = if config.mergeable.is_empty() {
+            Vec::new()
+        } else {
+            vec![false; data.node_count()]
+        };
+        let mut state = Self {
             induced,
             depth: 0,
-            query: GraphState::new(query),
-            data: GraphState::new(data),
-            source_stack: vec![Source::Outgoing; query.node_count()],
+            max_depth: 0,
+            map_len: query_len,
+            query: GraphState::new(query, config.query_cache.clone(), config.ignore_direction)
+                .with_optional_edges(&config.optional_edges),
+            data: GraphState::new(data, config.data_cache.clone(), config.ignore_direction)
+                .with_stack_len(query_len)
+                .with_adjacency(config.dense_adjacency),
+            source_stack: vec![Source::Outgoing; query_len],
             previous: None,
+            candidate_stack: vec![Vec::new(); query_len],
+            extra_edge_stack: vec![0; query_len],
+            mismatch_stack: vec![0; query_len],
+            merge_stack: vec![false; query_len],
+            held,
+            forbidden: config.occupied.clone(),
+            forbidden_roots: Vec::new(),
+            forbidden_edges: BTreeSet::new(),
+            leaf: false,
+            min_matched: None,
+            at_partial: false,
+            done: false,
+            budget: config.node_budget,
+            budget_exhausted: false,
+            aborted: false,
+            root_total: 0,
+            root_tried: 0,
             node_eq,
             edge_eq,
+            callbacks,
+            node_compat: None,
+            edge_compat: None,
+            components: None,
+            config,
+        };
+        if state.config.precompute_node_compat {
+            state.node_compat = state.node_compat_matrix();
+        }
+        if state.config.precompute_edge_compat {
+            state.edge_compat = state.edge_compat_matrix();
+        }
+        if state.config.decompose_components && state.config.query_order.is_empty() {
+            state.components = state.query_components();
         }
+        state
+    }
+
+    /// Returns the connected components of the query graph,
+    /// or [`None`] if it has only one.
+    ///
+    /// Optional edges are left out, since their far ends
+    /// are not added to the terminal sets.
+    fn query_components(&self) -> Option<Components> {
+        let query_len = self.query.map.len();
+        let mut sets = UnionFind::new(query_len);
+        for node in 0..query_len {
+            for &neighbor in self.query.neighbors(node, Direction::Outgoing) {
+                if !self.is_optional_edge(node, neighbor) {
+                    sets.union(node, neighbor);
+                }
+            }
+        }
+        // Components are numbered in the order of their lowest node.
+        let (mut numbers, mut count) = (vec![None; query_len], 0);
+        let of = (0..query_len)
+            .map(|node| {
+                *numbers[sets.find(node)].get_or_insert_with(|| {
+                    count += 1;
+                    count - 1
+                })
+            })
+            .collect();
+        (count > 1).then(|| Components {
+            of,
+            matches: vec![None; count],
+            cursors: vec![0; count],
+        })
+    }
+
+    /// Returns the node equality of every query and data node pair,
+    /// or [`None`] if there is no node equality function
+    /// or the matrix would be too large.
+    fn node_compat_matrix(&self) -> Option<BitMatrix> {
+        let node_eq = self.node_eq.as_ref()?;
+        let (rows, columns) = (self.query.map.len(), self.data.map.len());
+        if rows.checked_mul(columns)? > COMPAT_MATRIX_LIMIT {
+            return None;
+        }
+        let mut matrix = BitMatrix::new(rows, columns);
+        for query_node in 0..rows {
+            let query_label = self.query.node_label(query_node);
+            for data_node in 0..columns {
+                if node_eq(query_label, self.data.node_label(data_node)) {
+                    matrix.set(query_node, data_node);
+                }
+            }
+        }
+        Some(matrix)
+    }
+
+    /// Returns the edge equality of every query and data edge pair,
+    /// or [`None`] if edges are not compared
+    /// or the matrix would be too large.
+    ///
+    /// Each edge is read from its source to its target,
+    /// so undirected edges appear once in each orientation.
+    fn edge_compat_matrix(&self) -> Option<BitMatrix> {
+        if !self.checks_edges() {
+            return None;
+        }
+        let query_edges = self.query.cache.outgoing.edges();
+        let data_edges = self.data.cache.outgoing.edges();
+        let (rows, columns) = (query_edges.len(), data_edges.len());
+        if rows.checked_mul(columns)? > COMPAT_MATRIX_LIMIT {
+            return None;
+        }
+        let mut matrix = BitMatrix::new(rows, columns);
+        for (row, &query_edge) in query_edges.iter().enumerate() {
+            for (column, &data_edge) in data_edges.iter().enumerate() {
+                if self.edges_eq_uncached(query_edge, data_edge) {
+                    matrix.set(row, column);
+                }
+            }
+        }
+        Some(matrix)
     }
 
     /// Advances the search one step. Returns `true`
     /// if the map is ready or the search is complete.
     pub(crate) fn step(&mut self) -> bool {
-        if let Some(pair) = self.next_pair() {
+        if self.done {
+            return true;
+        }
+        if let Some(budget) = &mut self.budget {
+            if *budget == 0 {
+                self.budget_exhausted = true;
+                self.finish();
+                return true;
+            }
+            *budget -= 1;
+        }
+        if self.query.map.is_empty() {
+            // The empty map is the only isomorphism of an empty query.
+            // It is yielded on the first step, and the search ends on the next.
+            self.done = self.leaf;
+            self.leaf = true;
+            return true;
+        }
+        self.at_partial = false;
+        if self.depth == 0 && self.previous.is_some() {
+            self.report_progress();
+        }
+        let result = if let Some(component) = self.next_component() {
+            self.push_component(component)
+        } else if let Some(pair) = self.next_pair() {
+            if self.depth == 0 {
+                self.root_tried += 1;
+            }
             self.previous = Some(pair);
-            if self.feasible(pair) {
+            let result = self.node_match(pair);
+            if result == NodeMatch::Match && self.feasible(pair) {
                 self.push(pair);
             }
-            self.all_covered()
-        } else if self.depth > 0 {
-            self.pop();
-            false
+            Some(result)
         } else {
-            true
+            None
+        };
+        match result {
+            Some(NodeMatch::Abort) => {
+                self.aborted = true;
+                self.finish();
+                true
+            }
+            Some(_) => self.all_covered(),
+            None if self.depth > 0 => {
+                if self.leaf && !self.all_covered() {
+                    self.report_partial();
+                    if self.min_matched.is_some_and(|min| self.depth >= min) {
+                        // Backtrack on the next step.
+                        self.leaf = false;
+                        self.at_partial = true;
+                        return true;
+                    }
+                }
+                self.backtrack();
+                false
+            }
+            None => {
+                self.done = true;
+                true
+            }
+        }
+    }
+
+    /// Returns the query component whose next match is pushed
+    /// at the current depth, if the query graph is decomposed.
+    ///
+    /// The first component is searched as usual. Once the covered
+    /// query nodes make up whole components, the query terminal sets
+    /// are empty, and the uncovered component with the lowest node is next.
+    fn next_component(&self) -> Option<usize> {
+        let components = self.components.as_ref()?;
+        if self.depth == 0
+            || self.all_covered()
+            || self.query.outgoing_size > 0
+            || self.query.incoming_size > 0
+        {
+            return None;
+        }
+        let query_node = self.query.uncovered_nodes(0).next()?;
+        Some(components.of[query_node])
+    }
+
+    /// Pushes the pairs of the next match of `component`
+    /// that has not been tried at the current depth.
+    ///
+    /// A match whose pairs are not all feasible with the partial map
+    /// is popped again. Returns [`NodeMatch::Abort`] if the node match
+    /// function aborted the search, another result once a match is tried,
+    /// or [`None`] if no match is left.
+    fn push_component(&mut self, component: usize) -> Option<NodeMatch> {
+        if !self.search_component(component) {
+            return Some(NodeMatch::Abort);
+        }
+        let components = self.components.as_mut()?;
+        let next = match self.previous {
+            None => 0,
+            Some(_) => components.cursors[component],
+        };
+        let pairs = components.matches[component].as_ref()?.get(next)?.clone();
+        components.cursors[component] = next + 1;
+        let (depth, leaf) = (self.depth, self.leaf);
+        for &pair in &pairs {
+            let result = if self.may_map(pair.query_node, pair.data_node, Source::Uncovered) {
+                self.node_match(pair)
+            } else {
+                NodeMatch::NoMatch
+            };
+            if result != NodeMatch::Match || !self.feasible(pair) {
+                while self.depth > depth {
+                    self.pop();
+                }
+                // The match was not a successor of this state.
+                self.previous = Some(pairs[0]);
+                self.leaf = leaf;
+                return Some(result);
+            }
+            self.push(pair);
         }
+        Some(NodeMatch::Match)
+    }
+
+    /// Searches `component` of the query graph on its own
+    /// and keeps its matches, unless it has been searched already.
+    ///
+    /// Returns `false` if the node match function aborted the search.
+    fn search_component(&mut self, component: usize) -> bool {
+        let Some(components) = &self.components else {
+            return true;
+        };
+        if components.matches[component].is_some() {
+            return true;
+        }
+        let order = components.order(component, |node| {
+            let outgoing = self.query.neighbors(node, Direction::Outgoing);
+            let incoming = self.query.neighbors(node, Direction::Incoming);
+            outgoing.iter().chain(incoming).copied()
+        });
+        let mut config = self.config.clone();
+        config.root = None;
+        config.node_budget = None;
+        config.precompute_node_compat = false;
+        config.precompute_edge_compat = false;
+        config.query_cache = Some(Arc::clone(&self.query.cache));
+        config.data_cache = Some(Arc::clone(&self.data.cache));
+        config.decompose_components = false;
+        config.query_order = order;
+        let node_eq = self
+            .node_eq
+            .as_ref()
+            .map(|node_eq| node_eq as &dyn Fn(&Query::NodeLabel, &Data::NodeLabel) -> bool);
+        let edge_eq = self
+            .edge_eq
+            .as_ref()
+            .map(|edge_eq| edge_eq as &dyn Fn(&Query::EdgeLabel, &Data::EdgeLabel) -> bool);
+        let mut search = State::<_, _, _, _, DefaultOnPartial, DefaultOnProgress>::new(
+            self.query.graph.clone(),
+            self.data.graph.clone(),
+            node_eq,
+            edge_eq,
+            self.induced,
+            config,
+            Callbacks::default(),
+        );
+        search.map_len = search.config.query_order.len();
+        let mut matches = Vec::new();
+        while !search.is_done() {
+            if search.step() && search.all_covered() {
+                let query = &search.query;
+                let pairs = query.node_stack[..search.depth]
+                    .iter()
+                    .map(|&query_node| Pair::new(query_node, query.map[query_node]));
+                matches.push(pairs.collect());
+            }
+        }
+        let aborted = search.aborted();
+        if let Some(components) = &mut self.components {
+            components.matches[component] = Some(matches);
+        }
+        !aborted
+    }
+
+    /// Pops the last pair from the partial map, or all pairs
+    /// of the last component match if they were pushed together.
+    fn backtrack(&mut self) {
+        let component = self.component_of(self.query.node_stack[self.depth - 1]);
+        self.pop();
+        if component == self.component_of(self.query.node_stack[0]) {
+            return;
+        }
+        while self.depth > 0
+            && self.component_of(self.query.node_stack[self.depth - 1]) == component
+        {
+            self.pop();
+        }
+    }
+
+    /// Returns the component of `query_node`,
+    /// or [`None`] if the query graph is not decomposed.
+    fn component_of(&self, query_node: NodeIndex) -> Option<usize> {
+        self.components
+            .as_ref()
+            .map(|components| components.of[query_node])
+    }
+
+    /// Returns the depth from which the pairs of whole component matches
+    /// are pushed, or [`None`] if the query graph is not decomposed
+    /// or nothing is covered.
+    fn component_depth(&self) -> Option<usize> {
+        let components = self.components.as_ref().filter(|_| self.depth > 0)?;
+        let first = components.of[self.query.node_stack[0]];
+        Some(
+            components
+                .of
+                .iter()
+                .filter(|&&component| component == first)
+                .count(),
+        )
+    }
+
+    /// Forbids mapping to `data_nodes` for the rest of the search.
+    ///
+    /// Backtracks out of any branch whose partial map
+    /// already maps to a forbidden data node.
+    pub(crate) fn forbid_data_nodes(&mut self, data_nodes: &[NodeIndex]) {
+        if self.forbidden.is_empty() {
+            self.forbidden = vec![false; self.data.map.len()];
+        }
+        for &data_node in data_nodes {
+            self.forbidden[data_node] = true;
+        }
+        let forbidden_depth = (0..self.depth).find(|&depth| {
+            let data_node = self.query.map[self.query.node_stack[depth]];
+            self.forbidden[data_node]
+        });
+        if let Some(forbidden_depth) = forbidden_depth {
+            while self.depth > forbidden_depth {
+                self.pop();
+            }
+        }
+    }
+
+    /// Forbids mapping query node 0 to `data_node`
+    /// for the rest of the search.
+    ///
+    /// Backtracks out of any branch whose partial map
+    /// already maps query node 0 to `data_node`.
+    pub(crate) fn forbid_root(&mut self, data_node: NodeIndex) {
+        if self.forbidden_roots.is_empty() {
+            self.forbidden_roots = vec![false; self.data.map.len()];
+        }
+        self.forbidden_roots[data_node] = true;
+        let forbidden_depth = (0..self.depth)
+            .find(|&depth| self.query.node_stack[depth] == 0 && self.query.map[0] == data_node);
+        if let Some(forbidden_depth) = forbidden_depth {
+            while self.depth > forbidden_depth {
+                self.pop();
+            }
+        }
+    }
+
+    /// Forbids mapping to `data_edges` for the rest of the search.
+    ///
+    /// Backtracks out of any branch whose partial map
+    /// already maps a query edge to a forbidden data edge.
+    pub(crate) fn forbid_data_edges(&mut self, data_edges: &[(NodeIndex, NodeIndex)]) {
+        for &(source, target) in data_edges {
+            let key = self.edge_key(source, target);
+            self.forbidden_edges.insert(key);
+        }
+        // The value at index `i` is the depth query node `i` was covered at.
+        let mut depths = vec![usize::MAX; self.query.map.len()];
+        for depth in 0..self.depth {
+            depths[self.query.node_stack[depth]] = depth;
+        }
+        // An edge is added to the partial map with its later-covered end.
+        let forbidden_depth = (0..self.depth).find(|&depth| {
+            let query_node = self.query.node_stack[depth];
+            let pair = Pair::new(query_node, self.query.map[query_node]);
+            self.uses_forbidden_edge(pair, |neighbor| depths[neighbor] < depth)
+        });
+        if let Some(forbidden_depth) = forbidden_depth {
+            while self.depth > forbidden_depth {
+                self.pop();
+            }
+        }
+    }
+
+    /// Ends the search without exploring the remaining states.
+    pub(crate) fn finish(&mut self) {
+        while self.depth > 0 {
+            self.pop();
+        }
+        self.done = true;
     }
 
     /// Pushes `pair` to the partial map. Increments depth.
     fn push(&mut self, pair: Pair) {
+        if self.induced && self.config.induced_edge_slack > 0 {
+            self.extra_edge_stack[self.depth] =
+                self.extra_edges() + self.pair_extra_edges(pair, usize::MAX);
+        }
+        if self.config.node_mismatch_budget > 0 {
+            self.mismatch_stack[self.depth] =
+                self.mismatches() + usize::from(!self.nodes_are_eq(pair));
+        }
+        let merged = self.data.is_covered(pair.data_node);
+        self.merge_stack[self.depth] = merged;
+        if self.merges() && !self.is_mergeable(pair.query_node) {
+            self.held[pair.data_node] = true;
+        }
         self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
         self.previous = None;
-        self.query.push(pair.query_node, pair.data_node, self.depth);
-        self.data.push(pair.data_node, pair.query_node, self.depth);
+        self.leaf = true;
+        if self.is_single_node() {
+            self.query
+                .cover(pair.query_node, pair.data_node, self.depth);
+            self.data.cover(pair.data_node, pair.query_node, self.depth);
+        } else {
+            self.query.push(pair.query_node, pair.data_node, self.depth);
+            if !merged {
+                self.data.push(pair.data_node, pair.query_node, self.depth);
+            }
+        }
     }
 
     /// Pops the last pair from the partial map. Decrements depth.
     fn pop(&mut self) {
-        self.previous = Some(Pair {
-            query_node: self.query.pop(self.depth),
-            data_node: self.data.pop(self.depth),
-        });
+        let pair = if self.is_single_node() {
+            Pair::new(
+                self.query.uncover(self.depth),
+                self.data.uncover(self.depth),
+            )
+        } else if self.merge_stack[self.depth - 1] {
+            // The data node stays covered by the query node mapped to it first.
+            let data_node = self.query.map[self.query.node_stack[self.depth - 1]];
+            Pair::new(self.query.pop(self.depth), data_node)
+        } else {
+            Pair::new(self.query.pop(self.depth), self.data.pop(self.depth))
+        };
+        if self.merges() && !self.is_mergeable(pair.query_node) {
+            self.held[pair.data_node] = false;
+        }
+        self.previous = Some(pair);
         self.depth -= 1;
+        self.leaf = false;
+    }
+
+    /// Returns `true` if the query graph has a single node.
+    ///
+    /// Matching a single node only tries each data node in turn,
+    /// so the terminal sets are not updated.
+    fn is_single_node(&self) -> bool {
+        self.query.map.len() == 1
+    }
+
+    /// Passes the partial map to the partial map callback, if any.
+    fn report_partial(&mut self) {
+        if let Some(on_partial) = &mut self.callbacks.on_partial {
+            on_partial(&self.query.map, self.depth);
+        }
+    }
+
+    /// Passes the fraction of root candidates tried
+    /// to the progress callback, if any.
+    fn report_progress(&mut self) {
+        if let Some(on_progress) = &mut self.callbacks.on_progress {
+            let progress = self.root_tried as f64 / self.root_total.max(1) as f64;
+            on_progress(progress.min(1.0));
+        }
     }
 
     /// Returns the next candidate pair.
@@ -105,51 +690,344 @@ where
         if self.all_covered() {
             None
         } else if let Some(previous) = self.previous {
-            let source = self.source_stack[self.depth];
-            self.following_pair(source, previous)
+            if self.uses_candidate_stack(self.depth) {
+                self.candidate_stack[self.depth].pop()
+            } else {
+                let source = self.source_stack[self.depth];
+                self.following_pair(source, previous)
+            }
+        } else if let Some(root) = self.config.root.filter(|_| self.depth == 0) {
+            self.root_candidates(root);
+            self.root_total = self.candidate_stack[0].len();
+            self.candidate_stack[self.depth].pop()
         } else {
-            self.first_pair().map(|(pair, source)| {
-                self.source_stack[self.depth] = source;
-                pair
-            })
+            let (pair, source) = self.first_pair()?;
+            self.source_stack[self.depth] = source;
+            if self.depth == 0 && self.callbacks.on_progress.is_some() {
+                let mut nodes = Vec::new();
+                self.data_candidates(pair.query_node, source, &mut nodes);
+                self.root_total = nodes.len();
+            }
+            if self.config.prefer_dense {
+                self.order_candidates(pair.query_node, source);
+                self.candidate_stack[self.depth].pop()
+            } else {
+                Some(pair)
+            }
         }
     }
 
+    /// Returns `true` if candidate pairs at `depth`
+    /// are taken from the candidate stack.
+    fn uses_candidate_stack(&self, depth: usize) -> bool {
+        self.config.prefer_dense || (depth == 0 && self.config.root.is_some())
+    }
+
+    /// Fills the candidate stack at the root with the pairs allowed by `root`.
+    fn root_candidates(&mut self, root: Root) {
+        let candidates = &mut self.candidate_stack[0];
+        candidates.clear();
+        match root {
+            Root::Pair(query_node, data_node) => {
+                candidates.push(Pair::new(query_node, data_node));
+            }
+            Root::DataNode(data_node) => candidates.extend(
+                (0..self.query.map.len())
+                    .rev()
+                    .map(|query_node| Pair::new(query_node, data_node)),
+            ),
+        }
+        if let Some(label_index) = &self.config.label_index {
+            let wildcards = &self.config.wildcards;
+            candidates.retain(|pair| {
+                wildcards.get(pair.query_node) == Some(&true)
+                    || label_index.allows(pair.query_node, pair.data_node)
+            });
+        }
+        let restricted = &self.config.candidates;
+        candidates.retain(|pair| match restricted.get(pair.query_node) {
+            Some(Some(data_nodes)) => data_nodes.binary_search(&pair.data_node).is_ok(),
+            _ => true,
+        });
+    }
+
+    /// Fills the candidate stack at the current depth with pairs of
+    /// `query_node` and the data nodes in `source`, ordered so the data node
+    /// with the most covered neighbors is popped first.
+    /// Ties are broken by lowest index.
+    fn order_candidates(&mut self, query_node: NodeIndex, source: Source) {
+        let mut nodes = Vec::new();
+        self.data_candidates(query_node, source, &mut nodes);
+        nodes.sort_by_key(|&node| (self.data.covered_neighbors(node), Reverse(node)));
+        let candidates = &mut self.candidate_stack[self.depth];
+        candidates.clear();
+        candidates.extend(
+            nodes
+                .into_iter()
+                .map(|data_node| Pair::new(query_node, data_node)),
+        );
+    }
+
+    /// Appends the data nodes in `source` that are candidates
+    /// for `query_node` to `nodes`.
+    fn data_candidates(&self, query_node: NodeIndex, source: Source, nodes: &mut Vec<NodeIndex>) {
+        match self.restricted_candidates(query_node) {
+            None if self.merges() => nodes.extend(
+                (0..self.data.map.len()).filter(|&node| self.may_map(query_node, node, source)),
+            ),
+            None => self.data.extend_with_nodes(source, nodes),
+            Some(restricted) => nodes.extend(
+                restricted
+                    .iter()
+                    .copied()
+                    .filter(|&node| self.may_map(query_node, node, source)),
+            ),
+        }
+    }
+
+    /// Returns the sorted data nodes `query_node` is restricted to,
+    /// if any.
+    ///
+    /// These are the user-supplied candidates if set,
+    /// or else the label index bucket.
+    fn restricted_candidates(&self, query_node: NodeIndex) -> Option<&[NodeIndex]> {
+        if let Some(Some(candidates)) = self.config.candidates.get(query_node) {
+            return Some(candidates);
+        }
+        self.label_index(query_node)
+            .map(|label_index| label_index.bucket(query_node))
+    }
+
+    /// Returns the label index used to restrict
+    /// the candidates for `query_node`, if any.
+    fn label_index(&self, query_node: NodeIndex) -> Option<&LabelIndex> {
+        self.config
+            .label_index
+            .as_deref()
+            .filter(|_| !self.is_wildcard(query_node))
+    }
+
+    /// Returns `true` if `query_node` matches any data node regardless of label.
+    fn is_wildcard(&self, query_node: NodeIndex) -> bool {
+        self.config.wildcards.get(query_node) == Some(&true)
+    }
+
+    /// Returns `true` if `query_node` may map to a covered data node.
+    ///
+    /// Its candidates are then all data nodes, not only those in the source.
+    fn is_mergeable(&self, query_node: NodeIndex) -> bool {
+        self.config.mergeable.get(query_node) == Some(&true)
+    }
+
+    /// Returns `true` if `data_node` is a candidate for `query_node`
+    /// from `source`.
+    ///
+    /// A query node that is not mergeable may still map to a data node
+    /// covered only by mergeable query nodes,
+    /// so merging does not depend on which query node is mapped first.
+    fn may_map(&self, query_node: NodeIndex, data_node: NodeIndex, source: Source) -> bool {
+        self.is_mergeable(query_node)
+            || self.data.in_source(data_node, source)
+            || (self.merges() && self.data.is_covered(data_node) && !self.held[data_node])
+    }
+
+    /// Returns `true` if the query edge from `source` to `target`
+    /// need not map to a data edge.
+    fn is_optional_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        if self.config.optional_edges.is_empty() {
+            return false;
+        }
+        // A directed query read as undirected stores either orientation.
+        let optional_edges = &self.config.optional_edges;
+        optional_edges.contains(&(source, target))
+            || (!self.is_directed() && optional_edges.contains(&(target, source)))
+    }
+
+    /// Returns `true` if the query or data graph has parallel edges,
+    /// so edge multiplicities are compared.
+    fn checks_multiplicity(&self) -> bool {
+        self.query.cache.parallel_edges || self.data.cache.parallel_edges
+    }
+
+    /// Returns `true` if the query edge has a compatible multiplicity
+    /// with the data edge, where each is read from its source to its target.
+    ///
+    /// The multiplicities must be equal if the subgraph is induced,
+    /// or else the data edge must have at least as many parallel edges.
+    fn multiplicity_fits(
+        &self,
+        (query_source, query_target): (NodeIndex, NodeIndex),
+        (data_source, data_target): (NodeIndex, NodeIndex),
+    ) -> bool {
+        if !self.checks_multiplicity() {
+            return true;
+        }
+        let query = self
+            .query
+            .graph
+            .edge_multiplicity(query_source, query_target);
+        let data = self.data.graph.edge_multiplicity(data_source, data_target);
+        if self.induced {
+            query == data
+        } else {
+            query <= data
+        }
+    }
+
+    /// Returns `true` if some query nodes may be merged,
+    /// so the partial map need not be injective.
+    fn merges(&self) -> bool {
+        !self.config.mergeable.is_empty()
+    }
+
     /// Returns the first candidate pair and its source.
+    ///
+    /// The source is chosen by the query terminal sets alone,
+    /// so each connected component of the query is covered
+    /// before the next is started from an uncovered node.
+    /// If the query has terminal nodes but the data graph has none
+    /// in the same set, no pair is feasible and the search backtracks.
     fn first_pair(&self) -> Option<(Pair, Source)> {
-        let source = if self.query.outgoing_size > 0 && self.data.outgoing_size > 0 {
-            Source::Outgoing
-        } else if self.query.incoming_size > 0 && self.data.incoming_size > 0 {
-            Source::Incoming
-        } else {
-            Source::Uncovered
-        };
+        if let Some(&query_node) = self.config.query_order.get(self.depth) {
+            return self.first_pair_for(query_node);
+        }
+        let source = self
+            .terminal_sources()
+            .into_iter()
+            .find(|&source| match source {
+                Source::Outgoing => self.query.outgoing_size > 0,
+                Source::Incoming => self.query.incoming_size > 0,
+                Source::Uncovered => false,
+            })
+            .unwrap_or(Source::Uncovered);
         self.first_pair_in(source).map(|pair| (pair, source))
     }
 
+    /// Returns the terminal sets in the order they are tried.
+    fn terminal_sources(&self) -> [Source; 2] {
+        match self.config.terminal_preference {
+            SourcePreference::Outgoing => [Source::Outgoing, Source::Incoming],
+            SourcePreference::Incoming => [Source::Incoming, Source::Outgoing],
+        }
+    }
+
+    /// Returns the first candidate pair for `query_node` and its source,
+    /// which is the first terminal set `query_node` is in.
+    fn first_pair_for(&self, query_node: NodeIndex) -> Option<(Pair, Source)> {
+        let source = self
+            .terminal_sources()
+            .into_iter()
+            .find(|&source| self.query.in_source(query_node, source))
+            .unwrap_or(Source::Uncovered);
+        self.next_data_node(query_node, source, 0)
+            .map(|data_node| (Pair::new(query_node, data_node), source))
+    }
+
     /// Returns the first candidate pair from `source`.
     fn first_pair_in(&self, source: Source) -> Option<Pair> {
-        if let Some(query_node) = self.query.first_node(source) {
-            if let Some(data_node) = self.data.first_node(source) {
-                return Some(Pair::new(query_node, data_node));
-            }
-        }
-        None
+        let query_node = self.query.first_node(source)?;
+        self.next_data_node(query_node, source, 0)
+            .map(|data_node| Pair::new(query_node, data_node))
     }
 
     /// Returns the candidate pair from `source` following `previous`.
     fn following_pair(&self, source: Source, previous: Pair) -> Option<Pair> {
-        self.data
-            .next_node(source, previous.data_node + 1)
+        self.next_data_node(previous.query_node, source, previous.data_node + 1)
             .map(|data_node| Pair::new(previous.query_node, data_node))
     }
 
+    /// Returns the next data node in `source` beginning at `skip`
+    /// that is a candidate for `query_node`.
+    ///
+    /// With a label index, only data nodes with
+    /// the same label as `query_node` are visited.
+    /// A mergeable query node visits data nodes outside `source` too.
+    fn next_data_node(
+        &self,
+        query_node: NodeIndex,
+        source: Source,
+        skip: usize,
+    ) -> Option<NodeIndex> {
+        match self.restricted_candidates(query_node) {
+            None if self.merges() => {
+                (skip..self.data.map.len()).find(|&node| self.may_map(query_node, node, source))
+            }
+            None => self.data.next_node(source, skip),
+            Some(restricted) => restricted[restricted.partition_point(|&node| node < skip)..]
+                .iter()
+                .copied()
+                .find(|&node| self.may_map(query_node, node, source)),
+        }
+    }
+
     /// Returns `true` if a successor state would remain
     /// consistent with `pair` in the partial map.
     ///
     /// This is *F(s, n, m)* in the original VF2 paper.
     fn feasible(&self, pair: Pair) -> bool {
-        self.feasible_syntactic(pair) && self.feasible_semantic(pair)
+        !self.is_forbidden(pair.data_node)
+            && !self.is_forbidden_root(pair)
+            && fits_order(&self.config.component_order, &self.query.map, pair)
+            && fits_order(&self.config.index_order, &self.query.map, pair)
+            && !self.uses_forbidden_edge(pair, |neighbor| self.query.is_covered(neighbor))
+            && self.feasible_syntactic(pair)
+            && self.feasible_semantic(pair)
+    }
+
+    /// Returns `true` if the pair maps query node 0
+    /// to a data node it may no longer map to.
+    fn is_forbidden_root(&self, pair: Pair) -> bool {
+        pair.query_node == 0 && self.forbidden_roots.get(pair.data_node) == Some(&true)
+    }
+
+    /// Returns `true` if `data_node` may no longer be mapped to.
+    fn is_forbidden(&self, data_node: NodeIndex) -> bool {
+        self.forbidden.get(data_node) == Some(&true)
+    }
+
+    /// Returns `true` if the pair maps a query self-loop, or an edge to
+    /// a query neighbor for which `earlier` returns `true`,
+    /// to a forbidden data edge.
+    fn uses_forbidden_edge(&self, pair: Pair, earlier: impl Fn(NodeIndex) -> bool) -> bool {
+        if self.forbidden_edges.is_empty() {
+            return false;
+        }
+        let is_forbidden = |source, target| {
+            self.forbidden_edges
+                .contains(&self.edge_key(source, target))
+        };
+        let directions: &[Direction] = if self.is_directed() {
+            &[Direction::Outgoing, Direction::Incoming]
+        } else {
+            // This will check all neighbors since the graphs are undirected.
+            &[Direction::Outgoing]
+        };
+        let uses_neighbor_edge = directions.iter().any(|&direction| {
+            self.query
+                .neighbors(pair.query_node, direction)
+                .iter()
+                .filter(|&&neighbor| neighbor != pair.query_node && earlier(neighbor))
+                .any(|&neighbor| {
+                    let mapped = self.query.map[neighbor];
+                    match direction {
+                        Direction::Outgoing => is_forbidden(pair.data_node, mapped),
+                        Direction::Incoming => is_forbidden(mapped, pair.data_node),
+                    }
+                })
+        });
+        uses_neighbor_edge
+            || (self.query.graph.has_self_loop(pair.query_node)
+                && is_forbidden(pair.data_node, pair.data_node))
+    }
+
+    /// Returns the key of the data edge from `source` to `target`
+    /// in the forbidden edges.
+    fn edge_key(&self, source: NodeIndex, target: NodeIndex) -> (NodeIndex, NodeIndex) {
+        if self.is_directed() {
+            (source, target)
+        } else {
+            (source.min(target), source.max(target))
+        }
     }
 
     /// Returns `true` if a successor state would remain
@@ -158,6 +1036,9 @@ where
     ///
     /// This is *F_syn* in the original VF2 paper.
     fn feasible_syntactic(&self, pair: Pair) -> bool {
+        if !self.rule_degree(pair) {
+            return false;
+        }
         let consistent = if self.is_directed() {
             self.rule_neighbors(pair, Direction::Incoming)
                 && self.rule_neighbors(pair, Direction::Outgoing)
@@ -165,7 +1046,181 @@ where
             // This will check all neighbors since the graphs are undirected.
             self.rule_neighbors(pair, Direction::Incoming)
         };
-        consistent && self.rule_in(pair) && self.rule_out(pair) && self.rule_new(pair)
+        consistent
+            && self.rule_self_loop(pair)
+            && self.rule_induced(pair)
+            && self.rule_in(pair)
+            && self.rule_out(pair)
+            && self.rule_new(pair)
+    }
+
+    /// Returns `true` if the data node has at least
+    /// as many neighbors as the query node in each direction.
+    ///
+    /// Every query edge maps to a distinct data edge,
+    /// so a data node with fewer neighbors can never cover the query node.
+    /// This does not hold if query nodes may be merged
+    /// or query edges are optional. It also prunes partial maps
+    /// that cannot be completed, so it is skipped when they are yielded.
+    fn rule_degree(&self, pair: Pair) -> bool {
+        if self.merges() || !self.config.optional_edges.is_empty() || self.min_matched.is_some() {
+            return true;
+        }
+        // The cached neighbors are listed once per edge,
+        // or once per neighbor if the direction is ignored.
+        let fits = |direction| {
+            self.query.neighbors(pair.query_node, direction).len()
+                <= self.data.neighbors(pair.data_node, direction).len()
+        };
+        // If the graph is undirected, the outgoing neighbors are all neighbors.
+        fits(Direction::Outgoing) && (!self.is_directed() || fits(Direction::Incoming))
+    }
+
+    /// Returns `true` if a query self-loop has a data self-loop in the pair,
+    /// or the query self-loop is optional.
+    ///
+    /// A node is not covered while its own pair is checked,
+    /// so self-loops are not checked by the neighbors rules.
+    /// Extra data self-loops are checked by [`Self::rule_induced`].
+    fn rule_self_loop(&self, pair: Pair) -> bool {
+        if !self.query.graph.has_self_loop(pair.query_node) {
+            return true;
+        }
+        if !self.data.graph.has_self_loop(pair.data_node) {
+            return self.is_optional_edge(pair.query_node, pair.query_node);
+        }
+        self.multiplicity_fits(
+            (pair.query_node, pair.query_node),
+            (pair.data_node, pair.data_node),
+        )
+    }
+
+    /// Returns `true` if the subgraph is not induced, or adding the pair
+    /// would keep the number of extra data edges within the slack.
+    ///
+    /// An extra data edge is one between covered data nodes,
+    /// or a self-loop, with no counterpart in the query graph.
+    fn rule_induced(&self, pair: Pair) -> bool {
+        if !self.induced {
+            return true;
+        }
+        let slack = self.config.induced_edge_slack - self.extra_edges();
+        self.pair_extra_edges(pair, slack) <= slack
+    }
+
+    /// Returns the number of extra data edges that adding the pair would add,
+    /// counting no further once it exceeds `limit`.
+    fn pair_extra_edges(&self, pair: Pair, limit: usize) -> usize {
+        let data_loop = self.data.graph.has_self_loop(pair.data_node);
+        let mut extra = usize::from(
+            data_loop
+                && !self.query.graph.has_self_loop(pair.query_node)
+                && !self.is_transparent((pair.data_node, pair.data_node)),
+        );
+        if self.depth == 0 {
+            // No other data node is covered yet.
+            return extra;
+        }
+        let directions: &[Direction] = if self.is_directed() {
+            &[Direction::Incoming, Direction::Outgoing]
+        } else {
+            // This will check all neighbors since the graphs are undirected.
+            &[Direction::Incoming]
+        };
+        for &direction in directions {
+            if extra > limit {
+                break;
+            }
+            extra += self.extra_neighbor_edges(pair, direction, limit - extra);
+        }
+        extra
+    }
+
+    /// Returns the number of extra data edges in `direction` between
+    /// the data node in the pair and covered data nodes,
+    /// counting no further once it exceeds `limit`.
+    fn extra_neighbor_edges(&self, pair: Pair, direction: Direction, limit: usize) -> usize {
+        match &self.config.transparent_edge {
+            None => {
+                let neighbors = self.data.neighbors(pair.data_node, direction);
+                let edges = neighbors.iter().map(|&neighbor| (neighbor, None));
+                self.count_extra_edges(pair, direction, limit, edges)
+            }
+            // The incident edges keep their direction, so look up each edge.
+            Some(_) if self.data.ignores_direction() => {
+                let neighbors = self.data.neighbors(pair.data_node, direction);
+                let edges = neighbors.iter().map(|&neighbor| {
+                    (
+                        neighbor,
+                        Some(self.data.edge_label(pair.data_node, neighbor)),
+                    )
+                });
+                self.count_extra_edges(pair, direction, limit, edges)
+            }
+            // Read the labels along with the neighbors
+            // instead of looking up each edge.
+            Some(_) => {
+                let edges = self
+                    .data
+                    .graph
+                    .incident_edges(pair.data_node, direction)
+                    .map(|(neighbor, label)| (neighbor, Some(label)));
+                self.count_extra_edges(pair, direction, limit, edges)
+            }
+        }
+    }
+
+    /// Returns the number of `edges` from the data node in the pair
+    /// in `direction` to covered data nodes that are extra data edges,
+    /// counting no further once it exceeds `limit`.
+    ///
+    /// Each edge is a neighbor and the edge label, if it is needed
+    /// to check whether the edge is transparent.
+    fn count_extra_edges<'b>(
+        &self,
+        pair: Pair,
+        direction: Direction,
+        limit: usize,
+        edges: impl Iterator<Item = (NodeIndex, Option<&'b Data::EdgeLabel>)>,
+    ) -> usize
+    where
+        Data::EdgeLabel: 'b,
+    {
+        let mut extra = 0;
+        for (neighbor, label) in edges {
+            if !self.data.is_covered(neighbor) {
+                continue;
+            }
+            let mapped = self.data.map[neighbor];
+            let (source, target) = match direction {
+                Direction::Outgoing => (pair.query_node, mapped),
+                Direction::Incoming => (mapped, pair.query_node),
+            };
+            if self.query.contains_edge(source, target) {
+                continue;
+            }
+            let transparent = label.is_some_and(|label| {
+                self.config
+                    .transparent_edge
+                    .as_ref()
+                    .is_some_and(|transparent_edge| transparent_edge(label))
+            });
+            if !transparent {
+                extra += 1;
+                if extra > limit {
+                    break;
+                }
+            }
+        }
+        extra
+    }
+
+    /// Returns the number of extra data edges in the partial map.
+    fn extra_edges(&self) -> usize {
+        match self.depth {
+            0 => 0,
+            depth => self.extra_edge_stack[depth - 1],
+        }
     }
 
     /// Returns `true` if the predecessors or successors rule
@@ -174,43 +1229,42 @@ where
     /// [`Direction::Incoming`] is the predecessors rule.
     ///
     /// This is *R_pred* or *R_succ* in the original VF2 paper.
+    /// Optional query edges are not required.
+    /// Edge multiplicities are compared if a graph has parallel edges.
     fn rule_neighbors(&self, pair: Pair, direction: Direction) -> bool {
         let source_target = |node, neighbor| match direction {
             Direction::Outgoing => (node, neighbor),
             Direction::Incoming => (neighbor, node),
         };
-        for neighbor in self
-            .query
-            .graph
-            // If the graph is undirected, this returns all neighbors.
-            .neighbors(pair.query_node, direction)
-            .filter(|&n| self.query.is_covered(n))
-        {
+        for &neighbor in self.query.neighbors(pair.query_node, direction) {
+            if !self.query.is_covered(neighbor) {
+                continue;
+            }
             let mapped = self.query.map[neighbor];
+            let (query_source, query_target) = source_target(pair.query_node, neighbor);
             let (source, target) = source_target(pair.data_node, mapped);
-            if !self.data.graph.contains_edge(source, target) {
+            if !self.data.contains_edge(source, target) {
+                if self.is_optional_edge(query_source, query_target) {
+                    continue;
+                }
                 return false;
             }
-        }
-        if !self.induced {
-            return true;
-        }
-        for neighbor in self
-            .data
-            .graph
-            // If the graph is undirected, this returns all neighbors.
-            .neighbors(pair.data_node, direction)
-            .filter(|&n| self.data.is_covered(n))
-        {
-            let mapped = self.data.map[neighbor];
-            let (source, target) = source_target(pair.query_node, mapped);
-            if !self.query.graph.contains_edge(source, target) {
+            if !self.multiplicity_fits((query_source, query_target), (source, target)) {
                 return false;
             }
         }
         true
     }
 
+    /// Returns `true` if the data edge from `source` to `target`
+    /// is treated as absent when checking that a subgraph is induced.
+    fn is_transparent(&self, (source, target): (NodeIndex, NodeIndex)) -> bool {
+        self.config
+            .transparent_edge
+            .as_ref()
+            .is_some_and(|transparent_edge| transparent_edge(self.data.edge_label(source, target)))
+    }
+
     /// Returns `true` if the in rule is satisfied.
     ///
     /// This is *R_in* in the original VF2 paper.
@@ -244,78 +1298,459 @@ where
     ///
     /// This is *F_sem* in the original VF2 paper.
     fn feasible_semantic(&self, pair: Pair) -> bool {
-        self.nodes_are_eq(pair)
+        (self.nodes_are_eq(pair) || self.mismatches() < self.config.node_mismatch_budget)
+            && self.self_loops_are_eq(pair)
             && if self.is_directed() {
                 self.edges_are_eq(pair, Direction::Incoming)
                     && self.edges_are_eq(pair, Direction::Outgoing)
             } else {
                 // This will check all neighbors since the graphs are undirected.
-                self.edges_are_eq(pair, Direction::Incoming)
+                // The labels are still fetched from the pair node to each neighbor.
+                self.edges_are_eq(pair, Direction::Outgoing)
+            }
+    }
+
+    /// Returns the number of mismatched node labels in the partial map.
+    fn mismatches(&self) -> usize {
+        match self.depth {
+            0 => 0,
+            depth => self.mismatch_stack[depth - 1],
+        }
+    }
+
+    /// Returns `true` if the nodes in the pair
+    /// are semantically equivalent.
+    fn nodes_are_eq(&self, pair: Pair) -> bool {
+        if self.is_wildcard(pair.query_node) {
+            return true;
+        }
+        let node_eq = match &self.node_eq {
+            None => return true,
+            Some(node_eq) => node_eq,
+        };
+        if let Some(node_compat) = &self.node_compat {
+            return node_compat.get(pair.query_node, pair.data_node);
+        }
+        node_eq(
+            self.query.node_label(pair.query_node),
+            self.data.node_label(pair.data_node),
+        )
+    }
+
+    /// Returns `true` if the self-loops of the nodes in the pair
+    /// are semantically equivalent, or the query node has none.
+    /// An optional query self-loop is only compared if the data self-loop exists.
+    fn self_loops_are_eq(&self, pair: Pair) -> bool {
+        !self.checks_edges()
+            || !self.query.graph.has_self_loop(pair.query_node)
+            || !self.data.graph.has_self_loop(pair.data_node)
+            || self.edges_eq(
+                (pair.query_node, pair.query_node),
+                (pair.data_node, pair.data_node),
+            )
+    }
+
+    /// Returns the result of the aborting node equality function
+    /// for the nodes in the pair, or [`NodeMatch::Match`] if there is none.
+    fn node_match(&self, pair: Pair) -> NodeMatch {
+        match &self.config.node_match {
+            _ if self.is_wildcard(pair.query_node) => NodeMatch::Match,
+            None => NodeMatch::Match,
+            Some(node_match) => node_match(
+                self.query.node_label(pair.query_node),
+                self.data.node_label(pair.data_node),
+            ),
+        }
+    }
+
+    /// Returns `true` if the pair edges in `direction`
+    /// are semantically equivalent.
+    ///
+    /// The label of the query edge from `a` to `b` is always compared with
+    /// the label of the data edge from the node `a` maps to, to the node
+    /// `b` maps to. This keeps the orientations consistent on undirected
+    /// graphs, where [`Graph::edge_label`] may depend on the orientation.
+    /// Optional query edges are only compared if the data edge exists.
+    fn edges_are_eq(&self, pair: Pair, direction: Direction) -> bool {
+        if !self.checks_edges() {
+            return true;
+        }
+        let source_target = |node, neighbor| match direction {
+            Direction::Outgoing => (node, neighbor),
+            Direction::Incoming => (neighbor, node),
+        };
+        for &neighbor in self.query.neighbors(pair.query_node, direction) {
+            if !self.query.is_covered(neighbor) {
+                continue;
+            }
+            let (query_source, query_target) = source_target(pair.query_node, neighbor);
+            let mapped = self.query.map[neighbor];
+            let data_edge = source_target(pair.data_node, mapped);
+            if self.is_optional_edge(query_source, query_target)
+                && !self.data.contains_edge(data_edge.0, data_edge.1)
+            {
+                continue;
+            }
+            if !self.edges_eq((query_source, query_target), data_edge) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the symmetric edge equality function
+    /// if it is set and the graphs are undirected.
+    fn edge_eq_symmetric(&self) -> Option<&SymmetricEdgeEqFn<'a, Query, Data>> {
+        self.config
+            .edge_eq_symmetric
+            .as_deref()
+            .filter(|_| !self.is_directed())
+    }
+
+    /// Returns `true` if edge labels are compared.
+    fn checks_edges(&self) -> bool {
+        self.edge_eq.is_some()
+            || self.edge_eq_symmetric().is_some()
+            || self.config.edge_eq_ctx.is_some()
+    }
+
+    /// Returns `true` if the query edge is semantically equivalent
+    /// to the data edge, where each is read from its source to its target.
+    ///
+    /// Looks up the precomputed edge equality if there is one.
+    fn edges_eq(
+        &self,
+        query_edge: (NodeIndex, NodeIndex),
+        data_edge: (NodeIndex, NodeIndex),
+    ) -> bool {
+        if let Some(edge_compat) = &self.edge_compat {
+            let query_position = self.query.cache.outgoing.position(query_edge);
+            let data_position = self.data.cache.outgoing.position(data_edge);
+            if let (Some(row), Some(column)) = (query_position, data_position) {
+                return edge_compat.get(row, column);
+            }
+        }
+        self.edges_eq_uncached(query_edge, data_edge)
+    }
+
+    /// Returns `true` if the query edge is semantically equivalent
+    /// to the data edge by calling the edge equality functions.
+    fn edges_eq_uncached(
+        &self,
+        (query_source, query_target): (NodeIndex, NodeIndex),
+        (data_source, data_target): (NodeIndex, NodeIndex),
+    ) -> bool {
+        let query_edge = (query_source, query_target);
+        let data_edge = (data_source, data_target);
+        if self.checks_multiplicity() {
+            let query_labels = self.query.parallel_edge_labels(query_source, query_target);
+            let data_labels = self.data.parallel_edge_labels(data_source, data_target);
+            if let (Some(query_labels), Some(data_labels)) = (query_labels, data_labels) {
+                return labels_pair_up(query_labels.len(), data_labels.len(), |query, data| {
+                    self.edge_labels_match(
+                        query_edge,
+                        query_labels[query],
+                        data_edge,
+                        data_labels[data],
+                    )
+                });
+            }
+        }
+        let query_label = self.query.edge_label(query_source, query_target);
+        let data_label = self.data.edge_label(data_source, data_target);
+        self.edge_labels_match(query_edge, query_label, data_edge, data_label)
+    }
+
+    /// Returns `true` if the label of the query edge is semantically
+    /// equivalent to the label of the data edge,
+    /// where each edge is read from its source to its target.
+    fn edge_labels_match(
+        &self,
+        (query_source, query_target): (NodeIndex, NodeIndex),
+        query_label: &Query::EdgeLabel,
+        (data_source, data_target): (NodeIndex, NodeIndex),
+        data_label: &Data::EdgeLabel,
+    ) -> bool {
+        if !self.edge_labels_are_eq(query_label, data_label) {
+            return false;
+        }
+        self.config.edge_eq_ctx.as_ref().is_none_or(|edge_eq_ctx| {
+            edge_eq_ctx(
+                EdgeCtx {
+                    label: query_label,
+                    source: self.query.node_label(query_source),
+                    target: self.query.node_label(query_target),
+                },
+                EdgeCtx {
+                    label: data_label,
+                    source: self.data.node_label(data_source),
+                    target: self.data.node_label(data_target),
+                },
+            )
+        })
+    }
+
+    /// Returns `true` if the edge labels are semantically equivalent.
+    fn edge_labels_are_eq(&self, query: &Query::EdgeLabel, data: &Data::EdgeLabel) -> bool {
+        if let Some(edge_eq_symmetric) = self.edge_eq_symmetric() {
+            edge_eq_symmetric(query, data, false) || edge_eq_symmetric(query, data, true)
+        } else {
+            self.edge_eq
+                .as_ref()
+                .is_none_or(|edge_eq| edge_eq(query, data))
+        }
+    }
+
+    /// Returns a reference to the query partial map.
+    pub(crate) fn query_map(&self) -> &Vec<NodeIndex> {
+        &self.query.map
+    }
+
+    /// Returns a reference to the data partial map.
+    pub(crate) fn data_map(&self) -> &Vec<NodeIndex> {
+        &self.data.map
+    }
+
+    /// Returns the query partial map.
+    pub(crate) fn into_query_map(self) -> Vec<NodeIndex> {
+        self.query.map
+    }
+
+    /// Returns the depth in the SSR tree,
+    /// which is the number of covered query nodes.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the deepest depth reached so far.
+    pub(crate) fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns a snapshot of the search frontier.
+    pub(crate) fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            depth: self.depth,
+            query: self.query.snapshot(),
+            data: self.data.snapshot(),
+            source_stack: self.source_stack.clone(),
+            previous: self.previous,
+            candidate_stack: self.candidate_stack.clone(),
+            extra_edge_stack: self.extra_edge_stack.clone(),
+            mismatch_stack: self.mismatch_stack.clone(),
+            merge_stack: self.merge_stack.clone(),
+            leaf: self.leaf,
+            done: self.done,
+            root_total: self.root_total,
+            root_tried: self.root_tried,
+            component_cursors: self
+                .components
+                .as_ref()
+                .map_or_else(Vec::new, |components| components.cursors.clone()),
+        }
+    }
+
+    /// Restores the search frontier from `snapshot`.
+    ///
+    /// Returns an error if the snapshot does not fit this search.
+    pub(crate) fn restore(&mut self, snapshot: StateSnapshot) -> Result<(), Vf2Error> {
+        if !self.fits(&snapshot) {
+            return Err(Vf2Error::SnapshotMismatch);
+        }
+        self.depth = snapshot.depth;
+        self.max_depth = self.max_depth.max(self.depth);
+        self.query.restore(snapshot.query);
+        self.data.restore(snapshot.data);
+        self.source_stack = snapshot.source_stack;
+        self.previous = snapshot.previous;
+        self.candidate_stack = snapshot.candidate_stack;
+        self.extra_edge_stack = snapshot.extra_edge_stack;
+        self.mismatch_stack = snapshot.mismatch_stack;
+        self.merge_stack = snapshot.merge_stack;
+        self.leaf = snapshot.leaf;
+        self.done = snapshot.done;
+        self.root_total = snapshot.root_total;
+        self.root_tried = snapshot.root_tried;
+        if let Some(components) = &mut self.components {
+            components.cursors = snapshot.component_cursors;
+        }
+        if self.merges() {
+            self.held.fill(false);
+            for depth in 0..self.depth {
+                let query_node = self.query.node_stack[depth];
+                if !self.is_mergeable(query_node) {
+                    self.held[self.query.map[query_node]] = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `snapshot` could have been taken of this search.
+    ///
+    /// Besides the sizes of the graphs, this checks that every node index
+    /// is in range, that the maps and node stacks agree up to the depth,
+    /// and that the terminal set sizes match the sets.
+    fn fits(&self, snapshot: &StateSnapshot) -> bool {
+        let (query_len, data_len) = (self.query.map.len(), self.data.map.len());
+        let (query, data, depth) = (&snapshot.query, &snapshot.data, snapshot.depth);
+        let component_count = self
+            .components
+            .as_ref()
+            .map_or(0, |components| components.cursors.len());
+        if depth > query_len
+            || !query.fits(query_len, data_len, query_len, depth)
+            || !data.fits(data_len, query_len, query_len, depth)
+            || snapshot.source_stack.len() != query_len
+            || snapshot.candidate_stack.len() != query_len
+            || snapshot.extra_edge_stack.len() != query_len
+            || snapshot.mismatch_stack.len() != query_len
+            || snapshot.merge_stack.len() != query_len
+            || snapshot.component_cursors.len() != component_count
+        {
+            return false;
+        }
+        let mut stacked = vec![false; query_len];
+        let maps_agree = (0..depth).all(|depth| {
+            let query_node = query.node_stack[depth];
+            if query_node >= query_len || stacked[query_node] {
+                return false;
             }
+            stacked[query_node] = true;
+            let data_node = query.map[query_node];
+            data_node != NOT_IN_MAP
+                && if snapshot.merge_stack[depth] {
+                    data.map[data_node] != NOT_IN_MAP
+                } else {
+                    data.node_stack[depth] == data_node && data.map[data_node] == query_node
+                }
+        });
+        let merged = snapshot.merge_stack[..depth]
+            .iter()
+            .filter(|&&merged| merged)
+            .count();
+        let in_range = |pair: &Pair| pair.query_node < query_len && pair.data_node < data_len;
+        maps_agree
+            && query.covered() == depth
+            && data.covered() == depth - merged
+            && snapshot.previous.as_ref().is_none_or(in_range)
+            && snapshot.candidate_stack.iter().flatten().all(in_range)
     }
 
-    /// Returns `true` if the nodes in the pair
-    /// are semantically equivalent.
-    fn nodes_are_eq(&self, pair: Pair) -> bool {
-        let node_eq = match &self.node_eq {
-            None => return true,
-            Some(node_eq) => node_eq,
+    /// Returns an upper bound on the number of isomorphisms
+    /// the search has not yielded yet.
+    ///
+    /// Each untried candidate pair at a depth up to the current one
+    /// is counted as if every way to map the remaining query nodes
+    /// to the remaining data nodes were an isomorphism.
+    /// If query nodes may be merged, every data node remains.
+    pub(crate) fn remaining_upper_bound(&self) -> usize {
+        if self.done {
+            return 0;
+        }
+        let query_len = self.query.map.len();
+        if query_len == 0 {
+            // The empty map is yielded on the first step.
+            return usize::from(!self.leaf);
+        }
+        let data_len = self.data.map.len();
+        let merges = self.merges();
+        // The number of ways to map the query nodes after `depth`.
+        let completions = |depth: usize| {
+            (depth + 1..query_len).fold(1_usize, |product, depth| {
+                let remaining = if merges {
+                    data_len
+                } else {
+                    data_len.saturating_sub(depth)
+                };
+                product.saturating_mul(remaining)
+            })
         };
-        node_eq(
-            self.query.node_label(pair.query_node),
-            self.data.node_label(pair.data_node),
-        )
+        let mut bound = 0_usize;
+        for depth in 0..=self.depth.min(query_len - 1) {
+            let previous = if depth < self.depth {
+                Some(self.query.map[self.query.node_stack[depth]])
+            } else {
+                self.previous.map(|pair| pair.data_node)
+            };
+            let untried = match self.component_depth() {
+                // Any remaining match of the component may follow,
+                // whatever data node its first pair has.
+                Some(component_depth) if depth >= component_depth => {
+                    self.untried_candidates(depth, None)
+                }
+                _ => self.untried_candidates(depth, previous),
+            };
+            bound = bound.saturating_add(untried.saturating_mul(completions(depth)));
+        }
+        bound
     }
 
-    /// Returns `true` if the pair edges in `direction`
-    /// are semantically equivalent.
-    fn edges_are_eq(&self, pair: Pair, direction: Direction) -> bool {
-        let edge_eq = match &self.edge_eq {
-            None => return true,
-            Some(edge_eq) => edge_eq,
-        };
-        let source_target = |node, neighbor| match direction {
-            Direction::Outgoing => (node, neighbor),
-            Direction::Incoming => (neighbor, node),
-        };
-        // If the graph is undirected, this returns all neighbors.
-        for neighbor in self
-            .query
-            .graph
-            .neighbors(pair.query_node, direction)
-            .filter(|&neighbor| self.query.is_covered(neighbor))
-        {
-            let (query_source, query_target) = source_target(pair.query_node, neighbor);
-            let mapped = self.query.map[neighbor];
-            let (data_source, data_target) = source_target(pair.data_node, mapped);
-            if !edge_eq(
-                self.query.edge_label(query_source, query_target),
-                self.data.edge_label(data_source, data_target),
-            ) {
-                return false;
-            }
+    /// Returns an upper bound on the number of candidate pairs
+    /// at `depth` following the pair with `previous` as its data node,
+    /// or all candidate pairs if there is no previous pair.
+    fn untried_candidates(&self, depth: usize, previous: Option<NodeIndex>) -> usize {
+        match (previous, self.config.root) {
+            (None, Some(Root::Pair(..))) if depth == 0 => 1,
+            (None, Some(Root::DataNode(_))) if depth == 0 => self.query.map.len(),
+            (None, _) if self.merges() => self.data.map.len(),
+            (None, _) => self.data.map.len().saturating_sub(depth),
+            (Some(_), _) if self.uses_candidate_stack(depth) => self.candidate_stack[depth].len(),
+            // Candidates are tried in index order.
+            (Some(data_node), _) => self.data.map.len() - 1 - data_node,
         }
-        true
     }
 
-    /// Returns a reference to the query partial map.
-    pub(crate) fn query_map(&self) -> &Vec<NodeIndex> {
-        &self.query.map
+    /// Returns the depth and terminal set sizes of the current state.
+    pub(crate) fn info(&self) -> StateInfo {
+        StateInfo {
+            depth: self.depth,
+            query: self.query.terminal_set_sizes(),
+            data: self.data.terminal_set_sizes(),
+        }
     }
 
-    /// Returns the query partial map.
-    pub(crate) fn into_query_map(self) -> Vec<NodeIndex> {
-        self.query.map
+    /// Makes the search also stop at leaves of the SSR tree
+    /// whose partial maps cover at least `min` query nodes.
+    ///
+    /// The query graph is then not decomposed,
+    /// so the leaves are those of the usual search.
+    pub(crate) fn yield_partials(&mut self, min: usize) {
+        self.min_matched = Some(min);
+        self.components = None;
+    }
+
+    /// Returns `true` if the search stopped at a map to yield,
+    /// either an isomorphism or a partial map at a leaf.
+    pub(crate) fn at_match(&self) -> bool {
+        self.all_covered() || self.at_partial
+    }
+
+    /// Returns `true` if the search is complete.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns `true` if the search gave up because the budget ran out.
+    pub(crate) fn budget_exhausted(&self) -> bool {
+        self.budget_exhausted
+    }
+
+    /// Returns `true` if the node equality function aborted the search.
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted
     }
 
     /// Returns `true` if all query nodes are covered.
+    ///
+    /// A complete search covers none, even if the query graph is empty.
     pub(crate) fn all_covered(&self) -> bool {
-        self.depth == self.query.map.len()
+        self.depth == self.map_len && !self.done
     }
 
     /// Returns `true` if the graphs are directed.
     fn is_directed(&self) -> bool {
-        self.query.graph.is_directed()
+        self.query.is_directed()
     }
 }
 
@@ -324,7 +1759,7 @@ struct GraphState<'a, G> {
     /// Graph.
     ///
     /// This is *G_1* or *G_2* in the original VF2 paper.
-    graph: &'a G,
+    graph: GraphRef<'a, G>,
     /// A partial map of this graph's node indices to the other's.
     ///
     /// This is *M_1* or *M_2* in the original VF2 paper.
@@ -353,6 +1788,14 @@ struct GraphState<'a, G> {
     /// The value at index `i` is the node that
     /// was added to the partial map at depth `i + 1`.
     node_stack: Vec<NodeIndex>,
+    /// Edges whose far ends are not added to the terminal sets,
+    /// since they need not map to an edge of the other graph.
+    ///
+    /// Empty unless this is the query graph and some of its edges are optional.
+    optional_edges: BTreeSet<(NodeIndex, NodeIndex)>,
+    /// Neighbors read once from the graph,
+    /// which may be shared with other searches.
+    cache: Arc<GraphCache>,
 }
 
 impl<'a, G> GraphState<'a, G>
@@ -360,15 +1803,120 @@ where
     G: Graph,
 {
     /// Creates a new [`GraphState`].
-    fn new(graph: &'a G) -> Self {
+    ///
+    /// Reads the neighbors from `graph` unless `cache` is given.
+    /// If `undirected` is `true`, reads `graph` as undirected, and only
+    /// uses `cache` if it was read as undirected too.
+    fn new(graph: GraphRef<'a, G>, cache: Option<Arc<GraphCache>>, undirected: bool) -> Self {
+        let cache = cache
+            .filter(|cache| !(undirected && cache.directed))
+            .unwrap_or_else(|| {
+                Arc::new(if undirected {
+                    GraphCache::undirected(&*graph, false)
+                } else {
+                    GraphCache::new(&*graph, false)
+                })
+            });
         Self {
-            graph,
             map: vec![NOT_IN_MAP; graph.node_count()],
             outgoing: vec![NOT_IN_SET; graph.node_count()],
             outgoing_size: 0,
             incoming: vec![NOT_IN_SET; graph.node_count()],
             incoming_size: 0,
             node_stack: vec![0; graph.node_count()],
+            optional_edges: BTreeSet::new(),
+            cache,
+            graph,
+        }
+    }
+
+    /// Returns the neighbors of `node`.
+    ///
+    /// Has the same behaviour as [`Graph::neighbors`].
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> &[NodeIndex] {
+        self.cache.neighbors(node, direction)
+    }
+
+    /// Returns `true` if the graph is searched as directed.
+    ///
+    /// This is `false` for a directed graph read as undirected.
+    fn is_directed(&self) -> bool {
+        self.cache.directed
+    }
+
+    /// Returns `true` if the graph is directed but read as undirected.
+    fn ignores_direction(&self) -> bool {
+        !self.cache.directed && self.graph.is_directed()
+    }
+
+    /// Returns a snapshot of the partial map and terminal sets.
+    fn snapshot(&self) -> GraphSnapshot {
+        GraphSnapshot {
+            map: self.map.clone(),
+            outgoing: self.outgoing.clone(),
+            outgoing_size: self.outgoing_size,
+            incoming: self.incoming.clone(),
+            incoming_size: self.incoming_size,
+            node_stack: self.node_stack.clone(),
+        }
+    }
+
+    /// Restores the partial map and terminal sets from `snapshot`.
+    fn restore(&mut self, snapshot: GraphSnapshot) {
+        self.map = snapshot.map;
+        self.outgoing = snapshot.outgoing;
+        self.outgoing_size = snapshot.outgoing_size;
+        self.incoming = snapshot.incoming;
+        self.incoming_size = snapshot.incoming_size;
+        self.node_stack = snapshot.node_stack;
+    }
+
+    /// Keeps the far ends of `edges` out of the terminal sets.
+    ///
+    /// A node reached only through optional edges may map to
+    /// a data node that is not adjacent to the partial map,
+    /// so it is instead matched from the uncovered nodes.
+    fn with_optional_edges(mut self, edges: &BTreeSet<(NodeIndex, NodeIndex)>) -> Self {
+        self.optional_edges = edges.clone();
+        self
+    }
+
+    /// Resizes the node stack to hold `len` nodes.
+    ///
+    /// The data node stack is indexed by search depth,
+    /// which is bounded by the query node count.
+    fn with_stack_len(mut self, len: usize) -> Self {
+        self.node_stack.resize(len, 0);
+        self
+    }
+
+    /// Precomputes the adjacency matrix if `dense` is `true`
+    /// and the graph is no larger than [`DENSE_ADJACENCY_LIMIT`].
+    fn with_adjacency(mut self, dense: bool) -> Self {
+        if dense && self.cache.adjacency.is_none() {
+            Arc::make_mut(&mut self.cache).build_adjacency();
+        }
+        self
+    }
+
+    /// Returns `true` if there is an edge from `source` to `target`.
+    ///
+    /// Has the same behaviour as [`Graph::contains_edge`].
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        match &self.cache.adjacency {
+            Some(adjacency) => adjacency.contains_edge(source, target),
+            None => {
+                self.graph.contains_edge(source, target)
+                    || (self.ignores_direction() && self.graph.contains_edge(target, source))
+            }
+        }
+    }
+
+    /// Returns the sizes of the terminal sets.
+    fn terminal_set_sizes(&self) -> TerminalSetSizes {
+        TerminalSetSizes {
+            outgoing: self.outgoing_size,
+            incoming: self.incoming_size,
         }
     }
 
@@ -386,6 +1934,15 @@ where
         }
     }
 
+    /// Returns `true` if `node` is in `source`.
+    fn in_source(&self, node: NodeIndex, source: Source) -> bool {
+        match source {
+            Source::Outgoing => self.in_terminal_set(node, &self.outgoing),
+            Source::Incoming => self.in_terminal_set(node, &self.incoming),
+            Source::Uncovered => !self.is_covered(node),
+        }
+    }
+
     /// Returns an iterator of nodes in the terminal set beginning at `skip`.
     fn terminal_nodes(
         &self,
@@ -400,6 +1957,31 @@ where
         set[node] != NOT_IN_SET && !self.is_covered(node)
     }
 
+    /// Appends the nodes in `source` to `nodes`.
+    fn extend_with_nodes(&self, source: Source, nodes: &mut Vec<NodeIndex>) {
+        match source {
+            Source::Outgoing => nodes.extend(self.terminal_nodes(&self.outgoing, 0)),
+            Source::Incoming => nodes.extend(self.terminal_nodes(&self.incoming, 0)),
+            Source::Uncovered => nodes.extend(self.uncovered_nodes(0)),
+        }
+    }
+
+    /// Returns the number of covered neighbors of `node` in either direction.
+    fn covered_neighbors(&self, node: NodeIndex) -> usize {
+        let covered = |direction| {
+            self.neighbors(node, direction)
+                .iter()
+                .filter(|&&neighbor| self.is_covered(neighbor))
+                .count()
+        };
+        if self.is_directed() {
+            covered(Direction::Outgoing) + covered(Direction::Incoming)
+        } else {
+            // If the graph is undirected, this returns all neighbors.
+            covered(Direction::Outgoing)
+        }
+    }
+
     /// Returns an iterator of uncovered nodes beginning at `skip`.
     fn uncovered_nodes(&self, skip: usize) -> impl Iterator<Item = NodeIndex> + '_ {
         (skip..self.map.len()).filter(|&node| !self.is_covered(node))
@@ -407,13 +1989,12 @@ where
 
     /// Pushes a map from `node` to `to_node` to the partial map.
     fn push(&mut self, node: NodeIndex, to_node: NodeIndex, depth: usize) {
-        self.node_stack[depth - 1] = node;
-        self.map[node] = to_node;
+        self.cover(node, to_node, depth);
         if self.outgoing[node] != NOT_IN_SET {
             self.outgoing_size -= 1;
         }
         self.push_neighbors(node, Direction::Outgoing, depth);
-        if self.graph.is_directed() {
+        if self.is_directed() {
             if self.incoming[node] != NOT_IN_SET {
                 self.incoming_size -= 1;
             }
@@ -421,15 +2002,47 @@ where
         }
     }
 
+    /// Adds a map from `node` to `to_node` to the partial map
+    /// without updating the terminal sets.
+    fn cover(&mut self, node: NodeIndex, to_node: NodeIndex, depth: usize) {
+        self.node_stack[depth - 1] = node;
+        self.map[node] = to_node;
+    }
+
+    /// Removes the node at `depth` from the partial map
+    /// without updating the terminal sets, and returns it.
+    fn uncover(&mut self, depth: usize) -> NodeIndex {
+        let node = self.node_stack[depth - 1];
+        self.map[node] = NOT_IN_MAP;
+        node
+    }
+
     /// Pushes neighbors of `node` in `direction` to the corresponding terminal set.
     fn push_neighbors(&mut self, node: NodeIndex, direction: Direction, depth: usize) {
-        let (set, len) = match direction {
-            Direction::Outgoing => (&mut self.outgoing, &mut self.outgoing_size),
-            Direction::Incoming => (&mut self.incoming, &mut self.incoming_size),
+        let (optional_edges, directed) = (&self.optional_edges, self.cache.directed);
+        let is_optional = |neighbor: NodeIndex| {
+            let edge = match direction {
+                Direction::Outgoing => (node, neighbor),
+                Direction::Incoming => (neighbor, node),
+            };
+            !optional_edges.is_empty()
+                && (optional_edges.contains(&edge)
+                    || (!directed && optional_edges.contains(&(edge.1, edge.0))))
         };
-        // If the graph is undirected, this returns all neighbors.
-        for neighbor in self.graph.neighbors(node, direction) {
-            if set[neighbor] == NOT_IN_SET {
+        let (set, len, neighbors) = match direction {
+            Direction::Outgoing => (
+                &mut self.outgoing,
+                &mut self.outgoing_size,
+                &self.cache.outgoing,
+            ),
+            Direction::Incoming => (
+                &mut self.incoming,
+                &mut self.incoming_size,
+                &self.cache.incoming,
+            ),
+        };
+        for &neighbor in neighbors.get(node) {
+            if set[neighbor] == NOT_IN_SET && !is_optional(neighbor) {
                 set[neighbor] = depth;
                 if self.map[neighbor] == NOT_IN_MAP {
                     *len += 1;
@@ -440,13 +2053,12 @@ where
 
     /// Pops the node at `depth` from the partial map and returns it.
     fn pop(&mut self, depth: usize) -> NodeIndex {
-        let node = self.node_stack[depth - 1];
-        self.map[node] = NOT_IN_MAP;
+        let node = self.uncover(depth);
         if self.outgoing[node] != NOT_IN_SET {
             self.outgoing_size += 1;
         }
         self.pop_neighbors(node, Direction::Outgoing, depth);
-        if self.graph.is_directed() {
+        if self.is_directed() {
             if self.incoming[node] != NOT_IN_SET {
                 self.incoming_size += 1;
             }
@@ -458,12 +2070,19 @@ where
     /// Pops neighbors of `node` in `direction` from the corresponding
     /// terminal set if they were added at `depth`.
     fn pop_neighbors(&mut self, node: NodeIndex, direction: Direction, depth: usize) {
-        let (set, len) = match direction {
-            Direction::Outgoing => (&mut self.outgoing, &mut self.outgoing_size),
-            Direction::Incoming => (&mut self.incoming, &mut self.incoming_size),
+        let (set, len, neighbors) = match direction {
+            Direction::Outgoing => (
+                &mut self.outgoing,
+                &mut self.outgoing_size,
+                &self.cache.outgoing,
+            ),
+            Direction::Incoming => (
+                &mut self.incoming,
+                &mut self.incoming_size,
+                &self.cache.incoming,
+            ),
         };
-        // If the graph is undirected, this returns all neighbors.
-        for neighbor in self.graph.neighbors(node, direction) {
+        for &neighbor in neighbors.get(node) {
             if set[neighbor] == depth {
                 set[neighbor] = NOT_IN_SET;
                 if self.map[neighbor] == NOT_IN_MAP {
@@ -486,15 +2105,464 @@ where
     /// Returns the label of `node`.
     ///
     /// Has the same behaviour as [`Graph::edge_label`].
+    /// If the graph is read as undirected,
+    /// falls back to the edge from `target` to `source`.
     fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> &G::EdgeLabel {
         self.graph
             .edge_label(source, target)
+            .or_else(|| {
+                self.ignores_direction()
+                    .then(|| self.graph.edge_label(target, source))
+                    .flatten()
+            })
             .expect("edge should exist")
     }
+
+    /// Returns the labels of all edges from `source` to `target`,
+    /// or [`None`] if the graph does not look up labels by edge id.
+    ///
+    /// If the graph is read as undirected and there is no edge
+    /// from `source` to `target`, returns the labels of the edges
+    /// from `target` to `source`.
+    fn parallel_edge_labels(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Option<Vec<&G::EdgeLabel>> {
+        let labels = |source, target| {
+            self.graph
+                .neighbors_with_edge(source, Direction::Outgoing)
+                .filter(|&(neighbor, _)| neighbor == target)
+                .map(|(_, edge)| self.graph.edge_label_by_id(edge))
+                .collect::<Option<Vec<_>>>()
+        };
+        let forward = labels(source, target)?;
+        if forward.is_empty() && self.ignores_direction() {
+            labels(target, source)
+        } else {
+            Some(forward)
+        }
+    }
+}
+
+/// The neighbors of each node of a graph, read once from the graph.
+///
+/// This does not depend on the other graph,
+/// so it can be shared between searches of the same data graph.
+#[derive(Clone, Debug)]
+pub(crate) struct GraphCache {
+    /// Whether the graph is directed.
+    directed: bool,
+    /// Outgoing neighbors of each node.
+    ///
+    /// If the graph is undirected, this contains all neighbors.
+    outgoing: NeighborLists,
+    /// Incoming neighbors of each node.
+    ///
+    /// Empty if the graph is undirected.
+    incoming: NeighborLists,
+    /// Precomputed adjacency matrix used to check edges, if any.
+    adjacency: Option<AdjacencyMatrix>,
+    /// Outgoing and incoming degrees sorted in descending order,
+    /// if precomputed for checking whether a search can be skipped.
+    degree_sequences: Option<(Vec<usize>, Vec<usize>)>,
+    /// Whether some node has a neighbor more than once,
+    /// so the graph has parallel edges.
+    parallel_edges: bool,
+}
+
+impl GraphCache {
+    /// Creates a new [`GraphCache`] with the neighbors of `graph`.
+    ///
+    /// Also precomputes the adjacency matrix if `dense` is `true`
+    /// and the graph is no larger than [`DENSE_ADJACENCY_LIMIT`].
+    pub(crate) fn new<G: Graph>(graph: &G, dense: bool) -> Self {
+        let outgoing = NeighborLists::new(graph, Direction::Outgoing);
+        let mut cache = Self {
+            directed: graph.is_directed(),
+            parallel_edges: outgoing.has_repeats(),
+            outgoing,
+            incoming: if graph.is_directed() {
+                NeighborLists::new(graph, Direction::Incoming)
+            } else {
+                NeighborLists::default()
+            },
+            adjacency: None,
+            degree_sequences: None,
+        };
+        if dense {
+            cache.build_adjacency();
+        }
+        cache
+    }
+
+    /// Returns the neighbors of `node`.
+    ///
+    /// Has the same behaviour as [`Graph::neighbors`].
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> &[NodeIndex] {
+        match direction {
+            Direction::Incoming if self.directed => self.incoming.get(node),
+            _ => self.outgoing.get(node),
+        }
+    }
+
+    /// Creates a new [`GraphCache`] with the neighbors of `graph`
+    /// read as undirected, so each node has every node it shares
+    /// an edge with as a neighbor once, in either direction.
+    ///
+    /// Parallel edges and edges in both directions collapse into one.
+    pub(crate) fn undirected<G: Graph>(graph: &G, dense: bool) -> Self {
+        let mut cache = Self {
+            directed: false,
+            outgoing: NeighborLists::undirected(graph),
+            incoming: NeighborLists::default(),
+            adjacency: None,
+            degree_sequences: None,
+            parallel_edges: false,
+        };
+        if dense {
+            cache.build_adjacency();
+        }
+        cache
+    }
+
+    /// Precomputes the degree sequences so searches sharing this cache
+    /// do not each compute them.
+    pub(crate) fn with_degree_sequences(mut self) -> Self {
+        let sorted = |lists: &NeighborLists| {
+            let mut degrees: Vec<usize> = lists
+                .offsets
+                .windows(2)
+                .map(|window| window[1] - window[0])
+                .collect();
+            degrees.sort_unstable_by(|left, right| right.cmp(left));
+            degrees
+        };
+        let incoming = if self.directed {
+            &self.incoming
+        } else {
+            &self.outgoing
+        };
+        self.degree_sequences = Some((sorted(&self.outgoing), sorted(incoming)));
+        self
+    }
+
+    /// Returns the degrees of the nodes in `direction`
+    /// sorted in descending order, if precomputed.
+    ///
+    /// If the graph is undirected, ignores `direction` and uses all neighbors.
+    pub(crate) fn degree_sequence(&self, direction: Direction) -> Option<&[usize]> {
+        let (outgoing, incoming) = self.degree_sequences.as_ref()?;
+        match direction {
+            Direction::Outgoing => Some(outgoing),
+            Direction::Incoming => Some(incoming),
+        }
+    }
+
+    /// Precomputes the adjacency matrix
+    /// if the graph is no larger than [`DENSE_ADJACENCY_LIMIT`].
+    fn build_adjacency(&mut self) {
+        if self.outgoing.offsets.len() - 1 <= DENSE_ADJACENCY_LIMIT {
+            self.adjacency = Some(AdjacencyMatrix::new(&self.outgoing));
+        }
+    }
+}
+
+/// The neighbors of each node in one direction, stored contiguously.
+///
+/// These are read from the graph once when the search starts,
+/// so the graph must not change during the search.
+#[derive(Clone, Debug, Default)]
+struct NeighborLists {
+    /// The neighbors of node `i` are at `offsets[i]..offsets[i + 1]`.
+    offsets: Vec<usize>,
+    /// Neighbors of all nodes.
+    neighbors: Vec<NodeIndex>,
+}
+
+impl NeighborLists {
+    /// Creates new [`NeighborLists`] with the neighbors of `graph` in `direction`.
+    fn new<G: Graph>(graph: &G, direction: Direction) -> Self {
+        let mut offsets = Vec::with_capacity(graph.node_count() + 1);
+        let mut neighbors = Vec::new();
+        offsets.push(0);
+        // Offsets are positional, so visit nodes in index order.
+        for node in 0..graph.node_count() {
+            // If the graph is undirected, this returns all neighbors.
+            neighbors.extend(graph.neighbors(node, direction));
+            offsets.push(neighbors.len());
+        }
+        Self { offsets, neighbors }
+    }
+
+    /// Creates new [`NeighborLists`] with the neighbors of `graph`
+    /// in either direction, sorted and without repeats.
+    fn undirected<G: Graph>(graph: &G) -> Self {
+        let mut offsets = Vec::with_capacity(graph.node_count() + 1);
+        let mut neighbors = Vec::new();
+        offsets.push(0);
+        for node in 0..graph.node_count() {
+            let start = neighbors.len();
+            neighbors.extend(graph.neighbors(node, Direction::Outgoing));
+            if graph.is_directed() {
+                neighbors.extend(graph.neighbors(node, Direction::Incoming));
+            }
+            neighbors[start..].sort_unstable();
+            let mut end = start;
+            for index in start..neighbors.len() {
+                if end == start || neighbors[end - 1] != neighbors[index] {
+                    neighbors[end] = neighbors[index];
+                    end += 1;
+                }
+            }
+            neighbors.truncate(end);
+            offsets.push(neighbors.len());
+        }
+        Self { offsets, neighbors }
+    }
+
+    /// Returns the neighbors of `node`.
+    fn get(&self, node: NodeIndex) -> &[NodeIndex] {
+        &self.neighbors[self.offsets[node]..self.offsets[node + 1]]
+    }
+
+    /// Returns every edge as a `(source, target)` pair,
+    /// in the order of their positions.
+    fn edges(&self) -> Vec<(NodeIndex, NodeIndex)> {
+        (0..self.offsets.len() - 1)
+            .flat_map(|node| self.get(node).iter().map(move |&neighbor| (node, neighbor)))
+            .collect()
+    }
+
+    /// Returns the position of the first edge from `source` to `target`
+    /// among the neighbors of all nodes, if there is one.
+    fn position(&self, (source, target): (NodeIndex, NodeIndex)) -> Option<usize> {
+        let offset = self
+            .get(source)
+            .iter()
+            .position(|&neighbor| neighbor == target)?;
+        Some(self.offsets[source] + offset)
+    }
+
+    /// Returns `true` if some node has a neighbor more than once.
+    fn has_repeats(&self) -> bool {
+        let mut sorted = Vec::new();
+        self.offsets.windows(2).any(|window| {
+            sorted.clear();
+            sorted.extend_from_slice(&self.neighbors[window[0]..window[1]]);
+            sorted.sort_unstable();
+            sorted.windows(2).any(|pair| pair[0] == pair[1])
+        })
+    }
+}
+
+/// A dense boolean matrix with one bit per entry.
+#[derive(Clone, Debug)]
+struct BitMatrix {
+    /// Number of columns.
+    columns: usize,
+    /// Bits in row-major order.
+    bits: Vec<u64>,
+}
+
+impl BitMatrix {
+    /// Creates a new [`BitMatrix`] with all entries `false`.
+    fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            columns,
+            bits: vec![0; (rows * columns).div_ceil(64)],
+        }
+    }
+
+    /// Sets the entry at `row` and `column` to `true`.
+    fn set(&mut self, row: usize, column: usize) {
+        let bit = row * self.columns + column;
+        self.bits[bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// Returns the entry at `row` and `column`.
+    fn get(&self, row: usize, column: usize) -> bool {
+        let bit = row * self.columns + column;
+        self.bits[bit / 64] & (1 << (bit % 64)) != 0
+    }
+}
+
+/// A dense adjacency matrix with one bit per node pair.
+#[derive(Clone, Debug)]
+struct AdjacencyMatrix {
+    /// Number of nodes.
+    len: usize,
+    /// Bits in row-major order. The bit at `source * len + target`
+    /// is set if there is an edge from `source` to `target`.
+    bits: Vec<u64>,
+}
+
+impl AdjacencyMatrix {
+    /// Creates a new [`AdjacencyMatrix`] with the edges to `outgoing` neighbors.
+    fn new(outgoing: &NeighborLists) -> Self {
+        let len = outgoing.offsets.len() - 1;
+        let mut bits = vec![0; (len * len).div_ceil(64)];
+        for source in 0..len {
+            for &target in outgoing.get(source) {
+                let bit = source * len + target;
+                bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        Self { len, bits }
+    }
+
+    /// Returns `true` if there is an edge from `source` to `target`.
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        let bit = source * self.len + target;
+        self.bits[bit / 64] & (1 << (bit % 64)) != 0
+    }
+}
+
+/// A checkpoint of a search, created by
+/// [`IsomorphismIter::checkpoint`](crate::IsomorphismIter::checkpoint).
+///
+/// This holds the search frontier, which is only node indices,
+/// terminal sets, and stacks. It does not hold the graphs,
+/// equality functions, or other options, so it must be resumed
+/// with [`Vf2Builder::resume`](crate::Vf2Builder::resume)
+/// on a builder configured the same way as the original search.
+///
+/// With the `serde` feature, this implements
+/// `Serialize` and `Deserialize` so it can be saved and loaded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshot {
+    /// Depth in the SSR tree.
+    depth: usize,
+    /// Query graph state.
+    query: GraphSnapshot,
+    /// Data graph state.
+    data: GraphSnapshot,
+    /// A stack of candidate pair sources.
+    source_stack: Vec<Source>,
+    /// The previous candidate pair at the current depth.
+    previous: Option<Pair>,
+    /// A stack of candidate pairs.
+    candidate_stack: Vec<Vec<Pair>>,
+    /// A stack of extra data edge counts.
+    extra_edge_stack: Vec<usize>,
+    /// A stack of node label mismatch counts.
+    mismatch_stack: Vec<usize>,
+    /// A stack of merged pair flags.
+    merge_stack: Vec<bool>,
+    /// Whether the current state has no successors so far.
+    leaf: bool,
+    /// Whether the search is complete.
+    done: bool,
+    /// Number of candidate pairs at the root of the SSR tree.
+    root_total: usize,
+    /// Number of candidate pairs at the root of the SSR tree tried so far.
+    root_tried: usize,
+    /// The next match of each query component to try.
+    component_cursors: Vec<usize>,
+}
+
+/// The partial map and terminal sets of one graph in a [`StateSnapshot`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct GraphSnapshot {
+    /// A partial map of this graph's node indices to the other's.
+    map: Vec<NodeIndex>,
+    /// Outgoing terminal set.
+    outgoing: Vec<usize>,
+    /// Number of nodes in the outgoing terminal set.
+    outgoing_size: usize,
+    /// Incoming terminal set.
+    incoming: Vec<usize>,
+    /// Number of nodes in the incoming terminal set.
+    incoming_size: usize,
+    /// Tracks the order nodes were added to the partial map.
+    node_stack: Vec<NodeIndex>,
+}
+
+impl GraphSnapshot {
+    /// Returns `true` if the snapshot is of a graph with `len` nodes
+    /// mapped to a graph with `other_len` nodes, with a node stack
+    /// of `stack_len`, taken at `depth`.
+    ///
+    /// The terminal sets may only hold nodes added up to `depth`,
+    /// and their sizes must count their uncovered nodes.
+    fn fits(&self, len: usize, other_len: usize, stack_len: usize, depth: usize) -> bool {
+        let fits_set = |set: &[usize], size: usize| {
+            set.len() == len
+                && set.iter().all(|&added| added <= depth)
+                && (0..len)
+                    .filter(|&node| set[node] != NOT_IN_SET && self.map[node] == NOT_IN_MAP)
+                    .count()
+                    == size
+        };
+        self.map.len() == len
+            && self.node_stack.len() == stack_len
+            && self
+                .map
+                .iter()
+                .all(|&other| other == NOT_IN_MAP || other < other_len)
+            && fits_set(&self.outgoing, self.outgoing_size)
+            && fits_set(&self.incoming, self.incoming_size)
+    }
+
+    /// Returns the number of nodes in the partial map.
+    fn covered(&self) -> usize {
+        self.map
+            .iter()
+            .filter(|&&other| other != NOT_IN_MAP)
+            .count()
+    }
+}
+
+/// The connected components of a query graph
+/// that are matched on their own.
+#[derive(Clone, Debug)]
+struct Components {
+    /// The value at index `i` is the component of query node `i`.
+    of: Vec<usize>,
+    /// The value at index `i` is the matches of component `i`,
+    /// each with its pairs in the order they are pushed,
+    /// or [`None`] if the component has not been searched yet.
+    matches: Vec<Option<Vec<Vec<Pair>>>>,
+    /// The value at index `i` is the next match of component `i` to try
+    /// at the depth the component is pushed.
+    cursors: Vec<usize>,
+}
+
+impl Components {
+    /// Returns the nodes of `component` in breadth-first order
+    /// from its lowest node, where `neighbors` returns
+    /// the neighbors of a node in either direction.
+    ///
+    /// Each node but the first is then in a terminal set when it is added.
+    fn order<I>(&self, component: usize, neighbors: impl Fn(NodeIndex) -> I) -> Vec<NodeIndex>
+    where
+        I: Iterator<Item = NodeIndex>,
+    {
+        let mut seen = vec![false; self.of.len()];
+        let mut order = Vec::new();
+        if let Some(first) = self.of.iter().position(|&other| other == component) {
+            seen[first] = true;
+            order.push(first);
+        }
+        let mut next = 0;
+        while let Some(&node) = order.get(next) {
+            for neighbor in neighbors(node) {
+                if self.of[neighbor] == component && !seen[neighbor] {
+                    seen[neighbor] = true;
+                    order.push(neighbor);
+                }
+            }
+            next += 1;
+        }
+        order
+    }
 }
 
 /// Candidate pair source.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Source {
     /// Uncovered neighbors of covered nodes that are edge destinations.
     ///
@@ -511,7 +2579,8 @@ enum Source {
 }
 
 /// A pair of query and data node indices.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Pair {
     query_node: NodeIndex,
     data_node: NodeIndex,
@@ -525,3 +2594,54 @@ impl Pair {
         }
     }
 }
+
+/// Returns `true` if the pair keeps each `(earlier, later)` pair
+/// of query nodes in `order` mapped to increasing data nodes,
+/// where `map` is the partial map of the query nodes.
+fn fits_order(order: &[(NodeIndex, NodeIndex)], map: &[NodeIndex], pair: Pair) -> bool {
+    order.iter().all(|&(earlier, later)| {
+        if later == pair.query_node && map[earlier] != NOT_IN_MAP {
+            map[earlier] < pair.data_node
+        } else if earlier == pair.query_node && map[later] != NOT_IN_MAP {
+            pair.data_node < map[later]
+        } else {
+            true
+        }
+    })
+}
+
+/// Returns `true` if each of `left` items can be paired with
+/// a different one of `right` items for which `eq` returns `true`.
+///
+/// This finds a maximum bipartite matching with augmenting paths,
+/// which is quick for the few parallel edges between two nodes.
+fn labels_pair_up(left: usize, right: usize, eq: impl Fn(usize, usize) -> bool) -> bool {
+    /// Tries to pair `item` with a right item not yet `visited`, moving
+    /// the items already paired along an augmenting path if needed.
+    fn augment(
+        item: usize,
+        eq: &impl Fn(usize, usize) -> bool,
+        pairs: &mut [Option<usize>],
+        visited: &mut [bool],
+    ) -> bool {
+        for other in 0..pairs.len() {
+            if visited[other] || !eq(item, other) {
+                continue;
+            }
+            visited[other] = true;
+            let paired = pairs[other];
+            if paired.is_none_or(|paired| augment(paired, eq, pairs, visited)) {
+                pairs[other] = Some(item);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut pairs = vec![None; right];
+    let mut visited = vec![false; right];
+    (0..left).all(|item| {
+        visited.fill(false);
+        augment(item, &eq, &mut pairs, &mut visited)
+    })
+}
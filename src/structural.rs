@@ -0,0 +1,127 @@
+use crate::{Direction, Graph, NodeIndex};
+use alloc::vec::Vec;
+
+/// A view of a graph labeled by its structure,
+/// with the degree of each node as its label and no edge labels.
+///
+/// With [`default_eq`](crate::Vf2Builder::default_eq), nodes only match
+/// nodes of equal degree, without storing degrees as labels.
+/// The degree of a node in a directed graph is its in-degree
+/// plus its out-degree. Parallel edges are each counted.
+///
+/// Labels are returned by reference, so the degrees are counted once
+/// when the view is created rather than on every comparison.
+///
+/// # Example
+///
+/// ```
+/// use petgraph::graph::UnGraph;
+/// use vf2::StructuralLabels;
+///
+/// let query = UnGraph::<(), ()>::from_edges([(0, 1)]);
+/// let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+///
+/// let query = StructuralLabels::new(&query);
+/// let data = StructuralLabels::new(&data);
+///
+/// let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+///     .default_eq()
+///     .vec();
+/// assert!(isomorphisms.is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StructuralLabels<'a, G> {
+    /// Underlying graph.
+    graph: &'a G,
+    /// Degree of each node.
+    degrees: Vec<usize>,
+}
+
+impl<'a, G> StructuralLabels<'a, G>
+where
+    G: Graph,
+{
+    /// Creates a view of `graph` labeled by degree.
+    pub fn new(graph: &'a G) -> Self {
+        let degrees = (0..graph.node_count())
+            .map(|node| {
+                if graph.is_directed() {
+                    graph.in_degree(node) + graph.out_degree(node)
+                } else {
+                    graph.out_degree(node)
+                }
+            })
+            .collect();
+        Self { graph, degrees }
+    }
+}
+
+impl<G> StructuralLabels<'_, G> {
+    /// Returns the underlying graph.
+    pub fn graph(&self) -> &G {
+        self.graph
+    }
+}
+
+impl<G> Graph for StructuralLabels<'_, G>
+where
+    G: Graph,
+{
+    type NodeLabel = usize;
+    type EdgeLabel = ();
+
+    #[inline]
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.degrees.len()
+    }
+
+    #[inline]
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    #[inline]
+    fn in_degree(&self, node: NodeIndex) -> usize {
+        self.graph.in_degree(node)
+    }
+
+    #[inline]
+    fn out_degree(&self, node: NodeIndex) -> usize {
+        self.graph.out_degree(node)
+    }
+
+    #[inline]
+    fn node_label(&self, node: NodeIndex) -> Option<&Self::NodeLabel> {
+        self.degrees.get(node)
+    }
+
+    #[inline]
+    fn neighbors(&self, node: NodeIndex, direction: Direction) -> impl Iterator<Item = NodeIndex> {
+        self.graph.neighbors(node, direction)
+    }
+
+    #[inline]
+    fn contains_edge(&self, source: NodeIndex, target: NodeIndex) -> bool {
+        self.graph.contains_edge(source, target)
+    }
+
+    #[inline]
+    fn edge_multiplicity(&self, source: NodeIndex, target: NodeIndex) -> usize {
+        self.graph.edge_multiplicity(source, target)
+    }
+
+    #[inline]
+    fn has_self_loop(&self, node: NodeIndex) -> bool {
+        self.graph.has_self_loop(node)
+    }
+
+    #[inline]
+    fn edge_label(&self, source: NodeIndex, target: NodeIndex) -> Option<&Self::EdgeLabel> {
+        self.contains_edge(source, target).then_some(&())
+    }
+}
@@ -0,0 +1,36 @@
+use crate::NodeIndex;
+use alloc::vec::Vec;
+
+/// A disjoint set forest over node indices.
+#[derive(Clone, Debug)]
+pub(crate) struct UnionFind {
+    /// The value at index `i` is the parent of node `i`.
+    parents: Vec<NodeIndex>,
+}
+
+impl UnionFind {
+    /// Creates a new [`UnionFind`] where each of
+    /// `len` nodes is in its own set.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    /// Returns the representative node of the set containing `node`.
+    pub(crate) fn find(&mut self, mut node: NodeIndex) -> NodeIndex {
+        while self.parents[node] != node {
+            // Path halving.
+            self.parents[node] = self.parents[self.parents[node]];
+            node = self.parents[node];
+        }
+        node
+    }
+
+    /// Merges the sets containing `left` and `right`.
+    pub(crate) fn union(&mut self, left: NodeIndex, right: NodeIndex) {
+        let left = self.find(left);
+        let right = self.find(right);
+        self.parents[left] = right;
+    }
+}
@@ -0,0 +1,342 @@
+use petgraph::graph::{DiGraph, UnGraph};
+use petgraph::matrix_graph::{DiMatrix, UnMatrix};
+use std::collections::HashMap;
+use vf2::{CsrGraph, Direction, Graph, MapGraph, NodeFiltered, Reversed};
+
+/// Tests building a [`CsrGraph`] from an edge list.
+#[test]
+fn csr_graph() {
+    let graph = CsrGraph::new(true, vec!['a', 'b', 'c'], [(0, 2, 1), (0, 1, 2), (2, 1, 3)]);
+
+    assert!(graph.is_directed());
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 3);
+    assert_eq!(graph.node_label(2), Some(&'c'));
+    assert_eq!(graph.neighbor_slice(0, Direction::Outgoing), &[1, 2]);
+    assert_eq!(graph.neighbor_slice(1, Direction::Incoming), &[0, 2]);
+    assert!(graph.contains_edge(2, 1));
+    assert!(!graph.contains_edge(1, 2));
+    assert_eq!(graph.edge_label(0, 2), Some(&1));
+    assert_eq!(graph.edge_label(1, 0), None);
+}
+
+/// Tests that an undirected [`CsrGraph`] stores edges in both directions.
+#[test]
+fn csr_graph_undirected() {
+    let graph = CsrGraph::new(false, vec![(); 3], [(0, 1, 'x'), (1, 2, 'y')]);
+
+    assert_eq!(graph.edge_count(), 2);
+    assert_eq!(graph.neighbor_slice(1, Direction::Incoming), &[0, 2]);
+    assert_eq!(graph.edge_label(1, 0), Some(&'x'));
+    assert_eq!(graph.edge_label(2, 1), Some(&'y'));
+}
+
+/// Tests that a [`CsrGraph`] with parallel edges reports
+/// their multiplicity and keeps the label of each edge.
+#[test]
+fn csr_graph_parallel_edges() {
+    let graph = CsrGraph::new(true, vec![(); 3], [(0, 1, 'x'), (1, 2, 'z'), (0, 1, 'y')]);
+    let labels = |node, direction| {
+        graph
+            .neighbors_with_edge(node, direction)
+            .map(|(neighbor, edge)| (neighbor, graph.edge_label_by_id(edge).copied()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(graph.edge_count(), 3);
+    assert_eq!(graph.edge_multiplicity(0, 1), 2);
+    assert_eq!(graph.edge_multiplicity(1, 2), 1);
+    assert_eq!(graph.edge_multiplicity(1, 0), 0);
+    assert_eq!(
+        labels(0, Direction::Outgoing),
+        vec![(1, Some('x')), (1, Some('y'))]
+    );
+    assert_eq!(
+        labels(1, Direction::Incoming),
+        vec![(0, Some('x')), (0, Some('y'))]
+    );
+
+    let mut copied: Vec<_> = vf2::to_petgraph::<_, petgraph::Directed>(&graph)
+        .edge_weights()
+        .copied()
+        .collect();
+    copied.sort();
+    assert_eq!(copied, vec!['x', 'y', 'z']);
+
+    let single = CsrGraph::new(true, vec![(); 2], [(0, 1, 'x')]);
+    assert_eq!(vf2::subgraph_isomorphisms(&graph, &single).count(), 0);
+    assert_eq!(vf2::subgraph_isomorphisms(&single, &graph).count(), 2);
+}
+
+/// Tests that converting petgraph graphs to [`CsrGraph`]
+/// does not change the isomorphisms found.
+#[test]
+fn csr_graph_from_petgraph() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 2), (1, 2), (2, 3), (3, 4)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 3), (1, 3), (2, 3), (1, 2), (3, 4), (4, 5)]);
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&CsrGraph::from(&query), &CsrGraph::from(&data)).vec(),
+        vf2::induced_subgraph_isomorphisms(&query, &data).vec()
+    );
+
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3)]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&CsrGraph::from(&query), &CsrGraph::from(&data)).vec(),
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests that a [`petgraph::matrix_graph::MatrixGraph`] data graph
+/// gives the same isomorphisms as a [`petgraph::Graph`].
+#[test]
+fn matrix_graph() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 2), (1, 2), (2, 3), (3, 4)]);
+    let edges = [(0, 3), (1, 3), (2, 3), (1, 2), (3, 4), (4, 5), (5, 5)];
+    let data = DiGraph::<(), ()>::from_edges(edges);
+    let matrix = DiMatrix::<(), (), Option<()>, u32>::from_edges(edges);
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&query, &matrix).vec(),
+        vf2::induced_subgraph_isomorphisms(&query, &data).vec()
+    );
+
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let edges = [(0, 1), (1, 2), (2, 0), (2, 3)];
+    let data = UnGraph::<(), ()>::from_edges(edges);
+    let matrix = UnMatrix::<(), (), Option<()>, u32>::from_edges(edges);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &matrix).vec(),
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests the neighbors of a [`petgraph::matrix_graph::MatrixGraph`]
+/// in each direction.
+#[test]
+fn matrix_graph_neighbors() {
+    let edges = [(0, 3), (1, 3), (2, 3), (3, 4), (5, 5)];
+    let directed = DiMatrix::<(), (), Option<()>, u32>::from_edges(edges);
+    let undirected = UnMatrix::<(), (), Option<()>, u32>::from_edges(edges);
+
+    assert_eq!(neighbors(&directed, 3), (vec![4], vec![0, 1, 2]));
+    assert_eq!(neighbors(&directed, 5), (vec![5], vec![5]));
+    assert_eq!(
+        neighbors(&undirected, 3),
+        (vec![0, 1, 2, 4], vec![0, 1, 2, 4])
+    );
+}
+
+/// Returns the outgoing and incoming neighbors of `node`.
+fn neighbors(graph: &impl Graph, node: usize) -> (Vec<usize>, Vec<usize>) {
+    (
+        graph.neighbors(node, Direction::Outgoing).collect(),
+        graph.neighbors(node, Direction::Incoming).collect(),
+    )
+}
+
+/// Tests that a [`Reversed`] data graph gives the same isomorphisms
+/// as an explicitly reversed petgraph graph.
+#[test]
+fn reversed() {
+    let query = DiGraph::<(), char>::from_edges([(0, 2, 'a'), (1, 2, 'a'), (2, 3, 'b')]);
+    let data = DiGraph::<(), char>::from_edges([
+        (3, 0, 'b'),
+        (3, 1, 'a'),
+        (3, 2, 'a'),
+        (2, 1, 'a'),
+        (4, 3, 'b'),
+        (5, 4, 'a'),
+    ]);
+    let mut transpose = data.clone();
+    transpose.reverse();
+
+    let reversed = vf2::subgraph_isomorphisms(&query, &Reversed(&data))
+        .default_eq()
+        .vec();
+
+    assert!(!reversed.is_empty());
+    assert_eq!(
+        reversed,
+        vf2::subgraph_isomorphisms(&query, &transpose)
+            .default_eq()
+            .vec()
+    );
+    assert_eq!(Reversed(&data).in_degree(3), 3);
+    assert_eq!(Reversed(&data).edge_label(0, 3), Some(&'b'));
+}
+
+/// Tests that a [`NodeFiltered`] view renumbers the remaining nodes
+/// and gives the same isomorphisms as the induced subgraph.
+#[test]
+fn node_filtered() {
+    let data = DiGraph::<char, ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (1, 3)]);
+    let view = NodeFiltered::new(&data, |node| node != 2);
+    // The same graph built directly with nodes 3 and 4 renumbered to 2 and 3.
+    let subgraph = DiGraph::<char, ()>::from_edges([(0, 1), (2, 3), (3, 0), (1, 2)]);
+
+    assert_eq!(view.node_count(), 4);
+    assert_eq!(view.edge_count(), 4);
+    assert_eq!(view.original_index(2), 3);
+    assert_eq!(view.filtered_index(2), None);
+    assert_eq!(view.filtered_index(4), Some(3));
+    assert!(view.contains_edge(1, 2));
+    assert!(!view.contains_edge(1, 3));
+    assert_eq!(
+        view.neighbors(1, Direction::Outgoing).collect::<Vec<_>>(),
+        vec![2]
+    );
+
+    let query = DiGraph::<char, ()>::from_edges([(0, 1), (1, 2)]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &view).vec(),
+        vf2::subgraph_isomorphisms(&query, &subgraph).vec()
+    );
+}
+
+/// Tests that incident edges match the neighbors and their edge labels
+/// for each graph implementation.
+#[test]
+fn incident_edges() {
+    let edges = [
+        (0, 1, 'a'),
+        (1, 2, 'b'),
+        (2, 0, 'c'),
+        (2, 2, 'd'),
+        (3, 1, 'e'),
+    ];
+    let directed = DiGraph::<(), char>::from_edges(edges);
+    let undirected = UnGraph::<(), char>::from_edges(edges);
+    assert_incident_edges(&directed);
+    assert_incident_edges(&undirected);
+    assert_incident_edges(&CsrGraph::from(&directed));
+    assert_incident_edges(&CsrGraph::from(&undirected));
+    assert_incident_edges(&Reversed(&directed));
+    assert_incident_edges(&NodeFiltered::new(&directed, |node| node != 0));
+    assert_incident_edges(&DiMatrix::<(), char, Option<char>, u32>::from_edges(edges));
+}
+
+/// Asserts that [`Graph::incident_edges`] returns the same pairs
+/// as combining [`Graph::neighbors`] and [`Graph::edge_label`].
+fn assert_incident_edges<G>(graph: &G)
+where
+    G: Graph<EdgeLabel = char>,
+{
+    for node in graph.node_indices() {
+        for direction in [Direction::Outgoing, Direction::Incoming] {
+            let mut incident: Vec<_> = graph
+                .incident_edges(node, direction)
+                .map(|(neighbor, label)| (neighbor, *label))
+                .collect();
+            let mut expected: Vec<_> = graph
+                .neighbors(node, direction)
+                .map(|neighbor| {
+                    let label = match direction {
+                        Direction::Outgoing => graph.edge_label(node, neighbor),
+                        Direction::Incoming => graph.edge_label(neighbor, node),
+                    };
+                    (neighbor, *label.unwrap())
+                })
+                .collect();
+            incident.sort();
+            expected.sort();
+            assert_eq!(incident, expected);
+        }
+    }
+}
+
+/// Tests that a directed [`MapGraph`] tracks incoming neighbors.
+#[test]
+fn map_graph_directed() {
+    let graph = MapGraph::new(
+        true,
+        HashMap::from([(0, vec![1, 2]), (1, vec![2]), (2, vec![])]),
+    );
+
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 3);
+    assert!(graph.contains_edge(0, 2));
+    assert!(!graph.contains_edge(2, 0));
+    let mut incoming: Vec<_> = graph.neighbors(2, Direction::Incoming).collect();
+    incoming.sort();
+    assert_eq!(incoming, vec![0, 1]);
+}
+
+/// Tests that the default edge count counts undirected self-loops once.
+#[test]
+fn edge_count_self_loops() {
+    let graph = MapGraph::new(
+        false,
+        HashMap::from([(0, vec![0, 1]), (1, vec![1, 2]), (2, vec![])]),
+    );
+
+    assert_eq!(graph.edge_count(), 4);
+}
+
+/// An undirected multigraph given by neighbor lists
+/// that repeat a neighbor once for each parallel edge.
+struct NeighborLists(Vec<Vec<usize>>);
+
+impl Graph for NeighborLists {
+    type NodeLabel = ();
+    type EdgeLabel = ();
+
+    fn is_directed(&self) -> bool {
+        false
+    }
+
+    fn node_count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn node_label(&self, node: usize) -> Option<&()> {
+        self.contains_node(node).then_some(&())
+    }
+
+    fn neighbors(&self, node: usize, _: Direction) -> impl Iterator<Item = usize> {
+        self.0[node].iter().copied()
+    }
+
+    fn contains_edge(&self, source: usize, target: usize) -> bool {
+        self.0[source].contains(&target)
+    }
+
+    fn edge_label(&self, source: usize, target: usize) -> Option<&()> {
+        self.contains_edge(source, target).then_some(&())
+    }
+}
+
+/// Tests that the default edge count counts parallel self-loops.
+#[test]
+fn edge_count_parallel_self_loops() {
+    let graph = NeighborLists(vec![vec![0, 0, 1], vec![0]]);
+
+    assert_eq!(graph.edge_count(), 3);
+}
+
+/// Tests that [`MapGraph`] sorts neighbors and drops duplicate edges.
+#[test]
+fn map_graph_sorted_unique() {
+    let adjacency = HashMap::from([(0, vec![2, 1, 2]), (1, vec![0]), (2, vec![1, 0])]);
+    let directed = MapGraph::new(true, adjacency.clone());
+    let undirected = MapGraph::new(false, adjacency);
+
+    assert_eq!(directed.edge_count(), 5);
+    assert_eq!(
+        directed
+            .neighbors(0, Direction::Outgoing)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        directed
+            .neighbors(0, Direction::Incoming)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(undirected.edge_count(), 3);
+    for node in 0..3 {
+        let neighbors: Vec<_> = undirected.neighbors(node, Direction::Outgoing).collect();
+        let expected: Vec<_> = (0..3).filter(|&other| other != node).collect();
+        assert_eq!(neighbors, expected);
+    }
+}
@@ -1,6 +1,12 @@
 use petgraph::data::{Element, FromElements};
-use petgraph::graph::{DiGraph, UnGraph};
+use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+use petgraph::stable_graph::StableGraph;
 use petgraph::{Directed, EdgeType, Graph, Undirected};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use vf2::{DataMapGraph, EmptyBehavior, IntervalLabel, NodeMatch, SourcePreference};
 
 /// Tests graph isomorphism enumeration on directed graphs.
 #[test]
@@ -34,6 +40,176 @@ fn isomorphisms_undirected() {
     );
 }
 
+/// Tests reporting whether graphs are isomorphic
+/// and the number of isomorphisms between them.
+#[test]
+fn isomorphism_report() {
+    // Two differently numbered 4-cycles. The automorphism group has order 8.
+    let left = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let right = UnGraph::<(), ()>::from_edges([(0, 2), (2, 1), (1, 3), (3, 0)]);
+
+    let report = vf2::isomorphism_report(&left, &right);
+
+    assert!(report.is_isomorphic());
+    assert_eq!(report.count, 8);
+    let witness = report.witness.unwrap();
+    for edge in left.edge_indices() {
+        let (source, target) = left.edge_endpoints(edge).unwrap();
+        assert!(right.contains_edge(
+            NodeIndex::new(witness[source.index()]),
+            NodeIndex::new(witness[target.index()]),
+        ));
+    }
+}
+
+/// Tests reporting on graphs that are not isomorphic.
+#[test]
+fn isomorphism_report_not_isomorphic() {
+    let left = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let right = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+
+    let report = vf2::isomorphism_report(&left, &right);
+
+    assert!(!report.is_isomorphic());
+    assert_eq!(report.count, 0);
+}
+
+/// Tests that two empty graphs are isomorphic through the empty map.
+#[test]
+fn isomorphism_report_empty() {
+    let empty = UnGraph::<(), ()>::new_undirected();
+
+    let report = vf2::isomorphism_report(&empty, &empty);
+
+    assert!(report.is_isomorphic());
+    assert_eq!(report.count, 1);
+    assert_eq!(report.witness, Some(vec![]));
+}
+
+/// Tests that matching many queries at once gives
+/// the same isomorphisms as matching each query alone.
+#[test]
+fn match_many() {
+    let (query, data) = small_graphs::<Directed>();
+    let path = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let star = DiGraph::<(), ()>::from_edges([(0, 3), (1, 3), (2, 3)]);
+    let queries = [&query, &path, &star, &data];
+
+    let isomorphisms = vf2::match_many(&queries, &data);
+
+    assert_eq!(
+        isomorphisms,
+        queries
+            .iter()
+            .map(|query| vf2::subgraph_isomorphisms(*query, &data).vec())
+            .collect::<Vec<_>>()
+    );
+    assert!(isomorphisms
+        .iter()
+        .all(|isomorphisms| !isomorphisms.is_empty()));
+}
+
+/// Tests that matching many queries in parallel gives
+/// the same isomorphisms as matching them in sequence.
+#[cfg(feature = "parallel")]
+#[test]
+fn par_match_many() {
+    let (query, data) = small_graphs::<Undirected>();
+    let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let star = UnGraph::<(), ()>::from_edges([(0, 3), (1, 3), (2, 3)]);
+    let queries = [&query, &path, &star, &data];
+
+    assert_eq!(
+        vf2::par_match_many(&queries, &data),
+        vf2::match_many(&queries, &data)
+    );
+}
+
+/// Tests validating mappings found by the search.
+#[test]
+fn is_valid_isomorphism() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    for problem in [
+        vf2::Problem::SubgraphIsomorphism,
+        vf2::Problem::InducedSubgraphIsomorphism,
+    ] {
+        let builder = match problem {
+            vf2::Problem::SubgraphIsomorphism => vf2::subgraph_isomorphisms(&query, &data),
+            _ => vf2::induced_subgraph_isomorphisms(&query, &data),
+        };
+        for isomorphism in builder.default_eq().vec() {
+            assert!(vf2::is_valid_isomorphism(
+                &query,
+                &data,
+                &isomorphism,
+                problem,
+                |left, right| left == right,
+                |left, right| left == right,
+            ));
+        }
+    }
+}
+
+/// Tests validating deliberately corrupted mappings.
+#[test]
+fn is_valid_isomorphism_corrupted() {
+    let query = DiGraph::<Color, ()>::from_elements([
+        Element::Node {
+            weight: Color::White,
+        },
+        Element::Node {
+            weight: Color::Black,
+        },
+        Element::Node {
+            weight: Color::White,
+        },
+        Element::Edge {
+            source: 0,
+            target: 1,
+            weight: (),
+        },
+        Element::Edge {
+            source: 1,
+            target: 2,
+            weight: (),
+        },
+    ]);
+    let mut data = query.clone();
+    data.add_node(Color::Black);
+    data.add_edge(NodeIndex::new(0), NodeIndex::new(2), ());
+    let is_valid = |mapping: &[usize], problem| {
+        vf2::is_valid_isomorphism(
+            &query,
+            &data,
+            mapping,
+            problem,
+            |left, right| left == right,
+            |_, _| true,
+        )
+    };
+    let subgraph = vf2::Problem::SubgraphIsomorphism;
+
+    assert!(is_valid(&[0, 1, 2], subgraph));
+    // Wrong length.
+    assert!(!is_valid(&[0, 1], subgraph));
+    // Not injective.
+    assert!(!is_valid(&[0, 1, 0], subgraph));
+    // Data node out of range.
+    assert!(!is_valid(&[0, 1, 4], subgraph));
+    // Query edges 0 -> 1 and 1 -> 2 are not in data.
+    assert!(!is_valid(&[0, 3, 2], subgraph));
+    // Node labels differ.
+    assert!(!is_valid(&[1, 0, 2], subgraph));
+    // Extra data edge 0 -> 2 between mapped nodes.
+    assert!(!is_valid(
+        &[0, 1, 2],
+        vf2::Problem::InducedSubgraphIsomorphism
+    ));
+    // Different node counts.
+    assert!(!is_valid(&[0, 1, 2], vf2::Problem::Isomorphism));
+}
+
 /// Tests subgraph isomorphism enumeration on directed graphs.
 #[test]
 fn subgraph_isomorphisms_directed() {
@@ -156,6 +332,285 @@ fn induced_subgraph_isomorphisms_undirected() {
     );
 }
 
+/// Tests that transparent data edges are ignored by the induced check.
+#[test]
+fn transparent_edge() {
+    let query = DiGraph::<(), Color>::from_edges([(0, 1, Color::White), (1, 2, Color::White)]);
+    let data = DiGraph::<(), Color>::from_edges([
+        (0, 1, Color::White),
+        (1, 2, Color::White),
+        (0, 2, Color::Black),
+    ]);
+
+    let strict = vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+    let transparent = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .transparent_edge(|label| *label == Color::Black)
+        .vec();
+
+    assert!(strict.is_empty());
+    assert_eq!(transparent, vec![vec![0, 1, 2]]);
+}
+
+/// Tests that transparent data edges are ignored by graph isomorphisms.
+#[test]
+fn transparent_edge_isomorphisms() {
+    let query = DiGraph::<(), Color>::from_edges([(0, 1, Color::White), (1, 2, Color::White)]);
+    let data = DiGraph::<(), Color>::from_edges([
+        (0, 1, Color::White),
+        (1, 2, Color::White),
+        (0, 2, Color::Black),
+    ]);
+
+    let strict = vf2::isomorphisms(&query, &data).vec();
+    let transparent = vf2::isomorphisms(&query, &data)
+        .transparent_edge(|label| *label == Color::Black)
+        .vec();
+
+    assert!(strict.is_empty());
+    assert_eq!(transparent, vec![vec![0, 1, 2]]);
+}
+
+/// Tests that preferring dense candidates finds denser isomorphisms first
+/// without changing which isomorphisms are found.
+#[test]
+fn prefer_dense() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 3), (0, 3)]);
+
+    let first = vf2::subgraph_isomorphisms(&query, &data).first();
+    let dense_first = vf2::subgraph_isomorphisms(&query, &data)
+        .prefer_dense()
+        .first();
+    let mut isomorphisms = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let mut dense_isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .prefer_dense()
+        .vec();
+    isomorphisms.sort();
+    dense_isomorphisms.sort();
+
+    assert_eq!(first, Some(vec![0, 1, 2]));
+    assert_eq!(dense_first, Some(vec![0, 1, 3]));
+    assert_eq!(isomorphisms, dense_isomorphisms);
+}
+
+/// Tests anchoring a query node to a data node.
+#[test]
+fn anchor() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .anchor(3, 1)
+        .unwrap()
+        .vec();
+
+    assert_eq!(
+        isomorphisms,
+        vec![
+            vec![0, 4, 3, 1, 2],
+            vec![0, 6, 3, 1, 2],
+            vec![4, 0, 3, 1, 2],
+            vec![4, 6, 3, 1, 2],
+            vec![6, 0, 3, 1, 2],
+            vec![6, 4, 3, 1, 2],
+        ]
+    );
+}
+
+/// Tests that anchoring to nodes outside the graphs is an error.
+#[test]
+fn anchor_out_of_range() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let query_error = vf2::subgraph_isomorphisms(&query, &data).anchor(5, 1).err();
+    let data_error = vf2::subgraph_isomorphisms(&query, &data).anchor(3, 8).err();
+    let include_error = vf2::subgraph_isomorphisms(&query, &data)
+        .must_include_data_node(8)
+        .err();
+
+    assert_eq!(query_error, Some(vf2::Vf2Error::QueryNodeOutOfRange(5)));
+    assert_eq!(data_error, Some(vf2::Vf2Error::DataNodeOutOfRange(8)));
+    assert_eq!(include_error, Some(vf2::Vf2Error::DataNodeOutOfRange(8)));
+}
+
+/// Tests finding only isomorphisms that include a data node.
+#[test]
+fn must_include_data_node() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let mut isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .must_include_data_node(5)
+        .unwrap()
+        .vec();
+    isomorphisms.sort();
+
+    let mut expected = vf2::subgraph_isomorphisms(&query, &data).vec();
+    expected.retain(|isomorphism| isomorphism.contains(&5));
+    assert_eq!(isomorphisms.len(), 12);
+    assert_eq!(isomorphisms, expected);
+}
+
+/// Tests that a dense adjacency matrix does not change the isomorphisms found.
+#[test]
+fn dense_adjacency() {
+    let (query, data) = small_graphs::<Directed>();
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .dense_adjacency()
+            .vec(),
+        vf2::induced_subgraph_isomorphisms(&query, &data).vec()
+    );
+
+    let (query, data) = small_graphs::<Undirected>();
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .dense_adjacency()
+            .vec(),
+        vf2::induced_subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests that a node equality function can abort the search.
+#[test]
+fn node_eq_abort() {
+    let (query, data) = small_labeled_graphs::<Undirected>();
+
+    let matched = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .node_eq_abort(|left, right| match left == right {
+            true => NodeMatch::Match,
+            false => NodeMatch::NoMatch,
+        })
+        .vec();
+    let aborted = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .node_eq_abort(|_, _| NodeMatch::Abort)
+        .vec();
+
+    assert_eq!(
+        matched,
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .node_eq(|left, right| left == right)
+            .vec()
+    );
+    assert!(aborted.is_empty());
+}
+
+/// Tests that aborting keeps the isomorphisms found so far.
+#[test]
+fn node_eq_abort_keeps_found() {
+    let (query, data) = small_graphs::<Undirected>();
+    let data = data.map(|node, _| node.index(), |_, _| ());
+
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let found = vf2::subgraph_isomorphisms(&query, &data)
+        .node_eq_abort(|_, &data_node| match data_node {
+            7 => NodeMatch::Abort,
+            _ => NodeMatch::Match,
+        })
+        .vec();
+
+    assert_eq!(found, vec![vec![0, 1, 3, 4, 5]]);
+    assert!(all.starts_with(&found));
+}
+
+/// Tests that the predicates shared between clones of the builder
+/// may capture values that are not `Sync`.
+#[test]
+fn predicates_capture_cells() {
+    let (query, data) = small_graphs::<Undirected>();
+    let calls = Cell::new(0);
+    let count = || calls.set(calls.get() + 1);
+
+    let isomorphisms = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .node_eq_abort(|_, _| {
+            count();
+            NodeMatch::Match
+        })
+        .edge_eq_symmetric(|_, _, _| {
+            count();
+            true
+        })
+        .edge_eq_ctx(|_, _| {
+            count();
+            true
+        })
+        .transparent_edge(|_| {
+            count();
+            false
+        })
+        .vec();
+
+    assert_eq!(
+        isomorphisms,
+        vf2::induced_subgraph_isomorphisms(&query, &data).vec()
+    );
+    assert!(calls.get() > 0);
+}
+
+/// Tests that the computed order is a permutation of the query nodes
+/// that the search follows, starting at the root pair if set.
+///
+/// There is no degree-based ordering, so the order starts at node 0
+/// even if another node has a higher degree.
+#[test]
+fn computed_order() {
+    for (query, data) in [
+        small_graphs::<Directed>(),
+        (
+            // Node 2 has the highest degree.
+            DiGraph::<(), ()>::from_edges([(3, 0), (1, 2), (4, 2), (2, 3)]),
+            small_graphs::<Directed>().1,
+        ),
+    ] {
+        let builder = vf2::subgraph_isomorphisms(&query, &data);
+        let order = builder.computed_order();
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        let ordered = vf2::subgraph_isomorphisms(&query, &data)
+            .query_order(order.clone())
+            .unwrap()
+            .vec();
+
+        assert_eq!(sorted, (0..query.node_count()).collect::<Vec<_>>());
+        assert_eq!(order[0], 0);
+        assert_eq!(ordered, builder.vec());
+    }
+    let (query, data) = small_graphs::<Undirected>();
+    let rooted = vf2::subgraph_isomorphisms(&query, &data)
+        .anchor(2, 3)
+        .unwrap();
+
+    assert_eq!(rooted.computed_order()[0], 2);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .query_order(vec![4, 3, 2, 1, 0])
+            .unwrap()
+            .computed_order(),
+        vec![4, 3, 2, 1, 0]
+    );
+}
+
+/// Tests that a mapping is formatted with the node labels.
+#[test]
+fn format_mapping() {
+    let (query, data) = small_labeled_graphs::<Undirected>();
+
+    let isomorphism = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .first()
+        .unwrap();
+    let mut partial = isomorphism.clone();
+    partial[4] = usize::MAX;
+
+    assert_eq!(
+        vf2::format_mapping(&query, &data, &isomorphism),
+        "q0(Black) -> d0(Black)\n\
+         q1(White) -> d2(White)\n\
+         q2(White) -> d3(White)\n\
+         q3(Black) -> d4(Black)\n\
+         q4(White) -> d5(White)\n"
+    );
+    assert!(vf2::format_mapping(&query, &data, &partial).ends_with("q4(White) -> ?\n"));
+}
+
 /// Tests that node and edge labels are not compared by default.
 #[test]
 fn no_eq_by_default() {
@@ -238,107 +693,2584 @@ fn disconnected() {
     );
 }
 
-/// Tests that an empty query results in a panic.
+/// Tests that each connected component of a disconnected query
+/// is covered before the next is started.
 #[test]
-#[should_panic]
-fn empty_query() {
-    let query = DiGraph::<(), ()>::new();
-    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+fn disconnected_components_in_turn() {
+    // Query components are {0, 2, 4} and {1, 3}.
+    let query = DiGraph::<(), ()>::from_edges([(0, 2), (2, 4), (1, 3)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (2, 3), (3, 4), (5, 6), (7, 8)]);
+    let mut partials = Vec::new();
 
-    // Should panic since query is empty.
-    vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|map, _| partials.push(map.to_vec()))
+        .vec();
+
+    assert_eq!(
+        isomorphisms,
+        vec![
+            vec![2, 0, 3, 1, 4],
+            vec![2, 5, 3, 6, 4],
+            vec![2, 7, 3, 8, 4],
+        ]
+    );
+    let covered = |map: &Vec<usize>, node: usize| map[node] != usize::MAX;
+    for map in partials {
+        if covered(&map, 1) || covered(&map, 3) {
+            assert!([0, 2, 4].iter().all(|&node| covered(&map, node)));
+        }
+    }
 }
 
-/// Tests that query and data graphs must be the same size
-/// when finding graph isomorphisms.
+/// Tests that matching the components of a disconnected query
+/// on their own finds the same isomorphisms.
 #[test]
-#[should_panic]
-fn isomorphisms_same_size() {
-    let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
-    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+fn decompose_components() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (2, 3)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4)]);
 
-    // Should panic since query and data are not the same size.
-    vf2::isomorphisms(&query, &data).vec();
-}
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .decompose_components()
+        .vec();
 
-/// Tests that [`Debug`] is implemented for [`Vf2Builder`].
-///
-/// [`Vf2Builder`]: vf2::Vf2Builder
-#[test]
-fn builder_debug() {
-    let (query, data) = small_graphs::<Directed>();
-    let builder = vf2::subgraph_isomorphisms(&query, &data);
+    assert_eq!(
+        isomorphisms,
+        vec![
+            vec![0, 1, 3, 4],
+            vec![1, 2, 3, 4],
+            vec![3, 4, 0, 1],
+            vec![3, 4, 1, 2],
+        ]
+    );
 
-    // This should not panic due to missing debug implementation.
-    let debug = format!("{builder:#?}");
+    // Query components are {0, 2, 4}, {1, 3} and {5}.
+    let mut query = Graph::<u8, ()>::from_edges([(0, 2), (2, 4), (1, 3)]);
+    query.add_node(1);
+    query[NodeIndex::new(1)] = 1;
+    let mut data = Graph::<u8, ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 0),
+        (4, 5),
+        (5, 6),
+        (7, 7),
+        (8, 9),
+    ]);
+    data[NodeIndex::new(4)] = 1;
+    data[NodeIndex::new(8)] = 1;
+    for induced in [false, true] {
+        let builder = || {
+            if induced {
+                vf2::induced_subgraph_isomorphisms(&query, &data)
+            } else {
+                vf2::subgraph_isomorphisms(&query, &data)
+            }
+            .default_eq()
+        };
+        let mut expected = builder().vec();
+        let mut decomposed = builder().decompose_components().vec();
+        expected.sort();
+        decomposed.sort();
 
-    assert!(!debug.is_empty());
+        assert!(!expected.is_empty());
+        assert_eq!(decomposed, expected);
+    }
+
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .decompose_components()
+        .iter();
+    let first = iter.next().unwrap();
+    let snapshot = iter.checkpoint();
+    let rest: Vec<_> = iter.collect();
+    let resumed: Vec<_> = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .decompose_components()
+        .resume(snapshot)
+        .unwrap()
+        .collect();
+
+    assert!(!rest.contains(&first));
+    assert_eq!(resumed, rest);
 }
 
-/// Tests that [`Debug`] is implemented for [`IsomorphismIter`].
-///
-/// [`IsomorphismIter`]: vf2::IsomorphismIter
+/// Tests that a query self-loop requires a data self-loop on directed graphs.
 #[test]
-fn iter_debug() {
-    let (query, data) = small_graphs::<Directed>();
-    let iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+fn self_loops_directed() {
+    let looped = DiGraph::<(), ()>::from_edges([(0, 0), (0, 1)]);
+    let unlooped = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 1), (1, 2), (2, 3)]);
 
-    // This should not panic due to missing debug implementation.
-    let debug = format!("{iter:#?}");
+    let subgraph = vf2::subgraph_isomorphisms(&looped, &data).vec();
+    let induced = vf2::induced_subgraph_isomorphisms(&looped, &data).vec();
+    let induced_unlooped = vf2::induced_subgraph_isomorphisms(&unlooped, &data).vec();
 
-    assert!(!debug.is_empty());
+    assert_eq!(subgraph, vec![vec![1, 2]]);
+    assert_eq!(induced, vec![vec![1, 2]]);
+    assert_eq!(induced_unlooped, vec![vec![2, 3]]);
 }
 
-/// Tests finding only the first isomorphism.
+/// Tests that a query self-loop requires a data self-loop on undirected graphs.
 #[test]
-fn first() {
-    let (query, data) = small_graphs::<Directed>();
+fn self_loops_undirected() {
+    let looped = UnGraph::<(), ()>::from_edges([(0, 0), (0, 1)]);
+    let unlooped = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 1), (1, 2)]);
 
-    let first = vf2::subgraph_isomorphisms(&query, &data).first();
+    let subgraph = vf2::subgraph_isomorphisms(&looped, &data).vec();
+    let induced_unlooped = vf2::induced_subgraph_isomorphisms(&unlooped, &data).vec();
 
-    assert_eq!(first, Some(vec![0, 1, 3, 4, 5]));
+    assert_eq!(subgraph, vec![vec![1, 0], vec![1, 2]]);
+    assert!(induced_unlooped.is_empty());
 }
 
-/// Tests collecting isomorphisms into a vector.
+/// Tests that self-loop labels are compared.
 #[test]
-fn vec() {
-    let (query, data) = small_graphs::<Directed>();
+fn self_loop_labels() {
+    let query = DiGraph::<(), Color>::from_edges([(0, 0, Color::Black)]);
+    let data = DiGraph::<(), Color>::from_edges([(0, 0, Color::White), (1, 1, Color::Black)]);
 
-    let vec = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
 
-    assert!(!vec.is_empty());
+    assert_eq!(isomorphisms, vec![vec![1]]);
 }
 
-/// Tests getting an iterator of isomorphisms.
+/// Tests that a single-node query matches exactly
+/// the data nodes with equal labels, in order.
 #[test]
-fn iter() {
-    let (query, data) = small_graphs::<Directed>();
+fn single_node_query() {
+    let (_, data) = small_labeled_graphs::<Directed>();
+    let mut query = DiGraph::<Color, Color>::new();
+    query.add_node(Color::White);
 
-    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
 
-    assert!(iter.next().is_some());
+    let expected: Vec<_> = data
+        .node_indices()
+        .filter(|&node| data[node] == Color::White)
+        .map(|node| vec![node.index()])
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(isomorphisms, expected);
+
+    let mut looped = DiGraph::<(), ()>::from_edges([(0, 1), (1, 1), (1, 2)]);
+    looped.add_node(());
+    let mut query = DiGraph::<(), ()>::new();
+    query.add_node(());
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&query, &looped).vec(),
+        vec![vec![0], vec![2], vec![3]]
+    );
+    query.add_edge(NodeIndex::new(0), NodeIndex::new(0), ());
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &looped).vec(),
+        vec![vec![1]]
+    );
 }
 
-/// Tests getting a reference to the next isomorphism.
+/// Tests that an empty query results in a panic.
 #[test]
-fn iter_next_ref() {
-    let (query, data) = small_graphs::<Directed>();
-    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
-
-    let next_ref = iter.next_ref();
+#[should_panic]
+fn empty_query() {
+    let query = DiGraph::<(), ()>::new();
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
 
-    assert_eq!(next_ref, Some(&vec![0, 1, 3, 4, 5]));
+    // Should panic since query is empty.
+    vf2::induced_subgraph_isomorphisms(&query, &data).vec();
 }
 
-/// Tests converting the iterator into the next isomorphism.
+/// Tests that an empty query can find no isomorphisms
+/// or the empty map instead of panicking.
 #[test]
-fn iter_into_next() {
-    let (query, data) = small_graphs::<Directed>();
-    let iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+fn empty_query_behavior() {
+    let query = DiGraph::<(), ()>::new();
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
 
-    let next = iter.into_next();
+    let none = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .empty_query_behavior(EmptyBehavior::NoMatch)
+        .vec();
+    assert!(none.is_empty());
 
-    assert_eq!(next, Some(vec![0, 1, 3, 4, 5]));
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data)
+        .empty_query_behavior(EmptyBehavior::EmptyMatch)
+        .iter();
+    assert_eq!(iter.remaining_upper_bound(), 1);
+    assert_eq!(iter.next(), Some(vec![]));
+    assert_eq!(iter.remaining_upper_bound(), 0);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next(), None);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .empty_query_behavior(EmptyBehavior::EmptyMatch)
+            .match_clusters(),
+        vec![vec![Vec::<usize>::new()]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .empty_query_behavior(EmptyBehavior::EmptyMatch)
+            .one_per_root(),
+        vec![Vec::<usize>::new()]
+    );
+    assert_eq!(
+        vf2::isomorphisms(&query, &query)
+            .empty_query_behavior(EmptyBehavior::EmptyMatch)
+            .count(),
+        1
+    );
+}
+
+/// Tests that a query with more nodes than the data graph
+/// yields no isomorphisms instead of panicking.
+#[test]
+fn oversized_query() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+
+    assert!(vf2::subgraph_isomorphisms(&query, &data).vec().is_empty());
+    assert!(vf2::induced_subgraph_isomorphisms(&query, &data)
+        .vec()
+        .is_empty());
+}
+
+/// Tests that query and data graphs of different sizes
+/// have no graph isomorphisms.
+#[test]
+fn isomorphisms_same_size() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+
+    assert!(vf2::isomorphisms(&query, &data).vec().is_empty());
+    assert!(vf2::isomorphisms(&data, &query).vec().is_empty());
+    assert_eq!(
+        vf2::isomorphisms(&data, &query)
+            .try_iter()
+            .map(Iterator::count),
+        Ok(0)
+    );
+}
+
+/// Tests that copying a custom graph into petgraph preserves isomorphisms.
+#[test]
+fn to_petgraph() {
+    let query = AdjListGraph::new(true, 3, &[(0, 1), (1, 2)]);
+    let data = AdjListGraph::new(true, 4, &[(0, 1), (1, 2), (2, 3), (3, 1)]);
+
+    let query_petgraph = vf2::to_petgraph::<_, Directed>(&query);
+    let data_petgraph = vf2::to_petgraph::<_, Directed>(&data);
+
+    assert_eq!(query_petgraph.node_count(), 3);
+    assert_eq!(data_petgraph.edge_count(), 4);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query_petgraph, &data_petgraph).vec(),
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests that copying and extracting parallel edges
+/// keeps the label of each edge.
+#[test]
+fn parallel_edge_labels_copied() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = DiGraph::<(), u32>::from_edges([(0, 1, 1), (0, 1, 2)]);
+    let labels = |graph: &Graph<(), u32>| {
+        let mut labels: Vec<_> = graph.edge_weights().copied().collect();
+        labels.sort();
+        labels
+    };
+
+    let copied = vf2::to_petgraph::<_, Directed>(&data);
+    let extracted = vf2::subgraph_isomorphisms(&query, &data).extract_subgraphs::<Directed>();
+
+    assert_eq!(labels(&copied), vec![1, 2]);
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(labels(&extracted[0]), vec![1, 2]);
+}
+
+/// Tests that extracted subgraphs include every data edge
+/// between the mapped nodes, with their labels.
+#[test]
+fn extract_subgraphs() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let mut data = DiGraph::<char, u32>::new();
+    let d = ['a', 'b', 'c', 'd'].map(|label| data.add_node(label));
+    data.extend_with_edges([
+        (d[0], d[1], 1),
+        (d[1], d[2], 2),
+        (d[2], d[0], 3),
+        (d[2], d[3], 4),
+    ]);
+
+    let subgraphs = vf2::subgraph_isomorphisms(&query, &data).extract_subgraphs::<Directed>();
+
+    let sizes: Vec<_> = subgraphs
+        .iter()
+        .map(|subgraph| (subgraph.node_count(), subgraph.edge_count()))
+        .collect();
+    assert_eq!(sizes, vec![(3, 3), (3, 3), (3, 2), (3, 3)]);
+    let first = &subgraphs[0];
+    assert_eq!(first.node_weights().collect::<String>(), "abc");
+    assert_eq!(
+        first.edge_weights().copied().collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+}
+
+/// Tests that edges can be compared by the labels of their endpoints.
+#[test]
+fn edge_eq_ctx() {
+    let mut query = DiGraph::<char, u32>::new();
+    let q = ['x', 'y'].map(|label| query.add_node(label));
+    query.add_edge(q[0], q[1], 1);
+    let mut data = DiGraph::<char, u32>::new();
+    let d = ['x', 'y', 'z', 'y'].map(|label| data.add_node(label));
+    data.extend_with_edges([(d[0], d[1], 1), (d[0], d[2], 1), (d[2], d[3], 2)]);
+
+    let plain = vf2::subgraph_isomorphisms(&query, &data)
+        .edge_eq(|left, right| left == right)
+        .vec();
+    let ctx = vf2::subgraph_isomorphisms(&query, &data)
+        .edge_eq(|left, right| left == right)
+        .edge_eq_ctx(|query_edge, data_edge| query_edge.target == data_edge.target)
+        .vec();
+
+    assert_eq!(plain, vec![vec![0, 1], vec![0, 2]]);
+    assert_eq!(ctx, vec![vec![0, 1]]);
+}
+
+/// Tests finding a maximum common induced subgraph.
+#[test]
+fn max_common_induced_subgraph() {
+    // A triangle with a tail, and a square with a diagonal.
+    let a = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3)]);
+    let b = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)]);
+
+    let map = vf2::max_common_induced_subgraph(&a, &b);
+
+    // The tail node 3 is adjacent to 2 but not 0 or 1,
+    // which no fourth node of `b` is.
+    assert_eq!(map, vec![0, 1, 2, usize::MAX]);
+    let covered: Vec<_> = (0..4).filter(|&node| map[node] != usize::MAX).collect();
+    let subgraph = vf2::NodeFiltered::new(&a, |node| covered.contains(&node));
+    let mapped: Vec<_> = covered.iter().map(|&node| map[node]).collect();
+    assert!(vf2::is_valid_isomorphism(
+        &subgraph,
+        &b,
+        &mapped,
+        vf2::Problem::InducedSubgraphIsomorphism,
+        |_, _| true,
+        |_, _| true,
+    ));
+
+    let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let mut isolated = UnGraph::<(), ()>::new_undirected();
+    isolated.add_node(());
+    isolated.add_node(());
+    assert_eq!(
+        vf2::max_common_induced_subgraph(&path, &isolated),
+        vec![0, usize::MAX, usize::MAX]
+    );
+}
+
+/// Tests finding the maximal common connected induced subgraphs
+/// of a path and a shorter path beside a separate edge.
+#[test]
+fn maximal_common_connected() {
+    const NONE: usize = usize::MAX;
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4)]);
+
+    assert_eq!(
+        vf2::maximal_common_connected(&query, &data, 3),
+        vec![vec![0, 1, 2, NONE], vec![NONE, 0, 1, 2]]
+    );
+    let maximal = vf2::maximal_common_connected(&query, &data, 2);
+    // Both paths of three nodes, query edges mapped to the data edges
+    // that neither path extends, and every query edge on the separate edge.
+    assert_eq!(maximal.len(), 7);
+    assert!(maximal.contains(&vec![1, 2, NONE, NONE]));
+    assert!(maximal.contains(&vec![NONE, NONE, 0, 1]));
+    assert!(maximal.contains(&vec![NONE, 3, 4, NONE]));
+    assert!(!maximal.contains(&vec![0, 1, NONE, NONE]));
+    assert!(vf2::maximal_common_connected(&query, &data, 4).is_empty());
+}
+
+/// Tests that nodes labeled by degree only match nodes of equal degree.
+#[test]
+fn structural_labels() {
+    let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    // A path of three nodes beside a path of four.
+    let paths = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4), (4, 5), (5, 6)]);
+
+    let labeled_path = vf2::StructuralLabels::new(&path);
+    let labeled_paths = vf2::StructuralLabels::new(&paths);
+
+    assert_eq!(vf2::Graph::node_label(&labeled_paths, 4), Some(&2));
+    assert_eq!(vf2::subgraph_isomorphisms(&path, &paths).count(), 6);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&labeled_path, &labeled_paths)
+            .default_eq()
+            .vec(),
+        vec![vec![0, 1, 2], vec![2, 1, 0]]
+    );
+}
+
+/// Tests that index order constraints drop the isomorphisms
+/// that map a constrained pair to decreasing data nodes.
+#[test]
+fn index_order_constraints() {
+    let (query, data) = small_graphs::<Undirected>();
+    let pairs = [(0, 1), (1, 4)];
+
+    let constrained = vf2::subgraph_isomorphisms(&query, &data)
+        .index_order_constraints(&pairs)
+        .unwrap()
+        .vec();
+    let mut expected = vf2::subgraph_isomorphisms(&query, &data).vec();
+    expected.retain(|isomorphism| {
+        pairs
+            .iter()
+            .all(|&(earlier, later)| isomorphism[earlier] < isomorphism[later])
+    });
+
+    assert!(!expected.is_empty());
+    assert!(expected.len() < vf2::subgraph_isomorphisms(&query, &data).count());
+    assert_eq!(constrained, expected);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .index_order_constraints(&[(0, 5)])
+            .err(),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(5))
+    );
+}
+
+/// Tests that occupied data nodes are never mapped to,
+/// so occupying the hub of the data graph leaves no matches.
+#[test]
+fn occupied_data_nodes() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let without_leaf = vf2::subgraph_isomorphisms(&query, &data)
+        .occupied_data_nodes(&[7])
+        .unwrap()
+        .vec();
+    let mut expected = vf2::subgraph_isomorphisms(&query, &data).vec();
+    expected.retain(|isomorphism| !isomorphism.contains(&7));
+
+    assert!(!expected.is_empty());
+    assert!(expected.len() < vf2::subgraph_isomorphisms(&query, &data).count());
+    assert_eq!(without_leaf, expected);
+    assert!(vf2::subgraph_isomorphisms(&query, &data)
+        .occupied_data_nodes(&[3])
+        .unwrap()
+        .vec()
+        .is_empty());
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .occupied_data_nodes(&[8])
+            .err(),
+        Some(vf2::Vf2Error::DataNodeOutOfRange(8))
+    );
+}
+
+/// Tests that collecting into a buffer gives the same isomorphisms
+/// as collecting into a new vector and reuses the buffer's allocations.
+#[test]
+fn collect_into() {
+    let (query, data) = small_graphs::<Undirected>();
+    let single = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    let mut buf = Vec::new();
+
+    vf2::subgraph_isomorphisms(&query, &data).collect_into(&mut buf);
+    assert_eq!(buf, vf2::subgraph_isomorphisms(&query, &data).vec());
+    let outer = buf.as_ptr();
+    let first = buf[0].as_ptr();
+
+    vf2::subgraph_isomorphisms(&single, &data).collect_into(&mut buf);
+    assert_eq!(buf, vf2::subgraph_isomorphisms(&single, &data).vec());
+
+    vf2::subgraph_isomorphisms(&query, &data).collect_into(&mut buf);
+    assert_eq!(buf, vf2::subgraph_isomorphisms(&query, &data).vec());
+    assert_eq!(buf.as_ptr(), outer);
+    assert_eq!(buf[0].as_ptr(), first);
+}
+
+/// Tests that comparing only node labels or only edge labels
+/// enables exactly one of the two comparisons.
+#[test]
+fn nodes_only_and_edges_only_eq() {
+    let mut query = DiGraph::<u8, char>::new();
+    let (a, b) = (query.add_node(1), query.add_node(1));
+    query.add_edge(a, b, 'x');
+    // An edge with equal labels between unequal nodes,
+    // and an edge with unequal labels between equal nodes.
+    let mut data = DiGraph::<u8, char>::new();
+    let nodes = [1, 2, 1, 1].map(|label| data.add_node(label));
+    data.add_edge(nodes[0], nodes[1], 'x');
+    data.add_edge(nodes[2], nodes[3], 'y');
+
+    assert_eq!(vf2::subgraph_isomorphisms(&query, &data).count(), 2);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .nodes_only_eq()
+            .vec(),
+        vec![vec![2, 3]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .edges_only_eq()
+            .vec(),
+        vec![vec![0, 1]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .edges_only_eq()
+            .vec(),
+        vec![vec![0, 1]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .nodes_only_eq()
+            .vec(),
+        vec![vec![2, 3]]
+    );
+    assert!(vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .vec()
+        .is_empty());
+}
+
+/// Tests that [`Debug`] is implemented for [`Vf2Builder`].
+///
+/// [`Vf2Builder`]: vf2::Vf2Builder
+#[test]
+fn builder_debug() {
+    let (query, data) = small_graphs::<Directed>();
+    let builder = vf2::subgraph_isomorphisms(&query, &data);
+
+    // This should not panic due to missing debug implementation.
+    let debug = format!("{builder:#?}");
+
+    assert!(!debug.is_empty());
+}
+
+/// Tests that [`Debug`] is implemented for [`IsomorphismIter`].
+///
+/// [`IsomorphismIter`]: vf2::IsomorphismIter
+#[test]
+fn iter_debug() {
+    let (query, data) = small_graphs::<Directed>();
+    let iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    // This should not panic due to missing debug implementation.
+    let debug = format!("{iter:#?}");
+
+    assert!(!debug.is_empty());
+}
+
+/// Tests finding only the first isomorphism.
+#[test]
+fn first() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let first = vf2::subgraph_isomorphisms(&query, &data).first();
+
+    assert_eq!(first, Some(vec![0, 1, 3, 4, 5]));
+}
+
+/// Tests finding the deepest partial isomorphism when none is complete.
+#[test]
+fn best_partial() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4)]);
+
+    let best = vf2::subgraph_isomorphisms(&query, &data).best_partial();
+
+    // Query node 2 has an outgoing edge, but data node 2 does not,
+    // so the pair is pruned by degree and never covered.
+    assert_eq!(best, Some((vec![0, 1, usize::MAX, usize::MAX], 2)));
+}
+
+/// Tests finding the deepest partial isomorphism
+/// when every pair passes the degree check.
+#[test]
+fn best_partial_cycle() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+
+    let best = vf2::subgraph_isomorphisms(&query, &data).best_partial();
+
+    assert_eq!(best, Some((vec![0, 1, 2, usize::MAX], 3)));
+}
+
+/// Tests that the best partial isomorphism is complete if one exists.
+#[test]
+fn best_partial_complete() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let best = vf2::subgraph_isomorphisms(&query, &data).best_partial();
+
+    assert_eq!(best, Some((vec![0, 1, 3, 4, 5], 5)));
+}
+
+/// Tests collecting isomorphisms into a vector.
+#[test]
+fn vec() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let vec = vf2::subgraph_isomorphisms(&query, &data).vec();
+
+    assert!(!vec.is_empty());
+}
+
+/// Tests checking whether an isomorphism exists.
+#[test]
+fn exists() {
+    let (query, data) = small_graphs::<Directed>();
+    let path = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let cycle = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+
+    assert!(vf2::isomorphisms(&path, &path).exists());
+    assert!(!vf2::isomorphisms(&path, &cycle).exists());
+    assert!(vf2::subgraph_isomorphisms(&query, &data).exists());
+    assert!(!vf2::subgraph_isomorphisms(&cycle, &data).exists());
+    assert!(vf2::induced_subgraph_isomorphisms(&path, &data).exists());
+    assert!(!vf2::induced_subgraph_isomorphisms(&path, &cycle).exists());
+}
+
+/// Tests folding over isomorphisms and stopping early.
+#[test]
+fn try_fold() {
+    let (query, data) = small_graphs::<Directed>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let sum = |isomorphism: &Vec<usize>| isomorphism.iter().sum::<usize>();
+
+    let total = vf2::subgraph_isomorphisms(&query, &data).try_fold(0, |total, isomorphism| {
+        ControlFlow::Continue(total + sum(isomorphism))
+    });
+    let first_two = vf2::subgraph_isomorphisms(&query, &data).try_fold(
+        (0, 0),
+        |(count, total), isomorphism| {
+            let next = (count + 1, total + sum(isomorphism));
+            if next.0 == 2 {
+                ControlFlow::Break(next)
+            } else {
+                ControlFlow::Continue(next)
+            }
+        },
+    );
+
+    assert_eq!(total, all.iter().map(sum).sum::<usize>());
+    assert_eq!(first_two, (2, sum(&all[0]) + sum(&all[1])));
+}
+
+/// Tests collecting fewer isomorphisms than exist.
+#[test]
+fn first_n() {
+    let (query, data) = small_graphs::<Directed>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+
+    let first = vf2::subgraph_isomorphisms(&query, &data).first_n(2);
+
+    assert_eq!(first, all[..2]);
+}
+
+/// Tests collecting more isomorphisms than exist.
+#[test]
+fn first_n_more_than_exist() {
+    let (query, data) = small_graphs::<Directed>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+
+    let first = vf2::subgraph_isomorphisms(&query, &data).first_n(all.len() + 1);
+
+    assert_eq!(first, all);
+}
+
+/// Tests collecting isomorphisms in lexicographic order.
+#[test]
+fn sorted() {
+    let (query, data) = small_graphs::<Undirected>();
+    let mut expected = vf2::subgraph_isomorphisms(&query, &data).vec();
+    expected.sort();
+
+    let sorted = vf2::subgraph_isomorphisms(&query, &data).sorted();
+
+    assert_eq!(sorted, expected);
+    assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+/// Tests collecting isomorphisms into a flat vector.
+#[test]
+fn vec_flat() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let (flat, stride) = vf2::subgraph_isomorphisms(&query, &data).vec_flat();
+
+    assert_eq!(stride, 5);
+    assert_eq!(
+        flat.chunks(stride)
+            .map(<[usize]>::to_vec)
+            .collect::<Vec<_>>(),
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests counting isomorphisms.
+#[test]
+fn count() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let count = vf2::subgraph_isomorphisms(&query, &data).count();
+
+    assert_eq!(count, 36);
+}
+
+/// Tests counting isomorphisms grouped by a key.
+#[test]
+fn count_by() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    let counts = vf2::subgraph_isomorphisms(&query, &data).count_by(|isomorphism| isomorphism[3]);
+
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts[&1], 6);
+    assert_eq!(counts[&4], 12);
+    assert_eq!(
+        counts.values().sum::<usize>(),
+        vf2::subgraph_isomorphisms(&query, &data).count()
+    );
+}
+
+/// Tests counting isomorphisms by the data node a query node maps to.
+#[test]
+fn count_by_query_node() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let histogram = vf2::subgraph_isomorphisms(&query, &data)
+        .count_by_query_node(0)
+        .unwrap();
+
+    assert_eq!(
+        histogram,
+        vf2::subgraph_isomorphisms(&query, &data).count_by(|isomorphism| isomorphism[0])
+    );
+    assert_eq!(
+        histogram.values().sum::<usize>(),
+        vf2::subgraph_isomorphisms(&query, &data).count()
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .count_by_query_node(query.node_count())
+            .err(),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(query.node_count()))
+    );
+}
+
+/// Tests grouping overlapping isomorphisms into clusters.
+#[test]
+fn match_clusters() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4), (4, 5)]);
+
+    let clusters = vf2::subgraph_isomorphisms(&query, &data).match_clusters();
+
+    assert_eq!(
+        clusters,
+        vec![
+            vec![vec![0, 1], vec![1, 0], vec![1, 2], vec![2, 1]],
+            vec![vec![3, 4], vec![4, 3], vec![4, 5], vec![5, 4]],
+        ]
+    );
+}
+
+/// Tests that permutations covering the same data nodes are collapsed.
+#[test]
+fn distinct_node_sets() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (4, 3)]);
+    assert_eq!(vf2::subgraph_isomorphisms(&query, &data).count(), 6);
+
+    let sets = vf2::subgraph_isomorphisms(&query, &data).distinct_node_sets();
+
+    assert_eq!(sets, vec![vec![0, 1, 2, 3]]);
+}
+
+/// Tests that the peak depth never exceeds the number of query nodes
+/// and reaches it once an isomorphism is found.
+#[test]
+fn max_depth_reached() {
+    let (query, data) = small_graphs::<Directed>();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    assert_eq!(iter.max_depth_reached(), 0);
+    iter.next_ref().unwrap();
+    assert_eq!(iter.max_depth_reached(), query.node_count());
+    while iter.next_ref().is_some() {}
+    assert!(iter.max_depth_reached() <= query.node_count());
+}
+
+/// Tests that partial maps that cannot be extended are found
+/// when they cover enough query nodes.
+#[test]
+fn min_matched_nodes() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let unmatched = usize::MAX;
+
+    let three = vf2::induced_subgraph_isomorphisms(&query, &data).min_matched_nodes(3);
+    let two = vf2::induced_subgraph_isomorphisms(&query, &data).min_matched_nodes(2);
+    let full = vf2::induced_subgraph_isomorphisms(&data, &query).min_matched_nodes(3);
+
+    assert_eq!(
+        three,
+        vec![vec![0, 1, 2, unmatched], vec![2, 1, 0, unmatched]]
+    );
+    assert_eq!(two.len(), 4);
+    assert!(two.contains(&vec![1, 0, unmatched, unmatched]));
+    assert_eq!(
+        full,
+        vf2::induced_subgraph_isomorphisms(&data, &query).vec()
+    );
+}
+
+/// Tests that the data nodes no triangle can cover are unmatched.
+#[test]
+fn unmatched_data_nodes() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (5, 5)]);
+
+    let unmatched = vf2::subgraph_isomorphisms(&query, &data).unmatched_data_nodes();
+
+    assert_eq!(unmatched, vec![3, 4, 5]);
+}
+
+/// Tests that one isomorphism is kept for each data node
+/// query node 0 maps to, the first one found.
+#[test]
+fn one_per_root() {
+    let (query, data) = small_graphs::<Undirected>();
+    let mut roots = Vec::new();
+    let expected: Vec<_> = vf2::subgraph_isomorphisms(&query, &data)
+        .vec()
+        .into_iter()
+        .filter(|isomorphism| {
+            let new = !roots.contains(&isomorphism[0]);
+            roots.push(isomorphism[0]);
+            new
+        })
+        .collect();
+
+    let matches = vf2::subgraph_isomorphisms(&query, &data).one_per_root();
+
+    assert_eq!(matches, expected);
+    let mut found_roots: Vec<_> = matches.iter().map(|isomorphism| isomorphism[0]).collect();
+    found_roots.sort_unstable();
+    found_roots.dedup();
+    assert_eq!(found_roots.len(), matches.len());
+    assert!(matches.len() > 1);
+}
+
+/// Tests that disjoint matches never share a data node
+/// and cover every copy of the query that fits.
+#[test]
+fn disjoint_matches() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = UnGraph::<(), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (2, 0),
+        (2, 3),
+        (3, 4),
+        (4, 5),
+        (5, 3),
+        (6, 7),
+        (7, 8),
+        (8, 6),
+        (8, 9),
+    ]);
+    assert_eq!(vf2::subgraph_isomorphisms(&query, &data).count(), 18);
+
+    let matches = vf2::subgraph_isomorphisms(&query, &data).disjoint_matches();
+
+    assert_eq!(matches, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]]);
+}
+
+/// Tests that edge-disjoint matches may share data nodes
+/// but never a data edge.
+#[test]
+fn edge_disjoint_matches() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    // Two triangles sharing node 2.
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 2)]);
+
+    let node_disjoint = vf2::subgraph_isomorphisms(&query, &data).disjoint_matches();
+    let edge_disjoint = vf2::subgraph_isomorphisms(&query, &data).edge_disjoint_matches();
+
+    assert_eq!(node_disjoint, vec![vec![0, 1, 2]]);
+    assert_eq!(edge_disjoint, vec![vec![0, 1, 2], vec![2, 3, 4]]);
+
+    let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 0), (0, 2)]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data).edge_disjoint_matches(),
+        vec![vec![0, 1], vec![0, 2], vec![1, 0]]
+    );
+}
+
+/// Tests writing isomorphisms as JSON lines.
+#[cfg(feature = "json")]
+#[test]
+fn write_jsonl() {
+    let (query, data) = small_graphs::<Undirected>();
+    let mut buffer = Vec::new();
+
+    let count = vf2::subgraph_isomorphisms(&query, &data)
+        .write_jsonl(&mut buffer)
+        .unwrap();
+
+    let lines = String::from_utf8(buffer).unwrap();
+    let parsed = lines
+        .lines()
+        .map(|line| serde_json::from_str::<Vec<usize>>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(count, 36);
+    assert_eq!(parsed, vf2::subgraph_isomorphisms(&query, &data).vec());
+}
+
+/// Tests parsing graphs from DOT text and matching them.
+#[cfg(feature = "dot")]
+#[test]
+fn from_dot() {
+    let query = vf2::from_dot(
+        r#"
+        // A labeled path.
+        digraph query {
+            a [label="C"];
+            b [label="O"];
+            a -> b [label=double];
+        }
+        "#,
+    )
+    .unwrap();
+    let data = vf2::from_dot(
+        r#"
+        strict digraph {
+            node [shape=circle]
+            0 [label="C"]; 1 [label="O"]; 2 [label="C"]
+            2 -> 0 -> 1 [label=double, color=red]
+            2 -> 1 [label=single] /* Wrong edge label. */
+        }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data).default_eq().vec(),
+        vec![vec![0, 1]]
+    );
+
+    // Without a header, the edge operator sets the graph type.
+    let undirected = vf2::from_dot("x -- y -- z").unwrap();
+    assert!(!vf2::Graph::is_directed(&undirected));
+    assert_eq!(vf2::Graph::node_count(&undirected), 3);
+    assert_eq!(vf2::isomorphisms(&undirected, &undirected).count(), 2);
+
+    // Parallel edges keep their own labels.
+    let parallel = vf2::from_dot("digraph { a -> b [label=x]; a -> b [label=y] }").unwrap();
+    assert_eq!(vf2::Graph::edge_count(&parallel), 2);
+    assert_eq!(vf2::Graph::edge_multiplicity(&parallel, 0, 1), 2);
+
+    let error = vf2::from_dot("digraph {\n a -- b\n}").unwrap_err();
+    assert_eq!(error.line(), 2);
+    assert!(vf2::from_dot("graph { subgraph { a } }").is_err());
+    assert!(vf2::from_dot("graph { a [label=\"x] }").is_err());
+}
+
+/// Tests that resuming a checkpointed search
+/// yields exactly the remaining isomorphisms.
+#[test]
+fn checkpoint_resume() {
+    let (query, data) = small_graphs::<Undirected>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+    let mut found: Vec<_> = iter.by_ref().take(all.len() / 2).collect();
+
+    let snapshot = iter.checkpoint();
+    let remaining: Vec<_> = vf2::subgraph_isomorphisms(&query, &data)
+        .resume(snapshot.clone())
+        .unwrap()
+        .collect();
+
+    assert_eq!(remaining, iter.collect::<Vec<_>>());
+    found.extend(remaining);
+    assert_eq!(found, all);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &query)
+            .resume(snapshot)
+            .err(),
+        Some(vf2::Vf2Error::SnapshotMismatch)
+    );
+}
+
+/// Tests resuming a search from a checkpoint saved as JSON.
+#[cfg(feature = "serde")]
+#[test]
+fn checkpoint_serde() {
+    let (query, data) = small_graphs::<Directed>();
+    let all = vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+    let mut iter = vf2::induced_subgraph_isomorphisms(&query, &data).iter();
+    let mut found: Vec<_> = iter.by_ref().take(all.len() / 2).collect();
+
+    let json = serde_json::to_string(&iter.checkpoint()).unwrap();
+    let snapshot = serde_json::from_str(&json).unwrap();
+    found.extend(
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .resume(snapshot)
+            .unwrap(),
+    );
+
+    assert_eq!(found, all);
+}
+
+/// Tests that a checkpoint whose contents do not fit the search is rejected.
+#[cfg(feature = "serde")]
+#[test]
+fn checkpoint_serde_tampered() {
+    let (query, data) = small_graphs::<Directed>();
+    let mut iter = vf2::induced_subgraph_isomorphisms(&query, &data).iter();
+    iter.next();
+    let json = serde_json::to_value(iter.checkpoint()).unwrap();
+    let resume = |json: serde_json::Value| {
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .resume(serde_json::from_value(json).unwrap())
+            .err()
+    };
+    let tampered = |path: &str, value: serde_json::Value| {
+        let mut json = json.clone();
+        *json.pointer_mut(path).unwrap() = value;
+        resume(json)
+    };
+
+    assert_eq!(resume(json.clone()), None);
+    // A query node maps to a data node out of range.
+    assert_eq!(
+        tampered("/query/map/0", data.node_count().into()),
+        Some(vf2::Vf2Error::SnapshotMismatch)
+    );
+    // The data map disagrees with the query map.
+    assert_eq!(
+        tampered(
+            "/data/map",
+            json["data"]["map"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .rev()
+                .cloned()
+                .collect()
+        ),
+        Some(vf2::Vf2Error::SnapshotMismatch)
+    );
+    // A covered query node is missing from the node stack.
+    assert_eq!(
+        tampered(
+            "/query/node_stack/0",
+            json["query"]["node_stack"][1].clone()
+        ),
+        Some(vf2::Vf2Error::SnapshotMismatch)
+    );
+    // The terminal set size does not match the set.
+    let size = json["query"]["outgoing_size"].as_u64().unwrap();
+    assert_eq!(
+        tampered("/query/outgoing_size", (size + 1).into()),
+        Some(vf2::Vf2Error::SnapshotMismatch)
+    );
+}
+
+/// Tests getting an iterator of isomorphisms.
+#[test]
+fn iter() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    assert!(iter.next().is_some());
+}
+
+/// Tests getting a reference to the next isomorphism.
+#[test]
+fn iter_next_ref() {
+    let (query, data) = small_graphs::<Directed>();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    let next_ref = iter.next_ref();
+
+    assert_eq!(next_ref, Some(&vec![0, 1, 3, 4, 5]));
+}
+
+/// Tests converting the iterator into the next isomorphism.
+#[test]
+fn iter_into_next() {
+    let (query, data) = small_graphs::<Directed>();
+    let iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    let next = iter.into_next();
+
+    assert_eq!(next, Some(vec![0, 1, 3, 4, 5]));
+}
+
+/// Tests that partial maps are reported when the search backtracks from leaves.
+#[test]
+fn on_partial() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (2, 3)]);
+    let mut partials = Vec::new();
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|map, depth| partials.push((map.to_vec(), depth)))
+        .vec();
+
+    // Query node 1 has an outgoing edge, but data nodes 1 and 3 do not,
+    // so those pairs are pruned by degree and never reported.
+    assert!(isomorphisms.is_empty());
+    assert_eq!(
+        partials,
+        vec![
+            (vec![0, usize::MAX, usize::MAX], 1),
+            (vec![2, usize::MAX, usize::MAX], 1),
+        ]
+    );
+}
+
+/// Tests that partial maps are reported at the depth reached
+/// when every pair passes the degree check.
+#[test]
+fn on_partial_cycle() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let mut partials = Vec::new();
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|map, depth| partials.push((map.to_vec(), depth)))
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert!(partials.contains(&(vec![0, 1, usize::MAX], 2)));
+    assert!(partials
+        .iter()
+        .all(|(map, depth)| { map.iter().filter(|&&node| node != usize::MAX).count() == *depth }));
+    assert_eq!(partials.iter().map(|&(_, depth)| depth).max(), Some(2));
+}
+
+/// Tests that the partial map callback may capture values
+/// that cannot be sent to another thread.
+#[test]
+fn on_partial_not_send() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let depths = Rc::new(RefCell::new(Vec::new()));
+
+    let shared = Rc::clone(&depths);
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(move |_, depth| shared.borrow_mut().push(depth))
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert!(!depths.borrow().is_empty());
+}
+
+/// Tests that a query with more edges than the data graph
+/// yields no isomorphisms without searching.
+#[test]
+fn more_query_edges_than_data() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 0), (1, 2), (2, 1), (0, 2), (2, 0)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4)]);
+    let mut partials = 0;
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|_, _| partials += 1)
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert_eq!(partials, 0);
+}
+
+/// Tests that data nodes with too few neighbors
+/// are pruned before the search descends.
+#[test]
+fn degree_pruning() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (0, 2)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (2, 3), (2, 4)]);
+    let mut partials = 0;
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|_, _| partials += 1)
+        .vec();
+
+    assert_eq!(isomorphisms, vec![vec![2, 3, 4], vec![2, 4, 3]]);
+    assert_eq!(partials, 0);
+}
+
+/// Tests that a label index finds the same isomorphisms
+/// without trying pairs with different labels.
+#[test]
+fn label_index() {
+    let (query, data) = small_labeled_graphs::<Undirected>();
+    let mismatched = std::sync::atomic::AtomicUsize::new(0);
+
+    let isomorphisms = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .node_eq(|left, right| {
+            if left != right {
+                mismatched.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            left == right
+        })
+        .edge_eq(|left, right| left == right)
+        .label_index()
+        .vec();
+
+    assert_eq!(
+        isomorphisms,
+        vf2::induced_subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .vec()
+    );
+    assert_eq!(mismatched.into_inner(), 0);
+}
+
+/// Tests that feasibility sets find the same isomorphisms
+/// as the label index while trying fewer pairs.
+#[cfg(feature = "approx")]
+#[test]
+fn feasibility_sets() {
+    // A star whose center only fits the one data node with enough neighbors.
+    let mut query = DiGraph::<char, ()>::new();
+    let q = ['a', 'b', 'b', 'b'].map(|label| query.add_node(label));
+    query.extend_with_edges([(q[0], q[1]), (q[0], q[2]), (q[0], q[3])]);
+    let mut data = DiGraph::<char, ()>::new();
+    let d = ['a', 'b', 'a', 'b', 'a', 'b', 'b', 'b'].map(|label| data.add_node(label));
+    data.extend_with_edges([
+        (d[0], d[1]),
+        (d[2], d[3]),
+        (d[4], d[5]),
+        (d[4], d[6]),
+        (d[4], d[7]),
+    ]);
+    let tried = [Cell::new(0), Cell::new(0)];
+    let search = |index: usize| {
+        let tried = &tried[index];
+        let builder = vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .node_eq_abort(move |_, _| {
+                tried.set(tried.get() + 1);
+                NodeMatch::Match
+            });
+        if index == 0 {
+            builder.label_index().vec()
+        } else {
+            builder.feasibility_sets().vec()
+        }
+    };
+
+    let indexed = search(0);
+    let feasible = search(1);
+
+    assert_eq!(feasible.len(), 6);
+    assert_eq!(feasible, indexed);
+    let [indexed_tried, feasible_tried] = tried.map(Cell::into_inner);
+    assert!(feasible_tried < indexed_tried);
+}
+
+/// Tests that the search is skipped when the data graph
+/// has fewer nodes with some label than the query graph.
+#[test]
+fn label_multiset() {
+    let mut query = UnGraph::<Color, ()>::new_undirected();
+    let q = [Color::Black, Color::Black, Color::White].map(|label| query.add_node(label));
+    for (source, target) in [(0, 2), (1, 2)] {
+        query.add_edge(q[source], q[target], ());
+    }
+    let mut data = UnGraph::<Color, ()>::new_undirected();
+    let d =
+        [Color::Black, Color::White, Color::White, Color::White].map(|label| data.add_node(label));
+    for (source, target) in [(0, 1), (1, 2), (2, 3), (3, 0)] {
+        data.add_edge(d[source], d[target], ());
+    }
+    let tried = Cell::new(0);
+    let count_tried = |_: &Color, _: &Color| {
+        tried.set(tried.get() + 1);
+        NodeMatch::Match
+    };
+
+    let indexed = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .node_eq_abort(count_tried)
+        .label_index()
+        .count();
+    let wildcard = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .label_index()
+        .wildcard_query_nodes(&[1])
+        .unwrap()
+        .count();
+
+    assert_eq!(indexed, 0);
+    assert_eq!(tried.into_inner(), 0);
+    assert_eq!(wildcard, 2);
+}
+
+/// Tests that restricting the candidates of a query node
+/// prunes the isomorphisms and the pairs tried.
+#[test]
+fn candidates() {
+    let (query, data) = small_graphs::<Undirected>();
+    let tried = std::sync::atomic::AtomicUsize::new(0);
+    let count_tried = |_: &(), _: &()| {
+        tried.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        true
+    };
+
+    let all = vf2::subgraph_isomorphisms(&query, &data)
+        .node_eq(count_tried)
+        .vec();
+    let all_tried = tried.swap(0, std::sync::atomic::Ordering::Relaxed);
+    let restricted = vf2::subgraph_isomorphisms(&query, &data)
+        .node_eq(count_tried)
+        .candidates(0, vec![1, 0])
+        .unwrap()
+        .vec();
+    let restricted_tried = tried.into_inner();
+
+    let expected: Vec<_> = all
+        .into_iter()
+        .filter(|isomorphism| isomorphism[0] <= 1)
+        .collect();
+    assert_eq!(restricted, expected);
+    assert!(restricted_tried < all_tried);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .candidates(0, vec![8])
+            .err(),
+        Some(vf2::Vf2Error::DataNodeOutOfRange(8))
+    );
+}
+
+/// Tests that isomorphisms as maps have the same entries
+/// as the positional vectors.
+#[test]
+fn vec_maps() {
+    let (query, data) = small_graphs::<Directed>();
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let maps = vf2::subgraph_isomorphisms(&query, &data).vec_maps();
+
+    assert_eq!(maps.len(), isomorphisms.len());
+    for (map, isomorphism) in maps.iter().zip(&isomorphisms) {
+        assert_eq!(map.len(), query.node_count());
+        for (query_node, data_node) in isomorphism.iter().enumerate() {
+            assert_eq!(map[&query_node], *data_node);
+        }
+    }
+}
+
+/// Tests that the remaining upper bound is never below
+/// the number of isomorphisms left to yield.
+#[test]
+fn remaining_upper_bound() {
+    let (query, data) = small_graphs::<Undirected>();
+    let builders = [
+        vf2::subgraph_isomorphisms(&query, &data),
+        vf2::induced_subgraph_isomorphisms(&query, &data),
+        vf2::subgraph_isomorphisms(&query, &data).prefer_dense(),
+        vf2::subgraph_isomorphisms(&query, &data)
+            .must_include_data_node(4)
+            .unwrap(),
+    ];
+    for builder in builders {
+        let total = builder.clone().count();
+        let mut iter = builder.iter();
+        for yielded in 0..=total {
+            assert!(iter.remaining_upper_bound() >= total - yielded);
+            iter.next_ref();
+        }
+        assert_eq!(iter.remaining_upper_bound(), 0);
+    }
+}
+
+/// Tests that a custom query order finds the same isomorphisms.
+#[test]
+fn query_order() {
+    let (directed_query, directed_data) = small_graphs::<Directed>();
+    let (undirected_query, undirected_data) = small_graphs::<Undirected>();
+    for (query, data) in [
+        (
+            vf2::CsrGraph::from(&directed_query),
+            vf2::CsrGraph::from(&directed_data),
+        ),
+        (
+            vf2::CsrGraph::from(&undirected_query),
+            vf2::CsrGraph::from(&undirected_data),
+        ),
+    ] {
+        let mut isomorphisms = vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+        let mut reordered = vf2::induced_subgraph_isomorphisms(&query, &data)
+            .query_order(vec![4, 1, 3, 0, 2])
+            .unwrap()
+            .vec();
+        isomorphisms.sort();
+        reordered.sort();
+
+        assert!(!isomorphisms.is_empty());
+        assert_eq!(reordered, isomorphisms);
+    }
+
+    let (query, data) = small_graphs::<Directed>();
+    for order in [vec![0, 1, 2, 3], vec![0, 1, 2, 3, 3], vec![0, 1, 2, 3, 5]] {
+        assert_eq!(
+            vf2::subgraph_isomorphisms(&query, &data)
+                .query_order(order)
+                .err(),
+            Some(vf2::Vf2Error::InvalidQueryOrder)
+        );
+    }
+}
+
+/// Tests choosing boxed equality functions at runtime.
+#[test]
+fn eq_dyn() {
+    type Labeled = Graph<Color, Color, Undirected>;
+    let (query, data) = small_labeled_graphs::<Undirected>();
+    let mut counts = Vec::new();
+
+    for strict in [true, false] {
+        let node_eq: vf2::DynNodeEq<Labeled, Labeled> = if strict {
+            Box::new(|left, right| left == right)
+        } else {
+            Box::new(|_, _| true)
+        };
+        let edge_eq: vf2::DynEdgeEq<Labeled, Labeled> = Box::new(|left, right| left == right);
+        counts.push(
+            vf2::subgraph_isomorphisms(&query, &data)
+                .node_eq_dyn(node_eq)
+                .edge_eq_dyn(edge_eq)
+                .count(),
+        );
+    }
+
+    assert_eq!(
+        counts,
+        vec![
+            vf2::subgraph_isomorphisms(&query, &data)
+                .default_eq()
+                .count(),
+            vf2::subgraph_isomorphisms(&query, &data)
+                .edge_eq(|left, right| left == right)
+                .count(),
+        ]
+    );
+    assert!(counts[0] < counts[1]);
+}
+
+/// Tests that each query edge maps to the data edge
+/// between the nodes its ends map to.
+#[test]
+fn iter_with_edges() {
+    let (query, data) = small_graphs::<Directed>();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter_with_edges();
+    let query_edges = iter.query_edges().to_vec();
+    assert_eq!(query_edges.len(), query.edge_count());
+
+    let isomorphisms: Vec<_> = iter.by_ref().collect();
+
+    assert_eq!(
+        isomorphisms
+            .iter()
+            .map(|isomorphism| isomorphism.nodes.clone())
+            .collect::<Vec<_>>(),
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+    for isomorphism in isomorphisms {
+        for (&(source, target), &(data_source, data_target)) in
+            query_edges.iter().zip(&isomorphism.edges)
+        {
+            assert_eq!(data_source, isomorphism.nodes[source]);
+            assert_eq!(data_target, isomorphism.nodes[target]);
+            assert!(data.contains_edge(NodeIndex::new(data_source), NodeIndex::new(data_target)));
+        }
+    }
+    assert!(iter.next_ref().is_none());
+}
+
+/// Tests counting the data edges among the nodes of each isomorphism.
+#[test]
+fn iter_with_edge_density() {
+    let (query, data) = small_graphs::<Directed>();
+    let counts: Vec<_> = vf2::subgraph_isomorphisms(&query, &data)
+        .iter_with_edge_density()
+        .collect();
+    // Data edge 1 -> 2 is not covered by the query but is among the nodes.
+    assert_eq!(
+        counts,
+        vec![
+            (vec![0, 1, 3, 4, 5], 4),
+            (vec![0, 2, 3, 4, 5], 4),
+            (vec![1, 0, 3, 4, 5], 4),
+            (vec![1, 2, 3, 4, 5], 5),
+            (vec![2, 0, 3, 4, 5], 4),
+            (vec![2, 1, 3, 4, 5], 5),
+        ]
+    );
+
+    let (query, data) = small_graphs::<Undirected>();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter_with_edge_density();
+    assert_eq!(iter.next_ref(), Some((&vec![0, 1, 3, 4, 5], 4)));
+    assert_eq!(iter.next_ref(), Some((&vec![0, 1, 3, 6, 7], 4)));
+}
+
+/// Tests that a symmetric edge equality function matches
+/// undirected edges whose labels are read from the other end.
+#[test]
+fn edge_eq_symmetric() {
+    let mut query = UnGraph::<(), (char, char)>::new_undirected();
+    let mut data = UnGraph::<(), (char, char)>::new_undirected();
+    let q = [query.add_node(()), query.add_node(())];
+    let d = [data.add_node(()), data.add_node(()), data.add_node(())];
+    query.add_edge(q[0], q[1], ('a', 'b'));
+    data.add_edge(d[0], d[1], ('b', 'a'));
+    data.add_edge(d[1], d[2], ('c', 'a'));
+
+    let plain = vf2::subgraph_isomorphisms(&query, &data)
+        .edge_eq(|left, right| left == right)
+        .vec();
+    let symmetric = vf2::subgraph_isomorphisms(&query, &data)
+        .edge_eq_symmetric(|left, right, reversed| {
+            let left = if reversed { (left.1, left.0) } else { *left };
+            left == *right
+        })
+        .vec();
+
+    assert!(plain.is_empty());
+    assert_eq!(symmetric, vec![vec![0, 1], vec![1, 0]]);
+}
+
+/// Tests returning an iterator from the function that created the graphs.
+#[test]
+fn owned() {
+    fn isomorphisms() -> impl Iterator<Item = vf2::Isomorphism> {
+        let (query, data) = small_graphs::<Directed>();
+        vf2::Vf2Builder::owned(
+            vf2::Problem::SubgraphIsomorphism,
+            std::sync::Arc::new(query),
+            std::sync::Arc::new(data),
+        )
+        .iter()
+    }
+    let (query, data) = small_graphs::<Directed>();
+
+    let isomorphisms: Vec<_> = isomorphisms().collect();
+
+    assert_eq!(
+        isomorphisms,
+        vf2::subgraph_isomorphisms(&query, &data).vec()
+    );
+}
+
+/// Tests that a query whose degrees cannot fit in the data graph
+/// yields no isomorphisms without searching.
+#[test]
+fn degree_sequence_incompatible() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (2, 4)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)]);
+    let mut partials = 0;
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|_, _| partials += 1)
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert_eq!(partials, 0);
+
+    let query = DiGraph::<(), ()>::from_edges([(0, 2), (1, 2)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let mut partials = 0;
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .on_partial(|_, _| partials += 1)
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert_eq!(partials, 0);
+}
+
+/// Tests that graphs with the same edge count
+/// but different degree sequences are not isomorphic.
+#[test]
+fn degree_sequence_not_isomorphic() {
+    let left = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+    let right = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let mut partials = 0;
+
+    let isomorphisms = vf2::isomorphisms(&left, &right)
+        .on_partial(|_, _| partials += 1)
+        .vec();
+
+    assert!(isomorphisms.is_empty());
+    assert_eq!(partials, 0);
+}
+
+/// Tests that undirected edge labels that depend on the orientation
+/// are compared in matching orientations.
+#[test]
+fn undirected_edge_label_orientation() {
+    let query = AdjListGraph::new(false, 2, &[(0, 1)])
+        .with_edge_label(0, 1, 'x')
+        .with_edge_label(1, 0, 'y');
+    let data = AdjListGraph::new(false, 3, &[(0, 1), (1, 2)])
+        .with_edge_label(0, 1, 'x')
+        .with_edge_label(1, 0, 'y');
+
+    let isomorphisms = vf2::subgraph_isomorphisms(&query, &data)
+        .edge_eq(|left, right| left == right)
+        .vec();
+
+    assert_eq!(isomorphisms, vec![vec![0, 1]]);
+}
+
+/// Tests that graphs default to contiguous node indices.
+#[test]
+fn node_indices() {
+    let graph = AdjListGraph::new(true, 3, &[(0, 1)]);
+
+    assert_eq!(
+        vf2::Graph::node_indices(&graph).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+}
+
+/// Tests that progress increases to one as root branches are finished.
+#[test]
+fn on_progress() {
+    let (query, data) = small_graphs::<Directed>();
+    let mut progress = Vec::new();
+
+    vf2::subgraph_isomorphisms(&query, &data)
+        .on_progress(|fraction| progress.push(fraction))
+        .count();
+
+    assert_eq!(progress.len(), data.node_count());
+    assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(progress.last(), Some(&1.0));
+}
+
+/// Tests that the progress and partial map callbacks may both be set
+/// and capture values that cannot be sent to another thread.
+#[test]
+fn on_progress_not_send() {
+    let (query, data) = small_graphs::<Directed>();
+    let progress = Rc::new(Cell::new(0.0));
+    let partials = Rc::new(Cell::new(0));
+
+    let (shared_progress, shared_partials) = (Rc::clone(&progress), Rc::clone(&partials));
+    vf2::subgraph_isomorphisms(&query, &data)
+        .on_progress(move |fraction| shared_progress.set(fraction))
+        .on_partial(move |_, _| shared_partials.set(shared_partials.get() + 1))
+        .count();
+
+    assert_eq!(progress.get(), 1.0);
+    assert!(partials.get() > 0);
+}
+
+/// Tests that wildcard query nodes match any data node
+/// while other query nodes must match labels.
+#[test]
+fn wildcard_query_nodes() {
+    let mut query = DiGraph::<char, ()>::new();
+    let q = ['a', 'b', 'a'].map(|label| query.add_node(label));
+    query.extend_with_edges([(q[0], q[1]), (q[1], q[2])]);
+    let mut data = DiGraph::<char, ()>::new();
+    let d = ['a', 'c', 'a', 'a', 'b', 'a'].map(|label| data.add_node(label));
+    data.extend_with_edges([(d[0], d[1]), (d[1], d[2]), (d[3], d[4]), (d[4], d[5])]);
+
+    let strict = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+    let wildcard = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .wildcard_query_nodes(&[1])
+        .unwrap()
+        .vec();
+    let indexed = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .wildcard_query_nodes(&[1])
+        .unwrap()
+        .label_index()
+        .vec();
+
+    assert_eq!(strict, vec![vec![3, 4, 5]]);
+    assert_eq!(wildcard, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+    assert_eq!(indexed, wildcard);
+}
+
+/// Tests that a triangle maps onto an edge with a self-loop
+/// when one query node may be merged.
+#[test]
+fn allow_merge() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (0, 0)]);
+
+    let strict = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let merged = vf2::subgraph_isomorphisms(&query, &data)
+        .allow_merge(&[2])
+        .unwrap()
+        .vec();
+    let induced = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .allow_merge(&[2])
+        .unwrap()
+        .vec();
+
+    assert!(strict.is_empty());
+    assert_eq!(merged, vec![vec![0, 1, 0], vec![1, 0, 0]]);
+    assert!(induced.is_empty());
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .allow_merge(&[3])
+            .err(),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(3))
+    );
+}
+
+/// Tests that merging does not depend on whether the mergeable query node
+/// is mapped before or after the node it shares a data node with.
+#[test]
+fn allow_merge_any_order() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (0, 0)]);
+
+    let middle = vf2::subgraph_isomorphisms(&query, &data)
+        .allow_merge(&[1])
+        .unwrap()
+        .vec();
+    let first = vf2::subgraph_isomorphisms(&query, &data)
+        .allow_merge(&[0])
+        .unwrap()
+        .vec();
+
+    assert_eq!(middle, vec![vec![0, 0, 1], vec![1, 0, 0]]);
+    assert_eq!(first, vec![vec![0, 0, 1], vec![0, 1, 0]]);
+}
+
+/// Tests that a triangle maps onto a path when one query edge is optional,
+/// and that the optional edge label is compared when the data edge exists.
+#[test]
+fn optional_query_edges() {
+    let query = UnGraph::<(), i32>::from_edges([(0, 1, 1), (1, 2, 1), (2, 0, 1)]);
+    let data =
+        UnGraph::<(), i32>::from_edges([(0, 1, 1), (1, 2, 1), (3, 4, 1), (4, 5, 1), (5, 3, 2)]);
+
+    let strict = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+    let optional = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .optional_query_edges(&[(0, 2)])
+        .unwrap()
+        .vec();
+
+    assert!(strict.is_empty());
+    assert_eq!(optional, vec![vec![0, 1, 2], vec![2, 1, 0]]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .optional_query_edges(&[(0, 3)])
+            .err(),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(3))
+    );
+}
+
+/// Tests that a query node reached only through an optional edge
+/// may map to a data node that is not adjacent to the partial map.
+#[test]
+fn optional_query_edges_disconnected() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let mut data = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    data.add_node(());
+
+    let optional = vf2::subgraph_isomorphisms(&query, &data)
+        .optional_query_edges(&[(1, 2)])
+        .unwrap()
+        .vec();
+    let ordered = vf2::subgraph_isomorphisms(&query, &data)
+        .optional_query_edges(&[(1, 2)])
+        .unwrap()
+        .query_order(vec![0, 1, 2])
+        .unwrap()
+        .vec();
+
+    assert_eq!(optional, vec![vec![0, 1, 2], vec![1, 0, 2]]);
+    assert_eq!(ordered, optional);
+}
+
+/// Tests that parallel edges are counted, so a double edge
+/// only maps to a double edge and an induced single edge
+/// only maps to a single edge.
+#[test]
+fn edge_multiplicity() {
+    let double_first = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1), (1, 2)]);
+    let double_last = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 2)]);
+    let single = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    let double = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1)]);
+    let directed_double = DiGraph::<(), ()>::from_edges([(0, 1), (0, 1)]);
+    let directed_path = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 2)]);
+
+    assert_eq!(
+        vf2::isomorphisms(&double_first, &double_last).vec(),
+        vec![vec![2, 1, 0]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&double, &double_last).vec(),
+        vec![vec![1, 2], vec![2, 1]]
+    );
+    assert_eq!(vf2::subgraph_isomorphisms(&single, &double_last).count(), 4);
+    assert_eq!(
+        vf2::induced_subgraph_isomorphisms(&single, &double_last).vec(),
+        vec![vec![0, 1], vec![1, 0]]
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&directed_double, &directed_path).vec(),
+        vec![vec![1, 2]]
+    );
+}
+
+/// Tests that the labels of parallel edges are matched one to one,
+/// so a query edge maps to whichever parallel data edge has its label.
+#[test]
+fn parallel_edge_labels() {
+    let mut data = DiGraph::<(), char>::new();
+    let (a, b) = (data.add_node(()), data.add_node(()));
+    data.add_edge(a, b, 'a');
+    data.add_edge(a, b, 'b');
+    let query = |labels: &[char]| {
+        let mut query = DiGraph::<(), char>::new();
+        let (a, b) = (query.add_node(()), query.add_node(()));
+        for &label in labels {
+            query.add_edge(a, b, label);
+        }
+        query
+    };
+
+    let single = query(&['b']);
+    let twice = query(&['b', 'b']);
+    let both = query(&['b', 'a']);
+    let missing = query(&['c']);
+
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&single, &data)
+            .default_eq()
+            .vec(),
+        vec![vec![0, 1]]
+    );
+    assert_eq!(
+        vf2::isomorphisms(&both, &data).default_eq().vec(),
+        vec![vec![0, 1]]
+    );
+    assert!(vf2::isomorphisms(&twice, &data)
+        .default_eq()
+        .vec()
+        .is_empty());
+    assert!(vf2::subgraph_isomorphisms(&missing, &data)
+        .default_eq()
+        .vec()
+        .is_empty());
+}
+
+/// Tests that the automorphisms of a star permute its leaves
+/// in every order and keep the center fixed.
+#[test]
+fn automorphisms() {
+    let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (0, 4)]);
+    let empty = UnGraph::<(), ()>::new_undirected();
+
+    let automorphisms = vf2::automorphisms(&star);
+
+    assert_eq!(automorphisms.len(), 24);
+    assert!(automorphisms.contains(&vec![0, 1, 2, 3, 4]));
+    assert!(automorphisms.iter().all(|mapping| mapping[0] == 0));
+    assert_eq!(automorphisms, vf2::isomorphisms(&star, &star).vec());
+    assert_eq!(vf2::automorphisms(&empty), vec![Vec::<usize>::new()]);
+}
+
+/// Tests that sending isomorphisms into a channel from another thread
+/// delivers the same isomorphisms as collecting them.
+#[test]
+fn send_to() {
+    let (query, data) = small_graphs::<Directed>();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let search = {
+        let (query, data) = (query.clone(), data.clone());
+        std::thread::spawn(move || vf2::subgraph_isomorphisms(&query, &data).send_to(sender))
+    };
+    let received: Vec<_> = receiver.iter().collect();
+    let sent = search.join().unwrap();
+
+    assert_eq!(sent, received.len());
+    assert_eq!(received, vf2::subgraph_isomorphisms(&query, &data).vec());
+}
+
+/// Tests that graphs with a node index equal to
+/// a reserved value return an error instead of searching.
+#[test]
+fn graph_too_large() {
+    let small = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let huge = HugeGraph(usize::MAX);
+
+    let data_error = vf2::subgraph_isomorphisms(&small, &huge).try_iter().err();
+    let query_error = vf2::subgraph_isomorphisms(&huge, &small).try_iter().err();
+
+    assert_eq!(data_error, Some(vf2::Vf2Error::GraphTooLarge));
+    assert_eq!(query_error, Some(vf2::Vf2Error::GraphTooLarge));
+    assert!(vf2::subgraph_isomorphisms(&small, &small)
+        .try_iter()
+        .is_ok());
+}
+
+/// Tests that a directed and an undirected graph
+/// return an error instead of searching.
+#[test]
+fn direction_mismatch() {
+    let directed = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let undirected = UnGraph::<(), ()>::from_edges([(0, 1)]);
+
+    let data_error = vf2::subgraph_isomorphisms(&directed, &undirected)
+        .try_iter()
+        .err();
+    let query_error = vf2::subgraph_isomorphisms(&undirected, &directed)
+        .try_iter()
+        .err();
+
+    assert_eq!(data_error, Some(vf2::Vf2Error::DirectionMismatch));
+    assert_eq!(query_error, Some(vf2::Vf2Error::DirectionMismatch));
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&directed, &undirected)
+            .ignore_direction()
+            .vec(),
+        vec![vec![0, 1], vec![1, 0]]
+    );
+}
+
+/// Tests that searching a directed and an undirected graph panics.
+#[test]
+#[should_panic(expected = "one graph is directed and the other is undirected")]
+fn direction_mismatch_panics() {
+    let directed = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let undirected = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    vf2::subgraph_isomorphisms(&directed, &undirected).vec();
+}
+
+/// Tests that searching a graph that is too large panics.
+#[test]
+#[should_panic]
+fn graph_too_large_panics() {
+    let small = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let huge = HugeGraph(usize::MAX);
+
+    // Should panic since the data graph uses reserved values.
+    vf2::subgraph_isomorphisms(&small, &huge).count();
+}
+
+/// Tests the search state info at an isomorphism.
+#[test]
+fn current_state_info() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 0)]);
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    assert_eq!(iter.next_ref(), Some(&vec![0, 1]));
+    let info = iter.current_state_info();
+
+    assert_eq!(info.depth, 2);
+    assert_eq!(
+        info.query,
+        vf2::TerminalSetSizes {
+            outgoing: 0,
+            incoming: 0
+        }
+    );
+    assert_eq!(
+        info.data,
+        vf2::TerminalSetSizes {
+            outgoing: 1,
+            incoming: 1
+        }
+    );
+}
+
+/// Tests that induced edge slack admits isomorphisms
+/// with a few extra data edges.
+#[test]
+fn induced_edge_slack() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+    let data = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0), (4, 0)]);
+
+    let strict = vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+    let mut slack = vf2::induced_subgraph_isomorphisms(&query, &data)
+        .induced_edge_slack(1)
+        .vec();
+    let mut subgraph = vf2::subgraph_isomorphisms(&query, &data).vec();
+
+    // Paths within the 4-cycle have one extra data edge.
+    assert!(strict.iter().all(|isomorphism| isomorphism.contains(&4)));
+    assert!(strict.len() < slack.len());
+    slack.sort();
+    subgraph.sort();
+    assert_eq!(slack, subgraph);
+}
+
+/// Tests that extra edge slack lets a graph isomorphism
+/// map a path onto a triangle.
+#[test]
+fn induced_edge_slack_isomorphisms() {
+    let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+
+    let strict = vf2::isomorphisms(&path, &triangle).vec();
+    let slack = vf2::isomorphisms(&path, &triangle)
+        .induced_edge_slack(1)
+        .vec();
+
+    assert!(strict.is_empty());
+    assert_eq!(slack.len(), 6);
+}
+
+/// Tests that the terminal set preference changes the search order
+/// but not the isomorphisms found.
+#[test]
+fn terminal_preference() {
+    let (query, data) = small_labeled_graphs::<Directed>();
+    let mut outgoing = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+    let incoming_builder = vf2::subgraph_isomorphisms(&query, &data)
+        .default_eq()
+        .terminal_preference(SourcePreference::Incoming);
+    let order = incoming_builder.computed_order();
+    let mut incoming = incoming_builder.vec();
+
+    // Node 0 has only a successor, node 2 has both kinds of neighbors.
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data).computed_order(),
+        vec![0, 2, 3, 4, 1]
+    );
+    assert_eq!(order, vec![0, 2, 1, 3, 4]);
+    assert!(!outgoing.is_empty());
+    outgoing.sort();
+    incoming.sort();
+    assert_eq!(outgoing, incoming);
+
+    let (query, data) = small_graphs::<Directed>();
+    let ordered = vf2::subgraph_isomorphisms(&query, &data)
+        .query_order(order)
+        .unwrap()
+        .vec();
+    assert_eq!(
+        ordered,
+        vf2::subgraph_isomorphisms(&query, &data)
+            .terminal_preference(SourcePreference::Incoming)
+            .vec()
+    );
+}
+
+/// Tests that the data map is the inverse of the query map.
+#[test]
+fn next_ref_data_map() {
+    let (query, data) = small_graphs::<Directed>();
+    let isomorphisms = vf2::induced_subgraph_isomorphisms(&query, &data).vec();
+    let mut iter = vf2::induced_subgraph_isomorphisms(&query, &data).iter();
+
+    let mut count = 0;
+    while let Some(data_map) = iter.next_ref_data_map() {
+        let isomorphism = &isomorphisms[count];
+        assert_eq!(data_map.len(), data.node_count());
+        for (data_node, &query_node) in data_map.iter().enumerate() {
+            if query_node == usize::MAX {
+                assert!(!isomorphism.contains(&data_node));
+            } else {
+                assert_eq!(isomorphism[query_node], data_node);
+            }
+        }
+        count += 1;
+    }
+    assert!(count > 0);
+    assert_eq!(count, isomorphisms.len());
+}
+
+/// Tests matching edges in either orientation.
+#[test]
+fn ignore_direction() {
+    let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let cyclic = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+    let transitive = DiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 2)]);
+    let both_ways = DiGraph::<(), ()>::from_edges([(0, 1), (1, 0), (1, 2), (2, 0)]);
+
+    for data in [&cyclic, &transitive, &both_ways] {
+        assert_eq!(
+            vf2::isomorphisms(&triangle, data)
+                .ignore_direction()
+                .count(),
+            6
+        );
+        assert_eq!(
+            vf2::induced_subgraph_isomorphisms(&triangle, data)
+                .ignore_direction()
+                .count(),
+            6
+        );
+    }
+    assert_eq!(
+        vf2::isomorphisms(&cyclic, &transitive)
+            .ignore_direction()
+            .count(),
+        6
+    );
+    assert_eq!(vf2::isomorphisms(&cyclic, &transitive).count(), 0);
+
+    // Edge labels are read in either orientation.
+    let query = DiGraph::<(), char>::from_edges([(0, 1, 'a'), (1, 2, 'b')]);
+    let data = DiGraph::<(), char>::from_edges([(1, 0, 'a'), (2, 1, 'b'), (2, 3, 'a')]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .ignore_direction()
+            .vec(),
+        vec![vec![0, 1, 2], vec![3, 2, 1]]
+    );
+}
+
+/// Tests that precomputed node and edge equalities give the same results
+/// with each equality function called once per pair.
+#[test]
+fn precompute_compat() {
+    fn check<D: EdgeType>() {
+        let (query, data) = small_labeled_graphs::<D>();
+        let expected = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+
+        let node_calls = Cell::new(0);
+        let edge_calls = Cell::new(0);
+        let precomputed = vf2::subgraph_isomorphisms(&query, &data)
+            .node_eq(|left, right| {
+                node_calls.set(node_calls.get() + 1);
+                left == right
+            })
+            .edge_eq(|left, right| {
+                edge_calls.set(edge_calls.get() + 1);
+                left == right
+            })
+            .precompute_node_compat()
+            .precompute_edge_compat()
+            .vec();
+
+        // Undirected edges are compared in both orientations.
+        let orientations = if D::is_directed() { 1 } else { 4 };
+        assert!(!expected.is_empty());
+        assert_eq!(precomputed, expected);
+        assert_eq!(node_calls.get(), query.node_count() * data.node_count());
+        assert_eq!(
+            edge_calls.get(),
+            query.edge_count() * data.edge_count() * orientations
+        );
+    }
+    check::<Directed>();
+    check::<Undirected>();
+}
+
+/// Tests that isomorphisms are numbered in the order they are yielded.
+#[test]
+fn next_ref_with_id() {
+    let (query, data) = small_graphs::<Undirected>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).vec();
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data).iter();
+
+    assert_eq!(iter.next(), Some(all[0].clone()));
+    let mut ids = vec![0];
+    while let Some((id, isomorphism)) = iter.next_ref_with_id() {
+        assert_eq!(isomorphism, &all[id]);
+        ids.push(id);
+    }
+    assert_eq!(ids, (0..all.len()).collect::<Vec<_>>());
+    assert_eq!(iter.next_ref_with_id(), None);
+}
+
+/// Tests keeping only the best scored isomorphisms.
+#[test]
+fn top_k_by() {
+    let (query, data) = small_graphs::<Undirected>();
+    let score = |isomorphism: &Vec<usize>| isomorphism.iter().sum::<usize>() as f64;
+    let mut expected: Vec<_> = vf2::subgraph_isomorphisms(&query, &data)
+        .vec()
+        .into_iter()
+        .map(|isomorphism| (score(&isomorphism), isomorphism))
+        .collect();
+    // A stable sort keeps equal scores in the order they were found.
+    expected.sort_by(|left, right| right.0.total_cmp(&left.0));
+
+    let top = vf2::subgraph_isomorphisms(&query, &data).top_k_by(2, score);
+    assert_eq!(top, expected[..2]);
+    assert!(top[0].0 >= top[1].0);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data).top_k_by(usize::MAX, score),
+        expected
+    );
+    assert!(vf2::subgraph_isomorphisms(&query, &data)
+        .top_k_by(0, score)
+        .is_empty());
+}
+
+/// Tests matching the subgraph induced by some query nodes.
+#[test]
+fn restrict_query() {
+    let query = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+    let sliced = DiGraph::<(), ()>::from_edges([(1, 2), (2, 0)]);
+    let data = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+
+    let restricted = vf2::subgraph_isomorphisms(&query, &data)
+        .restrict_query(&[3, 1, 2])
+        .unwrap()
+        .vec();
+    assert_eq!(restricted, vec![vec![2, 0, 1], vec![3, 1, 2]]);
+    assert_eq!(restricted, vf2::subgraph_isomorphisms(&sliced, &data).vec());
+
+    // Options set before the restriction follow the chosen nodes.
+    let anchored = vf2::subgraph_isomorphisms(&query, &data)
+        .candidates(3, vec![3])
+        .unwrap()
+        .restrict_query(&[3, 1, 2])
+        .unwrap()
+        .vec();
+    assert_eq!(anchored, vec![vec![3, 1, 2]]);
+
+    // The label index follows the chosen nodes too.
+    let labeled_query = query.map(|node, _| node.index() % 2, |_, _| ());
+    let labeled_data = data.map(|node, _| node.index() % 2, |_, _| ());
+    let indexed = vf2::subgraph_isomorphisms(&labeled_query, &labeled_data)
+        .default_eq()
+        .label_index()
+        .restrict_query(&[3, 1, 2])
+        .unwrap()
+        .vec();
+    assert_eq!(indexed, vec![vec![3, 1, 2]]);
+
+    let restrict = |nodes: &[usize]| {
+        vf2::subgraph_isomorphisms(&query, &data)
+            .restrict_query(nodes)
+            .err()
+    };
+    assert_eq!(
+        restrict(&[0, 5]),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(5))
+    );
+    assert_eq!(
+        restrict(&[0, 1, 0]),
+        Some(vf2::Vf2Error::DuplicateQueryNode(0))
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .anchor(4, 0)
+            .unwrap()
+            .restrict_query(&[3, 1, 2])
+            .err(),
+        Some(vf2::Vf2Error::QueryNodeOutOfRange(4))
+    );
+}
+
+/// Tests allowing some node label mismatches in each isomorphism.
+#[test]
+fn node_mismatch_budget() {
+    let mut query = DiGraph::<char, ()>::new();
+    let a = query.add_node('a');
+    let b = query.add_node('b');
+    query.add_edge(a, b, ());
+    let mut data = DiGraph::<char, ()>::new();
+    let a = data.add_node('a');
+    let c = data.add_node('c');
+    let d = data.add_node('d');
+    data.add_edge(a, c, ());
+    data.add_edge(c, d, ());
+
+    let matches = |k| {
+        vf2::subgraph_isomorphisms(&query, &data)
+            .default_eq()
+            .node_mismatch_budget(k)
+            .vec()
+    };
+    assert!(matches(0).is_empty());
+    assert_eq!(matches(1), vec![vec![0, 1]]);
+    assert_eq!(matches(2), vec![vec![0, 1], vec![1, 2]]);
+}
+
+/// Tests that atom classes find the same isomorphisms as comparing
+/// the classes in the node equality function, with fewer calls.
+#[test]
+fn atom_classes() {
+    let (query, data) = small_graphs::<Undirected>();
+    let query_classes = vec![0, 0, 1, 2, 2];
+    let data_classes = vec![0, 0, 0, 1, 2, 2, 2, 2];
+    let labeled_query = query.map(|node, _| query_classes[node.index()], |_, _| ());
+    let labeled_data = data.map(|node, _| data_classes[node.index()], |_, _| ());
+
+    let calls = Cell::new(0);
+    let node_eq = |left: &usize, right: &usize| {
+        calls.set(calls.get() + 1);
+        left == right
+    };
+    let compared = vf2::subgraph_isomorphisms(&labeled_query, &labeled_data)
+        .node_eq(node_eq)
+        .vec();
+    let compared_calls = calls.replace(0);
+    let classed = vf2::subgraph_isomorphisms(&labeled_query, &labeled_data)
+        .node_eq(node_eq)
+        .atom_classes(query_classes.clone(), data_classes.clone())
+        .unwrap()
+        .vec();
+
+    assert!(!classed.is_empty());
+    assert_eq!(classed, compared);
+    assert!(calls.get() < compared_calls);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .atom_classes(query_classes, data_classes)
+            .unwrap()
+            .vec(),
+        compared
+    );
+}
+
+/// Tests that atom classes are enforced alongside candidates,
+/// wildcards and a node mismatch budget.
+#[test]
+fn atom_classes_hard_constraint() {
+    let mut query = UnGraph::<(), ()>::new_undirected();
+    query.add_node(());
+    let data = UnGraph::<(), ()>::from_edges([(0, 1)]);
+    let classed = || {
+        vf2::subgraph_isomorphisms(&query, &data)
+            .atom_classes(vec![0], vec![1, 0])
+            .unwrap()
+    };
+
+    assert_eq!(classed().vec(), vec![vec![1]]);
+    assert_eq!(classed().node_mismatch_budget(1).vec(), vec![vec![1]]);
+    assert_eq!(
+        classed().wildcard_query_nodes(&[0]).unwrap().vec(),
+        vec![vec![1]]
+    );
+    assert!(classed().candidates(0, vec![0]).unwrap().vec().is_empty());
+    assert!(classed()
+        .node_mismatch_budget(1)
+        .candidates(0, vec![0])
+        .unwrap()
+        .vec()
+        .is_empty());
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .atom_classes(vec![0, 1], vec![1, 0])
+            .err(),
+        Some(vf2::Vf2Error::NodeClassesMismatch)
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .atom_classes(vec![0], vec![1])
+            .err(),
+        Some(vf2::Vf2Error::NodeClassesMismatch)
+    );
+}
+
+/// Tests that identical query components are mapped in only one order.
+#[test]
+fn dedup_components() {
+    let query = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)]);
+    let data = UnGraph::<(), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (2, 0),
+        (3, 4),
+        (4, 5),
+        (5, 3),
+        (6, 7),
+        (7, 8),
+        (8, 6),
+    ]);
+
+    let all = vf2::subgraph_isomorphisms(&query, &data).count();
+    let deduped = vf2::subgraph_isomorphisms(&query, &data)
+        .dedup_components()
+        .vec();
+    assert_eq!(all, 216);
+    assert_eq!(deduped.len(), all / 2);
+    assert!(deduped
+        .iter()
+        .all(|isomorphism| isomorphism[0] < isomorphism[3]));
+
+    // Three identical components are mapped in one of 3! orders.
+    let three = UnGraph::<(), ()>::from_edges([(0, 1), (2, 3), (4, 5)]);
+    let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6)]);
+    let all = vf2::subgraph_isomorphisms(&three, &path).count();
+    assert!(all > 0);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&three, &path)
+            .dedup_components()
+            .count()
+            * 6,
+        all
+    );
+
+    // Components that differ in labels are not swapped.
+    let mut labeled = Graph::<Color, (), Undirected>::default();
+    let colors = [Color::Black, Color::White];
+    for color in colors {
+        let nodes = [0; 3].map(|_| labeled.add_node(color));
+        for i in 0..3 {
+            labeled.add_edge(nodes[i], nodes[(i + 1) % 3], ());
+        }
+    }
+    let mut labeled_data = Graph::<Color, (), Undirected>::default();
+    for color in [Color::Black, Color::White, Color::Black, Color::White] {
+        let nodes = [0; 3].map(|_| labeled_data.add_node(color));
+        for i in 0..3 {
+            labeled_data.add_edge(nodes[i], nodes[(i + 1) % 3], ());
+        }
+    }
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&labeled, &labeled_data)
+            .default_eq()
+            .dedup_components()
+            .count(),
+        vf2::subgraph_isomorphisms(&labeled, &labeled_data)
+            .default_eq()
+            .count()
+    );
+}
+
+/// Tests matching interval labels by containment.
+#[test]
+fn node_eq_contains() {
+    let mut query = DiGraph::<IntervalLabel<u32>, ()>::new();
+    let wide = query.add_node(IntervalLabel::new(0, 100));
+    let narrow = query.add_node(IntervalLabel::new(10, 20));
+    query.add_edge(wide, narrow, ());
+
+    let mut data = DiGraph::<IntervalLabel<u32>, ()>::new();
+    let inside = data.add_node(IntervalLabel::new(5, 50));
+    let within = data.add_node(IntervalLabel::new(12, 18));
+    let overlapping = data.add_node(IntervalLabel::new(15, 25));
+    let outside = data.add_node(IntervalLabel::new(90, 110));
+    data.add_edge(inside, within, ());
+    data.add_edge(inside, overlapping, ());
+    data.add_edge(outside, within, ());
+
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .node_eq_contains()
+            .vec(),
+        vec![vec![inside.index(), within.index()]]
+    );
+}
+
+/// Tests that a step budget stops the search at the same point every time.
+#[test]
+fn node_budget() {
+    let (query, data) = small_graphs::<Undirected>();
+    let all = vf2::subgraph_isomorphisms(&query, &data).count();
+
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data)
+        .node_budget(20)
+        .iter();
+    let found = iter.by_ref().count();
+    assert!(found < all);
+    assert!(iter.budget_exhausted());
+
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data)
+        .node_budget(20)
+        .iter();
+    assert_eq!(iter.by_ref().count(), found);
+
+    let mut iter = vf2::subgraph_isomorphisms(&query, &data)
+        .node_budget(usize::MAX)
+        .iter();
+    assert_eq!(iter.by_ref().count(), all);
+    assert!(!iter.budget_exhausted());
+}
+
+/// Tests telling a search with no isomorphism from one cut short.
+#[test]
+fn try_first() {
+    let (query, data) = small_graphs::<Undirected>();
+
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data).try_first(),
+        Ok(vf2::subgraph_isomorphisms(&query, &data).first())
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&data, &query).try_first(),
+        Ok(None)
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .node_budget(2)
+            .try_first(),
+        Err(vf2::SearchIncomplete)
+    );
+    assert_eq!(
+        vf2::subgraph_isomorphisms(&query, &data)
+            .node_eq_abort(|_, _| NodeMatch::Abort)
+            .try_first(),
+        Err(vf2::SearchIncomplete)
+    );
+}
+
+/// Tests searching petgraph graphs through their data map and visit traits.
+#[test]
+fn data_map_graph() {
+    fn check<D: EdgeType>() {
+        let (query, data) = small_labeled_graphs::<D>();
+        let stable_query = StableGraph::from(query.clone());
+        let stable_data = StableGraph::from(data.clone());
+
+        let expected = vf2::subgraph_isomorphisms(&query, &data).default_eq().vec();
+        let graph = vf2::subgraph_isomorphisms(
+            &DataMapGraph::new(&query).unwrap(),
+            &DataMapGraph::new(&data).unwrap(),
+        )
+        .default_eq()
+        .vec();
+        let stable = vf2::subgraph_isomorphisms(
+            &DataMapGraph::new(&stable_query).unwrap(),
+            &DataMapGraph::new(&stable_data).unwrap(),
+        )
+        .default_eq()
+        .vec();
+        assert!(!expected.is_empty());
+        assert_eq!(graph, expected);
+        assert_eq!(stable, expected);
+    }
+    check::<Directed>();
+    check::<Undirected>();
+
+    let double = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1)]);
+    let double_last = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 2)]);
+    assert_eq!(
+        vf2::subgraph_isomorphisms(
+            &DataMapGraph::new(&double).unwrap(),
+            &DataMapGraph::new(&double_last).unwrap()
+        )
+        .vec(),
+        vec![vec![1, 2], vec![2, 1]]
+    );
+}
+
+/// Tests that a graph with removed nodes is rejected.
+#[test]
+fn data_map_graph_removed_node() {
+    let mut graph = StableGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    graph.remove_node(petgraph::stable_graph::NodeIndex::new(0));
+
+    assert_eq!(
+        DataMapGraph::new(&graph).err(),
+        Some(vf2::Vf2Error::NonContiguousNodes)
+    );
 }
 
 /// Returns small query and data graphs used across tests.
@@ -394,8 +3326,116 @@ fn small_labeled_graphs<D: EdgeType>() -> (Graph<Color, Color, D>, Graph<Color,
 }
 
 /// A color enum used as node and edge labels.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 enum Color {
     White,
     Black,
 }
+
+/// An edgeless graph that reports a node count
+/// without allocating anything per node.
+#[derive(Debug)]
+struct HugeGraph(usize);
+
+impl vf2::Graph for HugeGraph {
+    type NodeLabel = ();
+    type EdgeLabel = ();
+
+    fn is_directed(&self) -> bool {
+        true
+    }
+
+    fn node_count(&self) -> usize {
+        self.0
+    }
+
+    fn node_label(&self, node: usize) -> Option<&()> {
+        (node < self.0).then_some(&())
+    }
+
+    fn neighbors(&self, _node: usize, _direction: vf2::Direction) -> impl Iterator<Item = usize> {
+        std::iter::empty()
+    }
+
+    fn contains_edge(&self, _source: usize, _target: usize) -> bool {
+        false
+    }
+
+    fn edge_label(&self, _source: usize, _target: usize) -> Option<&()> {
+        None
+    }
+}
+
+/// A minimal adjacency list graph used to test custom [`vf2::Graph`] implementations.
+#[derive(Debug)]
+struct AdjListGraph {
+    directed: bool,
+    labels: Vec<()>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    /// Edge labels by orientation. Unlabeled edges are labeled `'-'`.
+    edge_labels: HashMap<(usize, usize), char>,
+}
+
+impl AdjListGraph {
+    fn new(directed: bool, node_count: usize, edges: &[(usize, usize)]) -> Self {
+        let mut outgoing = vec![Vec::new(); node_count];
+        let mut incoming = vec![Vec::new(); node_count];
+        for &(source, target) in edges {
+            outgoing[source].push(target);
+            incoming[target].push(source);
+            if !directed {
+                outgoing[target].push(source);
+                incoming[source].push(target);
+            }
+        }
+        Self {
+            directed,
+            labels: vec![(); node_count],
+            outgoing,
+            incoming,
+            edge_labels: HashMap::new(),
+        }
+    }
+
+    /// Labels the edge read from `source` to `target`.
+    fn with_edge_label(mut self, source: usize, target: usize, label: char) -> Self {
+        self.edge_labels.insert((source, target), label);
+        self
+    }
+}
+
+impl vf2::Graph for AdjListGraph {
+    type NodeLabel = ();
+    type EdgeLabel = char;
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn node_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn node_label(&self, node: usize) -> Option<&()> {
+        self.labels.get(node)
+    }
+
+    fn neighbors(&self, node: usize, direction: vf2::Direction) -> impl Iterator<Item = usize> {
+        match direction {
+            vf2::Direction::Outgoing => self.outgoing[node].iter().copied(),
+            vf2::Direction::Incoming => self.incoming[node].iter().copied(),
+        }
+    }
+
+    fn contains_edge(&self, source: usize, target: usize) -> bool {
+        self.outgoing[source].contains(&target)
+    }
+
+    fn edge_label(&self, source: usize, target: usize) -> Option<&char> {
+        if !self.contains_edge(source, target) {
+            return None;
+        }
+        Some(self.edge_labels.get(&(source, target)).unwrap_or(&'-'))
+    }
+}